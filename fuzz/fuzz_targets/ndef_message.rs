@@ -0,0 +1,25 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_nfc_card_reader::ndef::{parse_ndef_message, parse_ndef_message_strict};
+use rust_nfc_card_reader::tlv::find_ndef_tlv;
+
+fuzz_target!(|data: &[u8]| {
+    // Raw message bytes, and the same bytes read as a tag's TLV data area.
+    for message in [Some(data), find_ndef_tlv(data).map(|tlv| tlv.value(data))]
+        .into_iter()
+        .flatten()
+    {
+        let lenient = parse_ndef_message(message);
+        match parse_ndef_message_strict(message) {
+            Ok(records) => {
+                assert_eq!(lenient.ok(), Some(records.clone()));
+                for record in &records {
+                    let _ = record.summary();
+                }
+            }
+            // A message the validator accepts must also parse.
+            Err(violations) => assert!(!violations.is_empty()),
+        }
+    }
+});
@@ -0,0 +1,87 @@
+/// Builds a READ BINARY command (PC/SC Part 3) for `len` bytes at `block`.
+pub fn read_binary(block: u8, len: u8) -> [u8; 5] {
+    [
+        0xFF,  // Class
+        0xB0,  // Instruction (Read Binary)
+        0x00,  // P1 (Memory address high byte)
+        block, // P2 (Memory address low byte, page or block number)
+        len,   // Le (Expected data length)
+    ]
+}
+
+/// Builds an UPDATE BINARY command (PC/SC Part 3) writing `data` to `block`.
+pub fn update_binary(block: u8, data: &[u8]) -> Vec<u8> {
+    let mut apdu = Vec::with_capacity(5 + data.len());
+    apdu.push(0xFF); // Class
+    apdu.push(0xD6); // Instruction (Update Binary)
+    apdu.push(0x00); // P1
+    apdu.push(block); // P2
+    apdu.push(data.len() as u8); // Lc
+    apdu.extend_from_slice(data);
+    apdu
+}
+
+/// Builds a GET DATA command returning the card UID.
+pub fn get_uid() -> [u8; 5] {
+    [0xFF, 0xCA, 0x00, 0x00, 0x00]
+}
+
+/// Builds a LOAD KEYS command storing a MIFARE key in the reader's volatile `slot`.
+pub fn load_key(slot: u8, key: &[u8; 6]) -> Vec<u8> {
+    let mut apdu = vec![0xFF, 0x82, 0x00, slot, 0x06];
+    apdu.extend_from_slice(key);
+    apdu
+}
+
+/// Builds a GENERAL AUTHENTICATE command for `block` using the key in `slot`.
+///
+/// `key_type` is 0x60 for key A and 0x61 for key B.
+pub fn general_authenticate(block: u8, key_type: u8, slot: u8) -> [u8; 10] {
+    [
+        0xFF, 0x86, 0x00, 0x00, 0x05, // Header
+        0x01, // Version
+        0x00, block, key_type, slot,
+    ]
+}
+
+/// Wraps a native tag command in the ACR122U/PN532 InCommunicateThru
+/// pseudo-APDU (`FF 00 00 00 Lc D4 42 ...`).
+pub fn in_communicate_thru(command: &[u8]) -> Vec<u8> {
+    let mut apdu = vec![
+        0xFF,
+        0x00,
+        0x00,
+        0x00,
+        (command.len() + 2) as u8,
+        0xD4,
+        0x42,
+    ];
+    apdu.extend_from_slice(command);
+    apdu
+}
+
+/// Returns `true` when the response ends with the 90 00 status word.
+pub fn is_successful_response(response: &[u8]) -> bool {
+    response.len() >= 2
+        && response[response.len() - 2] == 0x90
+        && response[response.len() - 1] == 0x00
+}
+
+/// Returns the response without its trailing status word.
+pub fn response_data(response: &[u8]) -> &[u8] {
+    &response[..response.len().saturating_sub(2)]
+}
+
+/// Logs `response` and its status word at debug level.
+pub fn log_response(context: &str, response: &[u8]) {
+    match response {
+        [.., sw1, sw2] => log::debug!(
+            "{}: {:02X?}, status words {:02X} {:02X}",
+            context,
+            response,
+            sw1,
+            sw2
+        ),
+        _ => log::debug!("{}: {:02X?}", context, response),
+    }
+}
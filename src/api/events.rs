@@ -0,0 +1,170 @@
+use super::card_json;
+use super::readers::{ReaderStatus, Readers};
+use crate::chip::atr_card_type;
+use crate::hex;
+use log::warn;
+use serde_json::{json, Value};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    /// A reader was plugged in.
+    Attached,
+    /// A reader was unplugged.
+    Detached,
+    Inserted,
+    Removed,
+}
+
+impl EventKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            EventKind::Attached => "attached",
+            EventKind::Detached => "detached",
+            EventKind::Inserted => "inserted",
+            EventKind::Removed => "removed",
+        }
+    }
+}
+
+/// A reader coming or going, or a card entering or leaving its field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub kind: EventKind,
+    pub reader: String,
+    /// ATR of the inserted card, empty for other events.
+    pub atr: Vec<u8>,
+    /// What [`card_json`] read from an inserted card, or `{"error": ...}`
+    /// when it could not be read. Only filled in while someone listens.
+    pub card: Option<Value>,
+}
+
+impl Event {
+    fn new(kind: EventKind, reader: &str) -> Event {
+        Event {
+            kind,
+            reader: reader.to_string(),
+            atr: Vec::new(),
+            card: None,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({
+            "event": self.kind.name(),
+            "reader": self.reader,
+        });
+        if self.kind == EventKind::Inserted {
+            value["atr"] = hex::encode(&self.atr).into();
+            value["card_type"] = atr_card_type(&self.atr).into();
+        }
+        if let Some(card) = &self.card {
+            value["card"] = card.clone();
+        }
+        value
+    }
+
+    /// `true` when `readers` is `None` (everything) or names this event's
+    /// reader.
+    pub fn is_for(&self, readers: Option<&[String]>) -> bool {
+        readers.is_none_or(|readers| readers.contains(&self.reader))
+    }
+}
+
+/// Events between two status snapshots, detaches and removals first. A card
+/// swapped for another between snapshots shows up as a removal and an
+/// insertion.
+pub fn status_events(before: &[ReaderStatus], after: &[ReaderStatus]) -> Vec<Event> {
+    let find = |statuses: &[ReaderStatus], name: &str| {
+        statuses.iter().find(|status| status.name == name).cloned()
+    };
+    let card = |status: &Option<ReaderStatus>| {
+        status
+            .as_ref()
+            .filter(|status| status.card_present)
+            .map(|status| status.atr.clone())
+    };
+
+    let mut events = Vec::new();
+    for status in before {
+        let now = find(after, &status.name);
+        if status.card_present && card(&now) != Some(status.atr.clone()) {
+            events.push(Event::new(EventKind::Removed, &status.name));
+        }
+        if now.is_none() {
+            events.push(Event::new(EventKind::Detached, &status.name));
+        }
+    }
+    for status in after {
+        let then = find(before, &status.name);
+        if then.is_none() {
+            events.push(Event::new(EventKind::Attached, &status.name));
+        }
+        if status.card_present && card(&then) != Some(status.atr.clone()) {
+            let mut event = Event::new(EventKind::Inserted, &status.name);
+            event.atr = status.atr.clone();
+            events.push(event);
+        }
+    }
+    events
+}
+
+/// Fans events out to every open event stream.
+#[derive(Default)]
+pub struct EventHub {
+    subscribers: Mutex<Vec<Sender<Event>>>,
+}
+
+impl EventHub {
+    pub fn subscribe(&self) -> Receiver<Event> {
+        let (sender, receiver) = channel();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(sender);
+        }
+        receiver
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        self.subscribers
+            .lock()
+            .is_ok_and(|subscribers| !subscribers.is_empty())
+    }
+
+    /// Sends `event` to every subscriber, forgetting the ones that went away.
+    pub fn publish(&self, event: &Event) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        }
+    }
+
+    /// Polls `readers` every `interval` and publishes what changed since the
+    /// first poll. Inserted cards are read so events carry their UID and
+    /// NDEF message, unless nobody is listening. Never returns; status
+    /// errors are reported and the poll goes on.
+    pub fn watch(&self, readers: &dyn Readers, interval: Duration) {
+        let mut previous = readers.status().unwrap_or_default();
+        loop {
+            std::thread::sleep(interval);
+            let current = match readers.status() {
+                Ok(current) => current,
+                Err(err) => {
+                    warn!("Reading the reader status failed: {}", err);
+                    continue;
+                }
+            };
+            for mut event in status_events(&previous, &current) {
+                if event.kind == EventKind::Inserted && self.has_subscribers() {
+                    event.card = Some(
+                        readers
+                            .with_card(&event.reader, &mut card_json)
+                            .unwrap_or_else(|err| json!({ "error": err.to_string() })),
+                    );
+                }
+                self.publish(&event);
+            }
+            previous = current;
+        }
+    }
+}
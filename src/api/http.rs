@@ -0,0 +1,177 @@
+use crate::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+/// Largest request body accepted; NDEF messages and APDUs are far smaller.
+pub const MAX_BODY: usize = 64 * 1024;
+
+/// Error for a `Content-Length` above [`MAX_BODY`].
+#[derive(Debug)]
+pub struct BodyTooLarge(pub usize);
+
+impl fmt::Display for BodyTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Request body of {} bytes is too large", self.0)
+    }
+}
+
+impl std::error::Error for BodyTooLarge {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    /// Path without the query string, still percent-encoded.
+    pub path: String,
+    /// Query string without the `?`, still percent-encoded.
+    pub query: String,
+    /// Header names are lower-cased.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Decoded value of `name` in the query string.
+    pub fn query_param(&self, name: &str) -> Option<String> {
+        self.query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .and_then(|(_, value)| percent_decode(value).ok())
+    }
+
+    /// Path segments with percent-escapes decoded, e.g.
+    /// `/readers/ACS%20ACR122U/card` gives `["readers", "ACS ACR122U", "card"]`.
+    pub fn segments(&self) -> Result<Vec<String>, Error> {
+        self.path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(percent_decode)
+            .collect()
+    }
+}
+
+/// Reads one HTTP/1.1 request. Only `Content-Length` bodies are supported;
+/// a longer one than [`MAX_BODY`] fails with an I/O error wrapping
+/// [`BodyTooLarge`].
+pub fn read_request<R: BufRead>(reader: &mut R) -> Result<Request, Error> {
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line)? == 0 {
+        return Err(Error::Invalid(
+            "Connection closed before the request line".into(),
+        ));
+    }
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") => {
+            (method, target)
+        }
+        _ => {
+            return Err(Error::Invalid(format!(
+                "Invalid request line {:?}",
+                request_line.trim_end()
+            )))
+        }
+    };
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(Error::Invalid(
+                "Connection closed inside the headers".into(),
+            ));
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| Error::Invalid(format!("Invalid header {:?}", line)))?;
+        headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: query.to_string(),
+        headers,
+        body: Vec::new(),
+    };
+    if let Some(length) = request.header("content-length") {
+        let length: usize = length
+            .parse()
+            .map_err(|_| Error::Invalid(format!("Invalid Content-Length {:?}", length)))?;
+        if length > MAX_BODY {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, BodyTooLarge(length)).into());
+        }
+        request.body = vec![0; length];
+        reader.read_exact(&mut request.body)?;
+    }
+    Ok(request)
+}
+
+/// Writes a complete response and asks the client to close the connection.
+pub fn write_response<W: Write>(
+    stream: &mut W,
+    status: u16,
+    headers: &[(&str, &str)],
+    body: &str,
+) -> io::Result<()> {
+    write!(stream, "HTTP/1.1 {} {}\r\n", status, reason(status))?;
+    for (name, value) in headers {
+        write!(stream, "{}: {}\r\n", name, value)?;
+    }
+    write!(
+        stream,
+        "Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+pub fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Decodes `%XX` escapes; `+` is left alone as it only means a space in forms.
+pub fn percent_decode(text: &str) -> Result<String, Error> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let [byte, tail @ ..] = rest {
+        if *byte == b'%' {
+            let escape = tail
+                .get(..2)
+                .and_then(|digits| std::str::from_utf8(digits).ok())
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| Error::Invalid(format!("Invalid percent-escape in {:?}", text)))?;
+            bytes.push(escape);
+            rest = &tail[2..];
+        } else {
+            bytes.push(*byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes)
+        .map_err(|_| Error::Invalid(format!("Percent-escapes in {:?} are not UTF-8", text)))
+}
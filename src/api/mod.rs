@@ -0,0 +1,437 @@
+mod events;
+mod http;
+mod readers;
+mod websocket;
+
+pub use events::{status_events, Event, EventHub, EventKind};
+pub use http::{percent_decode, read_request, write_response, BodyTooLarge, Request, MAX_BODY};
+pub use readers::{CardOperation, PcscReaders, ReaderStatus, Readers, SimulatedReaders};
+
+use crate::chip::detect_chip;
+use crate::dump::{ndef_message, read_dump};
+use crate::error::Error;
+use crate::hex;
+use crate::ndef::{encode_ndef_message, parse_ndef_message_strict, NdefRecord, Violation};
+use crate::ntag::write_ndef_message;
+use crate::transport::Transport;
+use log::warn;
+use serde_json::{json, Value};
+use std::io::{BufReader, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often the event watcher polls the readers by default.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Idle time after which an event stream gets a keep-alive comment, so
+/// closed clients are noticed.
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Localhost REST API over a set of readers:
+///
+/// - `GET /readers`: names, card presence and ATRs
+/// - `GET /readers/{name}/card`: UID, ATR, chip and NDEF message
+/// - `POST /readers/{name}/ndef`: writes `{"message": "<hex>"}` or
+///   `{"records": [{"uri": ...}, {"text": ..., "language": "en"},
+///   {"mime": ..., "payload": "<hex>"}, {"json": ...}, {"android_app": ...}]}`
+/// - `POST /readers/{name}/apdu`: sends `{"apdu": "<hex>"}` to the card
+/// - `GET /events`: Server-Sent Events for readers and cards coming and going
+/// - `GET /ws`: the same events over a WebSocket, see
+///   [`websocket::stream_events`] for changing the subscription
+///
+/// Both event streams take `?readers=a,b` to follow only some readers.
+/// Inserted cards are read, so their events carry the UID, card type and
+/// NDEF message. Reader names are percent-encoded in paths.
+///
+/// Web pages must not be able to drive the reader: requests are refused
+/// unless `Host` names the loopback address the server is bound to (against
+/// DNS rebinding), any `Origin` is `allow_origin`, and POST bodies are sent
+/// as `application/json`, which browsers never send cross-origin without a
+/// preflight.
+pub struct ApiServer {
+    readers: Arc<dyn Readers>,
+    events: Arc<EventHub>,
+    /// Set by [`ApiServer::serve`] from the listener.
+    address: Option<SocketAddr>,
+    pub poll_interval: Duration,
+    /// Value of `Access-Control-Allow-Origin` for browser front-ends served
+    /// from another origin. Requests from any other origin are refused, and
+    /// all cross-origin requests are when unset.
+    pub allow_origin: Option<String>,
+}
+
+/// An error response: HTTP status and message.
+#[derive(Debug)]
+struct ApiError(u16, String);
+
+impl From<Error> for ApiError {
+    fn from(err: Error) -> ApiError {
+        let status = match err {
+            Error::NoReader => 404,
+            Error::NoCard | Error::CardRemoved => 409,
+            _ => 500,
+        };
+        ApiError(status, err.to_string())
+    }
+}
+
+fn bad_request(err: impl std::fmt::Display) -> ApiError {
+    ApiError(400, err.to_string())
+}
+
+impl ApiServer {
+    pub fn new(readers: Arc<dyn Readers>) -> ApiServer {
+        ApiServer {
+            readers,
+            events: Arc::new(EventHub::default()),
+            address: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            allow_origin: None,
+        }
+    }
+
+    /// Serves requests from `listener` until it fails, one thread per
+    /// connection, with a background thread watching for card events. The
+    /// listener must be bound to a loopback address.
+    pub fn serve(mut self, listener: TcpListener) -> Result<(), Error> {
+        let address = listener.local_addr()?;
+        if !address.ip().is_loopback() {
+            return Err(Error::Invalid(format!(
+                "The reader API only listens on loopback addresses, not {}",
+                address
+            )));
+        }
+        self.address = Some(address);
+        let server = Arc::new(self);
+        let watcher = Arc::clone(&server);
+        std::thread::spawn(move || {
+            watcher
+                .events
+                .watch(&*watcher.readers, watcher.poll_interval)
+        });
+
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let server = Arc::clone(&server);
+            std::thread::spawn(move || {
+                if let Err(err) = server.handle_connection(stream) {
+                    warn!("API connection failed: {}", err);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn handle_connection(&self, stream: TcpStream) -> Result<(), Error> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut stream = stream;
+        let request = match read_request(&mut reader) {
+            Ok(request) => request,
+            Err(err) => {
+                let status = match &err {
+                    Error::Io(err) if err.get_ref().is_some_and(|err| err.is::<BodyTooLarge>()) => {
+                        413
+                    }
+                    _ => 400,
+                };
+                return self.respond(&mut stream, status, &json!({ "error": err.to_string() }));
+            }
+        };
+
+        if let Err(ApiError(status, message)) = self.check_access(&request) {
+            return self.respond(&mut stream, status, &json!({ "error": message }));
+        }
+        if request.method == "OPTIONS" {
+            return Ok(write_response(
+                &mut stream,
+                204,
+                &self.headers(&[
+                    ("Access-Control-Allow-Methods", "GET, POST"),
+                    ("Access-Control-Allow-Headers", "Content-Type"),
+                ]),
+                "",
+            )?);
+        }
+        if request.method == "GET" && request.path == "/ws" {
+            return self.upgrade(reader, stream, &request);
+        }
+        if request.method == "GET" && request.path == "/events" {
+            return self.stream_events(&mut stream, subscription(&request));
+        }
+        match self.route(&request) {
+            Ok(body) => self.respond(&mut stream, 200, &body),
+            Err(ApiError(status, message)) => {
+                self.respond(&mut stream, status, &json!({ "error": message }))
+            }
+        }
+    }
+
+    /// Refuses requests for another host name, from an origin other than
+    /// `allow_origin`, and POSTs whose body is not declared as JSON.
+    fn check_access(&self, request: &Request) -> Result<(), ApiError> {
+        let host = request
+            .header("host")
+            .ok_or_else(|| bad_request("Missing Host header"))?;
+        if !self
+            .address
+            .is_some_and(|address| is_local_host(host, address))
+        {
+            return Err(ApiError(403, format!("Host {} is not allowed", host)));
+        }
+        if let Some(origin) = request.header("origin") {
+            let allowed = self
+                .allow_origin
+                .as_deref()
+                .is_some_and(|allowed| allowed == "*" || allowed == origin);
+            if !allowed {
+                return Err(ApiError(403, format!("Origin {} is not allowed", origin)));
+            }
+        }
+        if request.method == "POST" {
+            let media_type = request
+                .header("content-type")
+                .and_then(|value| value.split(';').next())
+                .map(str::trim);
+            if !media_type
+                .is_some_and(|media_type| media_type.eq_ignore_ascii_case("application/json"))
+            {
+                return Err(ApiError(415, "POST bodies must be application/json".into()));
+            }
+        }
+        Ok(())
+    }
+
+    fn route(&self, request: &Request) -> Result<Value, ApiError> {
+        let segments = request.segments().map_err(bad_request)?;
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["readers"]) => Ok(self
+                .readers
+                .status()?
+                .iter()
+                .map(|status| {
+                    json!({
+                        "name": status.name,
+                        "card_present": status.card_present,
+                        "atr": hex::encode(&status.atr),
+                    })
+                })
+                .collect()),
+            ("GET", ["readers", reader, "card"]) => {
+                Ok(self.readers.with_card(reader, &mut card_json)?)
+            }
+            ("POST", ["readers", reader, "ndef"]) => {
+                let message = ndef_from_json(&parse_body(request)?).map_err(bad_request)?;
+                Ok(self.readers.with_card(reader, &mut |tx, atr| {
+                    let chip = detect_chip(tx, atr)?;
+                    write_ndef_message(tx, chip, &message)?;
+                    Ok(json!({ "chip": chip.id(), "written": message.len() }))
+                })?)
+            }
+            ("POST", ["readers", reader, "apdu"]) => {
+                let body = parse_body(request)?;
+                let apdu = body["apdu"]
+                    .as_str()
+                    .ok_or_else(|| bad_request("Body needs an \"apdu\" hex string"))?;
+                let apdu = hex::decode(apdu).map_err(bad_request)?;
+                Ok(self
+                    .readers
+                    .with_card(reader, &mut |tx, _| transmit_json(tx, &apdu))?)
+            }
+            (_, ["readers"] | ["readers", _, "card" | "ndef" | "apdu"] | ["events" | "ws"]) => Err(
+                ApiError(405, format!("{} is not allowed here", request.method)),
+            ),
+            _ => Err(ApiError(404, format!("No such endpoint: {}", request.path))),
+        }
+    }
+
+    /// Sends every event as `event: <kind>` with the JSON in `data`, until
+    /// the client goes away.
+    fn stream_events(
+        &self,
+        stream: &mut TcpStream,
+        readers: Option<Vec<String>>,
+    ) -> Result<(), Error> {
+        let events = self.events.subscribe();
+        let mut head = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+                        Cache-Control: no-cache\r\n"
+            .to_string();
+        for (name, value) in self.headers(&[]) {
+            head += &format!("{}: {}\r\n", name, value);
+        }
+        write!(stream, "{}\r\n", head)?;
+        stream.flush()?;
+
+        loop {
+            match events.recv_timeout(KEEP_ALIVE) {
+                Ok(event) if event.is_for(readers.as_deref()) => write!(
+                    stream,
+                    "event: {}\ndata: {}\n\n",
+                    event.kind.name(),
+                    event.to_json()
+                )?,
+                Ok(_) => continue,
+                Err(RecvTimeoutError::Timeout) => write!(stream, ": keep-alive\n\n")?,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+            stream.flush()?;
+        }
+    }
+
+    /// Completes the WebSocket handshake and streams events. Browsers can
+    /// open WebSockets to any host, so this relies on the origin check in
+    /// [`ApiServer::check_access`].
+    fn upgrade(
+        &self,
+        reader: BufReader<TcpStream>,
+        mut stream: TcpStream,
+        request: &Request,
+    ) -> Result<(), Error> {
+        let key = match websocket::upgrade_key(request) {
+            Ok(key) => key,
+            Err(err) => return self.respond(&mut stream, 400, &json!({ "error": err })),
+        };
+
+        let events = self.events.subscribe();
+        write!(
+            stream,
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+             Connection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+            websocket::accept_key(key)
+        )?;
+        stream.flush()?;
+        websocket::stream_events(reader, stream, events, subscription(request), KEEP_ALIVE)
+    }
+
+    fn respond(&self, stream: &mut TcpStream, status: u16, body: &Value) -> Result<(), Error> {
+        let headers = self.headers(&[("Content-Type", "application/json")]);
+        Ok(write_response(stream, status, &headers, &body.to_string())?)
+    }
+
+    fn headers<'a>(&'a self, headers: &[(&'a str, &'a str)]) -> Vec<(&'a str, &'a str)> {
+        let mut headers = headers.to_vec();
+        if let Some(origin) = &self.allow_origin {
+            headers.push(("Access-Control-Allow-Origin", origin));
+        }
+        headers
+    }
+}
+
+/// Whether the `Host` header names the loopback address the server listens
+/// on, as `localhost`, `127.0.0.1`, `[::1]` or the bound IP, with the bound
+/// port or none.
+fn is_local_host(host: &str, address: SocketAddr) -> bool {
+    let (name, port) = match host.rsplit_once(':') {
+        Some((name, port)) if !port.contains(']') => (name, Some(port)),
+        _ => (host, None),
+    };
+    let port_matches = port.is_none_or(|port| port.parse() == Ok(address.port()));
+    let name = name.trim_start_matches('[').trim_end_matches(']');
+    let name_matches = name.eq_ignore_ascii_case("localhost")
+        || name.parse::<IpAddr>().is_ok_and(|ip| ip == address.ip());
+    port_matches && name_matches
+}
+
+/// Readers named in `?readers=a,b`, or `None` for all of them.
+fn subscription(request: &Request) -> Option<Vec<String>> {
+    request
+        .query_param("readers")
+        .map(|readers| readers.split(',').map(str::to_string).collect())
+}
+
+fn parse_body(request: &Request) -> Result<Value, ApiError> {
+    serde_json::from_slice(&request.body)
+        .map_err(|err| bad_request(format!("Invalid JSON body: {}", err)))
+}
+
+/// UID, ATR, chip and NDEF message of the card, as returned by
+/// `GET /readers/{name}/card`.
+pub fn card_json(tx: &dyn Transport, atr: &[u8]) -> Result<Value, Error> {
+    let dump = read_dump(tx, atr)?;
+    let ndef = ndef_message(&dump).map(|message| {
+        let mut ndef = json!({ "message": hex::encode(&message) });
+        match parse_ndef_message_strict(&message) {
+            Ok(records) => ndef["records"] = records.iter().map(record_json).collect(),
+            Err(violations) => {
+                ndef["violations"] = violations.iter().map(Violation::to_string).collect()
+            }
+        }
+        ndef
+    });
+    Ok(json!({
+        "uid": hex::encode(&dump.uid),
+        "atr": hex::encode(atr),
+        "chip": dump.chip.id(),
+        "ndef": ndef,
+    }))
+}
+
+fn record_json(record: &NdefRecord) -> Value {
+    json!({
+        "tnf": record.tnf,
+        "type": String::from_utf8_lossy(&record.record_type),
+        "id": String::from_utf8_lossy(&record.id),
+        "payload": hex::encode(&record.payload),
+        "summary": record.summary(),
+    })
+}
+
+/// The NDEF message described by a `POST /readers/{name}/ndef` body.
+pub fn ndef_from_json(body: &Value) -> Result<Vec<u8>, Error> {
+    if let Some(message) = body["message"].as_str() {
+        let message = hex::decode(message)?;
+        if let Err(violations) = parse_ndef_message_strict(&message) {
+            return Err(Error::Ndef(format!(
+                "Invalid NDEF message, {}",
+                violations[0]
+            )));
+        }
+        return Ok(message);
+    }
+    let records = body["records"]
+        .as_array()
+        .ok_or_else(|| {
+            Error::Invalid("Body needs a \"message\" hex string or a \"records\" array".into())
+        })?
+        .iter()
+        .map(record_from_json)
+        .collect::<Result<Vec<_>, _>>()?;
+    if records.is_empty() {
+        return Err(Error::Invalid("\"records\" is empty".into()));
+    }
+    encode_ndef_message(&records)
+}
+
+fn record_from_json(value: &Value) -> Result<NdefRecord, Error> {
+    if let Some(uri) = value["uri"].as_str() {
+        return Ok(NdefRecord::uri(uri));
+    }
+    if let Some(text) = value["text"].as_str() {
+        let language = value["language"].as_str().unwrap_or("en");
+        return NdefRecord::text(language, text);
+    }
+    if let Some(mime_type) = value["mime"].as_str() {
+        let payload = hex::decode(value["payload"].as_str().unwrap_or_default())?;
+        return NdefRecord::mime(mime_type, payload);
+    }
+    if !value["json"].is_null() {
+        return Ok(NdefRecord::json(&value["json"]));
+    }
+    if let Some(package) = value["android_app"].as_str() {
+        return Ok(NdefRecord::android_app(package));
+    }
+    Err(Error::Invalid(format!("Unknown record: {}", value)))
+}
+
+/// Sends `apdu` and splits the response into data and status word.
+fn transmit_json(tx: &dyn Transport, apdu: &[u8]) -> Result<Value, Error> {
+    let mut response_buf = [0; 258];
+    let response = tx.transmit(apdu, &mut response_buf)?;
+    let (data, sw) = response.split_at(response.len().saturating_sub(2));
+    Ok(json!({
+        "response": hex::encode(response),
+        "data": hex::encode(data),
+        "sw": hex::encode(sw),
+    }))
+}
@@ -0,0 +1,204 @@
+use crate::error::Error;
+use crate::ntag::{Password, PasswordTransport};
+use crate::passthrough::ReaderTransport;
+use crate::sim::SimulatedTag;
+use crate::transport::Transport;
+use pcsc::{Context, Protocols, ReaderState, Scope, ShareMode, State};
+use serde_json::Value;
+use std::ffi::CString;
+use std::io;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Operation run against a connected card, given its transport and ATR.
+pub type CardOperation<'a> = dyn FnMut(&dyn Transport, &[u8]) -> Result<Value, Error> + 'a;
+
+/// A reader and whether a card is in its field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReaderStatus {
+    pub name: String,
+    pub card_present: bool,
+    /// ATR of the card, empty when there is none.
+    pub atr: Vec<u8>,
+}
+
+/// The readers the API server works with.
+///
+/// Errors from [`with_card`](Readers::with_card) are library [`Error`]s, so
+/// the server can tell [`Error::NoReader`] or [`Error::NoCard`] from a failed
+/// operation.
+pub trait Readers: Send + Sync {
+    fn status(&self) -> Result<Vec<ReaderStatus>, Error>;
+
+    /// Connects to the card on `reader` and runs `operation` in one
+    /// exclusive session.
+    fn with_card(&self, reader: &str, operation: &mut CardOperation) -> Result<Value, Error>;
+}
+
+/// Readers attached through PC/SC.
+pub struct PcscReaders {
+    ctx: Context,
+    /// Sent with PWD_AUTH before reads and writes when set.
+    pub password: Option<Password>,
+}
+
+impl PcscReaders {
+    pub fn establish(password: Option<Password>) -> Result<PcscReaders, Error> {
+        Ok(PcscReaders {
+            ctx: Context::establish(Scope::User)?,
+            password,
+        })
+    }
+}
+
+impl Readers for PcscReaders {
+    fn status(&self) -> Result<Vec<ReaderStatus>, Error> {
+        let names = match self.ctx.list_readers_owned() {
+            Ok(names) => names,
+            Err(pcsc::Error::NoReadersAvailable) => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+        let mut states: Vec<ReaderState> = names
+            .into_iter()
+            .map(|name| ReaderState::new(name, State::UNAWARE))
+            .collect();
+        if !states.is_empty() {
+            match self.ctx.get_status_change(Duration::ZERO, &mut states) {
+                Ok(()) | Err(pcsc::Error::Timeout) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(states
+            .iter()
+            .map(|state| {
+                let card_present = state.event_state().contains(State::PRESENT);
+                ReaderStatus {
+                    name: state.name().to_string_lossy().into_owned(),
+                    card_present,
+                    atr: if card_present {
+                        state.atr().to_vec()
+                    } else {
+                        Vec::new()
+                    },
+                }
+            })
+            .collect())
+    }
+
+    fn with_card(&self, reader: &str, operation: &mut CardOperation) -> Result<Value, Error> {
+        let name = CString::new(reader).map_err(|_| Error::NoReader)?;
+        let mut card = self.ctx.connect(&name, ShareMode::Shared, Protocols::ANY)?;
+        let atr = card.status2_owned()?.atr().to_vec();
+        let tx = card.transaction()?;
+        let transport = ReaderTransport::new(&tx, reader);
+        let value = match self.password {
+            Some(password) => operation(&PasswordTransport::new(transport, password), &atr)?,
+            None => operation(&transport, &atr)?,
+        };
+        Ok(value)
+    }
+}
+
+/// In-memory readers holding [`SimulatedTag`]s, for tests and demos.
+#[derive(Default)]
+pub struct SimulatedReaders {
+    readers: Mutex<Vec<(String, Option<SimulatedTag>)>>,
+}
+
+impl SimulatedReaders {
+    pub fn new(names: &[&str]) -> SimulatedReaders {
+        SimulatedReaders {
+            readers: Mutex::new(names.iter().map(|name| (name.to_string(), None)).collect()),
+        }
+    }
+
+    /// Plugs in an empty reader.
+    pub fn attach(&self, reader: &str) -> Result<(), Error> {
+        let mut readers = self
+            .readers
+            .lock()
+            .map_err(|_| io::Error::other("Reader lock poisoned"))?;
+        if readers.iter().any(|(name, _)| name == reader) {
+            return Err(Error::Invalid(format!(
+                "Reader {} is already attached",
+                reader
+            )));
+        }
+        readers.push((reader.to_string(), None));
+        Ok(())
+    }
+
+    /// Unplugs `reader` along with any tag on it.
+    pub fn detach(&self, reader: &str) -> Result<(), Error> {
+        let mut readers = self
+            .readers
+            .lock()
+            .map_err(|_| io::Error::other("Reader lock poisoned"))?;
+        let count = readers.len();
+        readers.retain(|(name, _)| name != reader);
+        if readers.len() == count {
+            return Err(pcsc::Error::UnknownReader.into());
+        }
+        Ok(())
+    }
+
+    /// Puts `tag` on `reader`, replacing any tag already there.
+    pub fn insert(&self, reader: &str, tag: SimulatedTag) -> Result<(), Error> {
+        self.slot(reader, |slot| *slot = Some(tag))
+    }
+
+    /// Takes the tag off `reader`.
+    pub fn remove(&self, reader: &str) -> Result<Option<SimulatedTag>, Error> {
+        let tag = self.slot(reader, Option::take)?;
+        if let Some(tag) = &tag {
+            tag.remove();
+        }
+        Ok(tag)
+    }
+
+    fn slot<R>(
+        &self,
+        reader: &str,
+        f: impl FnOnce(&mut Option<SimulatedTag>) -> R,
+    ) -> Result<R, Error> {
+        let mut readers = self
+            .readers
+            .lock()
+            .map_err(|_| io::Error::other("Reader lock poisoned"))?;
+        let (_, slot) = readers
+            .iter_mut()
+            .find(|(name, _)| name == reader)
+            .ok_or(pcsc::Error::UnknownReader)?;
+        Ok(f(slot))
+    }
+}
+
+impl Readers for SimulatedReaders {
+    fn status(&self) -> Result<Vec<ReaderStatus>, Error> {
+        let readers = self
+            .readers
+            .lock()
+            .map_err(|_| io::Error::other("Reader lock poisoned"))?;
+        Ok(readers
+            .iter()
+            .map(|(name, tag)| ReaderStatus {
+                name: name.clone(),
+                card_present: tag.is_some(),
+                atr: tag.as_ref().map(SimulatedTag::atr).unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    fn with_card(&self, reader: &str, operation: &mut CardOperation) -> Result<Value, Error> {
+        let readers = self
+            .readers
+            .lock()
+            .map_err(|_| io::Error::other("Reader lock poisoned"))?;
+        let (_, tag) = readers
+            .iter()
+            .find(|(name, _)| name == reader)
+            .ok_or(pcsc::Error::UnknownReader)?;
+        let tag = tag.as_ref().ok_or(pcsc::Error::NoSmartcard)?;
+        operation(tag, &tag.atr())
+    }
+}
@@ -0,0 +1,335 @@
+use super::events::Event;
+use super::http::{Request, MAX_BODY};
+use crate::error::Error;
+use serde_json::{json, Value};
+use std::io::{self, BufReader, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Appended to the client key before hashing (RFC 6455, section 1.3).
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const TEXT: u8 = 0x1;
+const CLOSE: u8 = 0x8;
+const PING: u8 = 0x9;
+const PONG: u8 = 0xA;
+
+/// Close status for messages the server cannot handle.
+const UNSUPPORTED_DATA: u16 = 1003;
+
+/// How often the sending side checks whether the client went away.
+const CLOSE_CHECK: Duration = Duration::from_secs(1);
+
+/// The `Sec-WebSocket-Key` of a valid upgrade request.
+pub fn upgrade_key(request: &Request) -> Result<&str, String> {
+    let has = |name: &str, token: &str| {
+        request.header(name).is_some_and(|value| {
+            value
+                .split(',')
+                .any(|part| part.trim().eq_ignore_ascii_case(token))
+        })
+    };
+    if !has("upgrade", "websocket") || !has("connection", "upgrade") {
+        return Err("Expected a WebSocket upgrade request".to_string());
+    }
+    if request.header("sec-websocket-version") != Some("13") {
+        return Err("Only WebSocket version 13 is supported".to_string());
+    }
+    request
+        .header("sec-websocket-key")
+        .ok_or_else(|| "Missing Sec-WebSocket-Key".to_string())
+}
+
+/// `Sec-WebSocket-Accept` for `key`.
+pub fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{}{}", key, WEBSOCKET_GUID).as_bytes()))
+}
+
+/// Pushes every event for the subscribed readers as a JSON text message
+/// until either side closes. Clients change the subscription with
+/// `{"subscribe": ["reader", ...]}`, or `{"subscribe": null}` for all
+/// readers, and get `{"subscribed": ...}` back.
+pub(super) fn stream_events(
+    mut reader: BufReader<TcpStream>,
+    stream: TcpStream,
+    events: Receiver<Event>,
+    readers: Option<Vec<String>>,
+    keep_alive: Duration,
+) -> Result<(), Error> {
+    let writer = Arc::new(Mutex::new(stream));
+    let subscription = Arc::new(Mutex::new(readers));
+    let closed = Arc::new(AtomicBool::new(false));
+
+    let incoming = {
+        let (writer, subscription, closed) = (
+            Arc::clone(&writer),
+            Arc::clone(&subscription),
+            Arc::clone(&closed),
+        );
+        std::thread::spawn(move || {
+            let result = receive(&mut reader, &writer, &subscription);
+            closed.store(true, Ordering::SeqCst);
+            if let Ok(stream) = writer.lock() {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+            result
+        })
+    };
+
+    let mut last_write = Instant::now();
+    while !closed.load(Ordering::SeqCst) {
+        let sent = match events.recv_timeout(CLOSE_CHECK) {
+            Ok(event) => {
+                let wanted = subscription
+                    .lock()
+                    .map_or(true, |readers| event.is_for(readers.as_deref()));
+                if wanted {
+                    send(&writer, TEXT, event.to_json().to_string().as_bytes())
+                } else {
+                    continue;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) if last_write.elapsed() >= keep_alive => {
+                send(&writer, PING, b"")
+            }
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if sent.is_err() {
+            break;
+        }
+        last_write = Instant::now();
+    }
+
+    closed.store(true, Ordering::SeqCst);
+    if let Ok(stream) = writer.lock() {
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+    incoming
+        .join()
+        .map_err(|_| io::Error::other("WebSocket reader thread panicked"))?
+        .map_err(Error::Invalid)
+}
+
+/// Handles client frames until the connection closes. Errors are returned
+/// as strings so they can cross the thread boundary.
+fn receive(
+    reader: &mut BufReader<TcpStream>,
+    writer: &Mutex<TcpStream>,
+    subscription: &Mutex<Option<Vec<String>>>,
+) -> Result<(), String> {
+    loop {
+        let (opcode, payload) = match read_frame(reader) {
+            Ok(frame) => frame,
+            // The sending side shut the socket down, or the client vanished.
+            Err(err) if is_disconnect(&err) => return Ok(()),
+            Err(err) => {
+                let _ = send(writer, CLOSE, &close_payload(UNSUPPORTED_DATA));
+                return Err(err.to_string());
+            }
+        };
+        let reply = match opcode {
+            TEXT => {
+                let reply = match subscribe(&payload) {
+                    Ok(readers) => {
+                        let reply = json!({ "subscribed": readers });
+                        if let Ok(mut subscription) = subscription.lock() {
+                            *subscription = readers;
+                        }
+                        reply
+                    }
+                    Err(err) => json!({ "error": err }),
+                };
+                send(writer, TEXT, reply.to_string().as_bytes())
+            }
+            PING => send(writer, PONG, &payload),
+            PONG => Ok(()),
+            CLOSE => {
+                let _ = send(writer, CLOSE, &payload[..payload.len().min(2)]);
+                return Ok(());
+            }
+            _ => {
+                let _ = send(writer, CLOSE, &close_payload(UNSUPPORTED_DATA));
+                return Err(format!("Unsupported WebSocket opcode {:#X}", opcode));
+            }
+        };
+        reply.map_err(|err| err.to_string())?;
+    }
+}
+
+/// Readers named by a `{"subscribe": [...]}` message.
+fn subscribe(payload: &[u8]) -> Result<Option<Vec<String>>, String> {
+    let message: Value = serde_json::from_slice(payload).map_err(|err| err.to_string())?;
+    match &message["subscribe"] {
+        Value::Null if message.get("subscribe").is_some() => Ok(None),
+        Value::Array(readers) => readers
+            .iter()
+            .map(|reader| {
+                reader
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| "Reader names must be strings".to_string())
+            })
+            .collect::<Result<_, _>>()
+            .map(Some),
+        _ => Err("Expected {\"subscribe\": [reader, ...]}".to_string()),
+    }
+}
+
+fn is_disconnect(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+    )
+}
+
+fn close_payload(status: u16) -> Vec<u8> {
+    status.to_be_bytes().to_vec()
+}
+
+fn send(writer: &Mutex<TcpStream>, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut stream = writer
+        .lock()
+        .map_err(|_| io::Error::other("WebSocket writer lock poisoned"))?;
+    write_frame(&mut *stream, opcode, payload)
+}
+
+/// Writes one unmasked, unfragmented frame, as servers send them.
+fn write_frame<W: Write>(stream: &mut W, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        length @ 0..=125 => frame.push(length as u8),
+        length @ 126..=0xFFFF => {
+            frame.push(126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    stream.write_all(&frame)?;
+    stream.flush()
+}
+
+/// Reads one masked client frame and returns its opcode and unmasked
+/// payload. Fragmented messages are refused; clients only send short
+/// subscription requests.
+fn read_frame<R: Read>(reader: &mut R) -> io::Result<(u8, Vec<u8>)> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut head = [0; 2];
+    reader.read_exact(&mut head)?;
+    if head[0] & 0x80 == 0 || head[0] & 0x0F == 0 {
+        return Err(invalid("Fragmented WebSocket messages are not supported"));
+    }
+    if head[1] & 0x80 == 0 {
+        return Err(invalid("Client WebSocket frames must be masked"));
+    }
+    let length = match head[1] & 0x7F {
+        126 => {
+            let mut length = [0; 2];
+            reader.read_exact(&mut length)?;
+            u16::from_be_bytes(length) as usize
+        }
+        127 => {
+            let mut length = [0; 8];
+            reader.read_exact(&mut length)?;
+            usize::try_from(u64::from_be_bytes(length)).unwrap_or(usize::MAX)
+        }
+        length => length as usize,
+    };
+    if length > MAX_BODY {
+        return Err(invalid("WebSocket message is too large"));
+    }
+
+    let mut mask = [0; 4];
+    reader.read_exact(&mut mask)?;
+    let mut payload = vec![0; length];
+    reader.read_exact(&mut payload)?;
+    for (index, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[index % 4];
+    }
+    Ok((head[0] & 0x0F, payload))
+}
+
+/// SHA-1, needed only for the handshake (FIPS 180-4).
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut words = [0u32; 80];
+        for (word, bytes) in words.iter_mut().zip(block.chunks(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for index in 16..80 {
+            words[index] =
+                (words[index - 3] ^ words[index - 8] ^ words[index - 14] ^ words[index - 16])
+                    .rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (index, word) in words.iter().enumerate() {
+            let (f, k) = match index {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (value, add) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, word) in digest.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+/// Standard base64 with padding.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let group = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * index) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -0,0 +1,251 @@
+use crate::monitor::{watch_readers, ReaderEvent};
+use crate::transport::Transport;
+use futures_core::Stream;
+use pcsc::{Card, Context, Error, Protocols, ReaderState, Scope, ShareMode, State};
+use std::ffi::CString;
+use std::pin::Pin;
+use std::sync::mpsc;
+use std::task::Poll;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc as tokio_mpsc, oneshot};
+
+type Job<T> = Box<dyn FnOnce(&T) + Send>;
+
+/// A thread that owns a value and runs jobs on it one at a time. The thread
+/// ends, dropping the value, once every handle is gone.
+struct Worker<T> {
+    jobs: mpsc::Sender<Job<T>>,
+}
+
+impl<T> Clone for Worker<T> {
+    fn clone(&self) -> Worker<T> {
+        Worker {
+            jobs: self.jobs.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> Worker<T> {
+    fn spawn(value: T) -> Worker<T> {
+        let (jobs, receiver) = mpsc::channel::<Job<T>>();
+        std::thread::spawn(move || {
+            for job in receiver {
+                job(&value);
+            }
+        });
+        Worker { jobs }
+    }
+
+    /// Runs `f` on the worker thread. `Error::ServiceStopped` when the
+    /// thread is gone, e.g. because an earlier job panicked.
+    async fn run<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&T) -> R + Send + 'static,
+    ) -> Result<R, Error> {
+        let (reply, result) = oneshot::channel();
+        self.jobs
+            .send(Box::new(move |value| {
+                let _ = reply.send(f(value));
+            }))
+            .map_err(|_| Error::ServiceStopped)?;
+        result.await.map_err(|_| Error::ServiceStopped)
+    }
+}
+
+/// A [`Transport`] for async code. It lives on a dedicated thread that runs
+/// one job at a time, so exchanges never block the runtime and never
+/// interleave. Clones share the thread.
+///
+/// A job still runs to the end when its future is dropped.
+pub struct AsyncTransport<T> {
+    worker: Worker<T>,
+}
+
+impl<T> Clone for AsyncTransport<T> {
+    fn clone(&self) -> AsyncTransport<T> {
+        AsyncTransport {
+            worker: self.worker.clone(),
+        }
+    }
+}
+
+impl<T: Transport + Send + 'static> AsyncTransport<T> {
+    pub fn spawn(transport: T) -> AsyncTransport<T> {
+        AsyncTransport {
+            worker: Worker::spawn(transport),
+        }
+    }
+
+    /// Sends `apdu` and returns the whole response, status word included.
+    pub async fn transmit(&self, apdu: &[u8]) -> Result<Vec<u8>, Error> {
+        let apdu = apdu.to_vec();
+        self.run(move |transport| {
+            let mut response_buf = [0; 258];
+            transport
+                .transmit(&apdu, &mut response_buf)
+                .map(<[u8]>::to_vec)
+        })
+        .await?
+    }
+
+    /// Runs `f` with the transport on its thread, for library functions
+    /// such as `read_dump` that make several exchanges.
+    pub async fn run<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&T) -> R + Send + 'static,
+    ) -> Result<R, Error> {
+        self.worker.run(f).await
+    }
+}
+
+/// PC/SC context for async code.
+///
+/// Listing readers and connecting run on the context's dedicated thread;
+/// each card then gets its own thread through [`AsyncTransport`]. Waits for
+/// status changes run on threads of their own, as `SCardGetStatusChange`
+/// can block for as long as nothing happens. [`AsyncContext::cancel`] ends
+/// them; dropping their future does not.
+#[derive(Clone)]
+pub struct AsyncContext {
+    ctx: Context,
+    worker: Worker<Context>,
+}
+
+impl AsyncContext {
+    pub fn establish() -> Result<AsyncContext, Error> {
+        let ctx = Context::establish(Scope::User)?;
+        Ok(AsyncContext {
+            worker: Worker::spawn(ctx.clone()),
+            ctx,
+        })
+    }
+
+    pub async fn list_readers(&self) -> Result<Vec<String>, Error> {
+        self.worker
+            .run(|ctx| match ctx.list_readers_owned() {
+                Ok(names) => Ok(names
+                    .iter()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .collect()),
+                Err(Error::NoReadersAvailable) => Ok(Vec::new()),
+                Err(err) => Err(err),
+            })
+            .await?
+    }
+
+    /// Connects to the card on `reader` and returns it with its ATR.
+    pub async fn connect(&self, reader: &str) -> Result<(AsyncTransport<Card>, Vec<u8>), Error> {
+        let name = reader_name(reader)?;
+        let (card, atr) = self
+            .worker
+            .run(move |ctx| {
+                let card = ctx.connect(&name, ShareMode::Shared, Protocols::ANY)?;
+                let atr = card.status2_owned()?.atr().to_vec();
+                Ok::<_, Error>((card, atr))
+            })
+            .await??;
+        Ok((AsyncTransport::spawn(card), atr))
+    }
+
+    /// Waits until a card is on `reader` and returns its ATR, right away if
+    /// one already is. Fails with `Error::Timeout` once `timeout` has passed,
+    /// with `Error::Cancelled` after [`cancel`](AsyncContext::cancel), and
+    /// with `Error::UnknownReader` when the reader goes away.
+    pub async fn wait_for_card(
+        &self,
+        reader: &str,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<u8>, Error> {
+        let name = reader_name(reader)?;
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        self.blocking(move |ctx| {
+            let mut states = [ReaderState::new(name, State::UNAWARE)];
+            loop {
+                let remaining =
+                    deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+                ctx.get_status_change(remaining, &mut states)?;
+                let state = &mut states[0];
+                if state.event_state().contains(State::PRESENT) {
+                    return Ok(state.atr().to_vec());
+                }
+                if state
+                    .event_state()
+                    .intersects(State::UNKNOWN | State::IGNORE)
+                {
+                    return Err(Error::UnknownReader);
+                }
+                state.sync_current_state();
+            }
+        })
+        .await
+    }
+
+    /// Reader and card events, starting with the readers and cards already
+    /// present. After [`cancel`](AsyncContext::cancel) or another PC/SC
+    /// error the stream yields the error and ends.
+    pub fn events(&self) -> ReaderEvents {
+        let (sender, receiver) = tokio_mpsc::unbounded_channel();
+        let ctx = self.ctx.clone();
+        std::thread::spawn(move || {
+            // Stops at the first event after the stream was dropped.
+            let result = watch_readers(&ctx, |event| sender.send(Ok(event)).is_ok());
+            if let Err(err) = result {
+                let _ = sender.send(Err(err));
+            }
+        });
+        ReaderEvents { receiver }
+    }
+
+    /// Ends every pending [`wait_for_card`](AsyncContext::wait_for_card) and
+    /// event stream of this context and its clones (`SCardCancel`).
+    pub fn cancel(&self) -> Result<(), Error> {
+        self.ctx.cancel()
+    }
+
+    /// Runs `f` on a thread of its own.
+    async fn blocking<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Context) -> Result<R, Error> + Send + 'static,
+    ) -> Result<R, Error> {
+        let (reply, result) = oneshot::channel();
+        let ctx = self.ctx.clone();
+        std::thread::spawn(move || {
+            let _ = reply.send(f(&ctx));
+        });
+        result.await.map_err(|_| Error::ServiceStopped)?
+    }
+}
+
+/// Stream of [`ReaderEvent`]s from [`AsyncContext::events`].
+pub struct ReaderEvents {
+    receiver: tokio_mpsc::UnboundedReceiver<Result<ReaderEvent, Error>>,
+}
+
+impl ReaderEvents {
+    /// Events sent into `receiver` instead of read from PC/SC, for tests.
+    pub fn from_receiver(
+        receiver: tokio_mpsc::UnboundedReceiver<Result<ReaderEvent, Error>>,
+    ) -> ReaderEvents {
+        ReaderEvents { receiver }
+    }
+
+    /// The next event, `None` once the stream has ended.
+    pub async fn next(&mut self) -> Option<Result<ReaderEvent, Error>> {
+        self.receiver.recv().await
+    }
+}
+
+impl Stream for ReaderEvents {
+    type Item = Result<ReaderEvent, Error>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+fn reader_name(reader: &str) -> Result<CString, Error> {
+    CString::new(reader).map_err(|_| Error::UnknownReader)
+}
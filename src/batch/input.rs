@@ -0,0 +1,189 @@
+use crate::error::Error;
+use crate::ndef::NdefRecord;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// What one tag of a batch gets written with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    /// Key the report and resumed runs use; the line number when the input
+    /// has no `id`.
+    pub id: String,
+    pub records: Vec<NdefRecord>,
+}
+
+impl Job {
+    /// Builds a job from the fields of one input line: an optional `id` and
+    /// one of `uri` (or `url`), `text` with an optional `language`, or
+    /// `mime` with a UTF-8 `payload`.
+    fn from_fields<'a>(line: usize, field: impl Fn(&str) -> Option<&'a str>) -> Result<Job, Error> {
+        let field = |name: &str| field(name).filter(|value| !value.is_empty());
+        let record = match (
+            field("uri").or_else(|| field("url")),
+            field("text"),
+            field("mime"),
+        ) {
+            (Some(uri), None, None) => NdefRecord::uri(uri),
+            (None, Some(text), None) => NdefRecord::text(field("language").unwrap_or("en"), text)?,
+            (None, None, Some(mime_type)) => NdefRecord::mime(
+                mime_type,
+                field("payload").unwrap_or_default().as_bytes().to_vec(),
+            )?,
+            (None, None, None) => {
+                return Err(Error::Invalid("No uri, text or mime payload".into()))
+            }
+            _ => {
+                return Err(Error::Invalid(
+                    "Only one of uri, text and mime may be given".into(),
+                ))
+            }
+        };
+        Ok(Job {
+            id: field("id").map_or_else(|| line.to_string(), str::to_string),
+            records: vec![record],
+        })
+    }
+
+    /// Summaries of the records, as written to the report.
+    pub fn summary(&self) -> String {
+        self.records
+            .iter()
+            .map(NdefRecord::summary)
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// Loads the jobs of a CSV file, or of a JSON Lines file when the name ends
+/// in `.jsonl` or `.ndjson`.
+pub fn load_jobs(path: &Path) -> Result<Vec<Job>, Error> {
+    let text = std::fs::read_to_string(path)?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("jsonl" | "ndjson") => parse_json_lines(&text),
+        _ => parse_csv(&text),
+    }
+}
+
+/// Parses CSV with a header row naming the columns, e.g.
+/// `id,uri` then `dev-1,https://example.com/d/1`. Fields may be quoted,
+/// with `""` for a quote inside.
+pub fn parse_csv(text: &str) -> Result<Vec<Job>, Error> {
+    let mut rows = csv_rows(text)?.into_iter();
+    let (_, header) = rows
+        .next()
+        .ok_or_else(|| Error::Invalid("CSV file has no header row".into()))?;
+    let header: Vec<String> = header
+        .iter()
+        .map(|name| name.trim().to_ascii_lowercase())
+        .collect();
+
+    let jobs = rows
+        .filter(|(_, row)| row.iter().any(|field| !field.is_empty()))
+        .map(|(line, row)| {
+            if row.len() != header.len() {
+                return Err(Error::Invalid(format!(
+                    "Line {}: {} fields, the header has {}",
+                    line,
+                    row.len(),
+                    header.len()
+                )));
+            }
+            Job::from_fields(line, |name| {
+                let column = header.iter().position(|column| column == name)?;
+                Some(row[column].as_str())
+            })
+            .map_err(|err| Error::Invalid(format!("Line {}: {}", line, err)))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    check_unique(&jobs)?;
+    Ok(jobs)
+}
+
+/// Parses one JSON object per line, with the same keys as the CSV columns,
+/// e.g. `{"id": "dev-1", "text": "12345-device-id"}`. Blank lines are
+/// skipped.
+pub fn parse_json_lines(text: &str) -> Result<Vec<Job>, Error> {
+    let jobs = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let line_number = index + 1;
+            let value: Value = serde_json::from_str(line)
+                .map_err(|err| Error::Invalid(format!("Line {}: {}", line_number, err)))?;
+            if !value.is_object() {
+                return Err(Error::Invalid(format!(
+                    "Line {}: expected a JSON object",
+                    line_number
+                )));
+            }
+            Job::from_fields(line_number, |name| value[name].as_str())
+                .map_err(|err| Error::Invalid(format!("Line {}: {}", line_number, err)))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    check_unique(&jobs)?;
+    Ok(jobs)
+}
+
+/// Resumed runs find finished jobs by id, so ids must not repeat.
+fn check_unique(jobs: &[Job]) -> Result<(), Error> {
+    let mut seen = HashSet::new();
+    match jobs.iter().find(|job| !seen.insert(job.id.as_str())) {
+        Some(job) => Err(Error::Invalid(format!(
+            "Job id {:?} appears more than once",
+            job.id
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Fields of a CSV row and the line the row starts on.
+type Row = (usize, Vec<String>);
+
+/// Splits CSV text into rows.
+fn csv_rows(text: &str) -> Result<Vec<Row>, Error> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push((row_line, std::mem::take(&mut row)));
+                line += 1;
+                row_line = line;
+            }
+            _ => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if quoted {
+        return Err(Error::Invalid(format!(
+            "Line {}: unterminated quoted field",
+            row_line
+        )));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push((row_line, row));
+    }
+    Ok(rows)
+}
@@ -0,0 +1,179 @@
+mod input;
+mod report;
+
+pub use input::{load_jobs, parse_csv, parse_json_lines, Job};
+pub use report::{Entry, Report, Status};
+
+use crate::apdu::{get_uid, is_successful_response, response_data};
+use crate::chip::{detect_chip, Chip};
+use crate::error::Error;
+use crate::lock::make_read_only;
+use crate::ndef::encode_ndef_message;
+use crate::ntag::{self, read_pages, write_ndef_message, Password, Protection};
+use crate::tlv::{encode_tlv, NDEF_TLV, TERMINATOR_TLV};
+use crate::transport::Transport;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// What happens to each tag after its payload was written and verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Options {
+    /// Password-protects writes from page `auth0` on.
+    pub protect: Option<(Password, u8)>,
+    /// Makes the tag permanently read-only, after protecting it.
+    pub lock: bool,
+}
+
+/// Works through a list of jobs, one tag each, keeping a report that lets
+/// an interrupted run carry on where it stopped.
+///
+/// Jobs are handed out in order. A job whose tag failed stays next in line
+/// for the following tag. Tags whose UID already got a job, in this run or
+/// in one recorded in the report, are never written again.
+pub struct Batch {
+    jobs: Vec<Job>,
+    pub options: Options,
+    report: Report,
+    /// Ids of the finished jobs.
+    done: HashSet<String>,
+    /// Job id of every provisioned UID.
+    provisioned: HashMap<Vec<u8>, String>,
+}
+
+impl Batch {
+    /// Starts or resumes a batch, taking the finished jobs and provisioned
+    /// UIDs from the report at `report_path`.
+    pub fn open(jobs: Vec<Job>, report_path: &Path, options: Options) -> Result<Batch, Error> {
+        let (report, entries) = Report::open(report_path)?;
+        let mut batch = Batch {
+            jobs,
+            options,
+            report,
+            done: HashSet::new(),
+            provisioned: HashMap::new(),
+        };
+        for entry in entries
+            .into_iter()
+            .filter(|entry| entry.status == Status::Provisioned)
+        {
+            batch.done.insert(entry.id.clone());
+            batch.provisioned.insert(entry.uid, entry.id);
+        }
+        Ok(batch)
+    }
+
+    /// The job the next tag gets, `None` once every job is done.
+    pub fn next_job(&self) -> Option<&Job> {
+        self.jobs.iter().find(|job| !self.done.contains(&job.id))
+    }
+
+    /// Number of jobs still to do.
+    pub fn remaining(&self) -> usize {
+        self.jobs
+            .iter()
+            .filter(|job| !self.done.contains(&job.id))
+            .count()
+    }
+
+    /// Provisions the tag behind `tx` with the next job and reports the
+    /// result. `None` when there is nothing left to do; an error when the
+    /// tag's UID cannot be read, which is not recorded in the report.
+    pub fn provision<T: Transport + ?Sized>(
+        &mut self,
+        tx: &T,
+        atr: &[u8],
+        reader: &str,
+    ) -> Result<Option<Entry>, Error> {
+        let job = match self.next_job() {
+            Some(job) => job.clone(),
+            None => return Ok(None),
+        };
+        let uid = read_uid(tx)?;
+
+        let entry = match self.provisioned.get(&uid) {
+            Some(id) => {
+                let mut entry = Entry::new(id, &uid, reader, Status::AlreadyProvisioned, "");
+                entry.error = Some(format!("Tag already has job {}", id));
+                entry
+            }
+            None => match write_job(tx, atr, &job, &self.options) {
+                Ok(()) => {
+                    self.done.insert(job.id.clone());
+                    self.provisioned.insert(uid.clone(), job.id.clone());
+                    Entry::new(&job.id, &uid, reader, Status::Provisioned, &job.summary())
+                }
+                Err(err) => {
+                    let mut entry =
+                        Entry::new(&job.id, &uid, reader, Status::Failed, &job.summary());
+                    entry.error = Some(err.to_string());
+                    entry
+                }
+            },
+        };
+        self.report.append(&entry)?;
+        Ok(Some(entry))
+    }
+}
+
+fn read_uid<T: Transport + ?Sized>(tx: &T) -> Result<Vec<u8>, Error> {
+    let mut response_buf = [0; 256];
+    let response = tx.transmit(&get_uid(), &mut response_buf)?;
+    if !is_successful_response(response) || response.len() <= 2 {
+        return Err(Error::status("Reading the UID failed", response));
+    }
+    Ok(response_data(response).to_vec())
+}
+
+/// Writes, verifies and protects one tag.
+fn write_job<T: Transport + ?Sized>(
+    tx: &T,
+    atr: &[u8],
+    job: &Job,
+    options: &Options,
+) -> Result<(), Error> {
+    let chip = detect_chip(tx, atr)?;
+    if let Some((password, _)) = &options.protect {
+        // A tag that failed after protecting it in an earlier attempt needs
+        // the password before it can be written again.
+        if (ntag::read_protection(tx, chip)?.0 as usize) < chip.memory_size() / 4 {
+            ntag::authenticate(tx, password)?;
+        }
+    }
+
+    let message = encode_ndef_message(&job.records)?;
+    write_ndef_message(tx, chip, &message)?;
+    verify_ndef_message(tx, chip, &message)?;
+
+    if let Some((password, auth0)) = &options.protect {
+        ntag::protect(tx, chip, password, *auth0, Protection::Write)?;
+    }
+    if options.lock {
+        make_read_only(tx, chip, false, |_| true)?;
+    }
+    Ok(())
+}
+
+/// Reads the NDEF TLV back and compares it with what was written.
+fn verify_ndef_message<T: Transport + ?Sized>(
+    tx: &T,
+    chip: Chip,
+    message: &[u8],
+) -> Result<(), Error> {
+    let mut expected = encode_tlv(NDEF_TLV, message);
+    expected.push(TERMINATOR_TLV);
+    let start = chip.user_pages().start;
+    let pages = read_pages(tx, chip, start..start + expected.len().div_ceil(4))?;
+
+    let mut written = Vec::with_capacity(pages.len() * 4);
+    for (index, page) in pages.into_iter().enumerate() {
+        let page = page
+            .ok_or_else(|| Error::Tag(format!("Page {} could not be read back", start + index)))?;
+        written.extend_from_slice(&page);
+    }
+    if written[..expected.len()] != expected[..] {
+        return Err(Error::Tag(
+            "The NDEF message read back differs from the one written".into(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use crate::error::Error;
+use crate::hex;
+use log::warn;
+use serde_json::{json, Value};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Written, verified and protected as asked.
+    Provisioned,
+    Failed,
+    /// The tag was provisioned before, by this run or an earlier one, and
+    /// was left alone.
+    AlreadyProvisioned,
+}
+
+impl Status {
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Provisioned => "provisioned",
+            Status::Failed => "failed",
+            Status::AlreadyProvisioned => "already_provisioned",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Status> {
+        [
+            Status::Provisioned,
+            Status::Failed,
+            Status::AlreadyProvisioned,
+        ]
+        .into_iter()
+        .find(|status| status.name() == name)
+    }
+}
+
+/// One line of the report: which tag got which job, and how it went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub id: String,
+    pub uid: Vec<u8>,
+    pub reader: String,
+    pub status: Status,
+    /// Summary of the records the job writes.
+    pub payload: String,
+    pub error: Option<String>,
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+}
+
+impl Entry {
+    pub fn new(id: &str, uid: &[u8], reader: &str, status: Status, payload: &str) -> Entry {
+        Entry {
+            id: id.to_string(),
+            uid: uid.to_vec(),
+            reader: reader.to_string(),
+            status,
+            payload: payload.to_string(),
+            error: None,
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut value = json!({
+            "id": self.id,
+            "uid": hex::encode(&self.uid),
+            "reader": self.reader,
+            "status": self.status.name(),
+            "payload": self.payload,
+            "time": self.time,
+        });
+        if let Some(error) = &self.error {
+            value["error"] = error.as_str().into();
+        }
+        value
+    }
+
+    pub fn from_json(value: &Value) -> Result<Entry, Error> {
+        let text = |name: &str| {
+            value[name]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| Error::Invalid(format!("Report entry has no {:?}", name)))
+        };
+        let status = text("status")?;
+        Ok(Entry {
+            id: text("id")?,
+            uid: hex::decode(&text("uid")?)?,
+            reader: text("reader")?,
+            status: Status::from_name(&status)
+                .ok_or_else(|| Error::Invalid(format!("Unknown report status {:?}", status)))?,
+            payload: text("payload")?,
+            error: value["error"].as_str().map(str::to_string),
+            time: value["time"].as_u64().unwrap_or_default(),
+        })
+    }
+}
+
+/// JSON Lines report, one [`Entry`] per tag presented. Every entry is
+/// written in one go as soon as it is known, so an interrupted run loses at
+/// most the tag it was working on.
+pub struct Report {
+    file: File,
+}
+
+impl Report {
+    /// Opens `path` for appending and returns it with the entries already
+    /// in it. A last line cut short by a crash is skipped.
+    pub fn open(path: &Path) -> Result<(Report, Vec<Entry>), Error> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err.into()),
+        };
+        let lines: Vec<&str> = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        let mut entries = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            let entry = serde_json::from_str(line)
+                .map_err(|err| err.into())
+                .and_then(|value| Entry::from_json(&value));
+            match entry {
+                Ok(entry) => entries.push(entry),
+                Err(_) if index + 1 == lines.len() && !text.ends_with('\n') => {
+                    warn!("Ignoring incomplete last line of {}", path.display());
+                }
+                Err(err) => {
+                    return Err(Error::Invalid(format!(
+                        "{} line {}: {}",
+                        path.display(),
+                        index + 1,
+                        err
+                    )))
+                }
+            }
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if !text.is_empty() && !text.ends_with('\n') {
+            file.write_all(b"\n")?;
+        }
+        Ok((Report { file }, entries))
+    }
+
+    pub fn append(&mut self, entry: &Entry) -> Result<(), Error> {
+        let line = format!("{}\n", entry.to_json());
+        self.file.write_all(line.as_bytes())?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
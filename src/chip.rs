@@ -0,0 +1,309 @@
+use crate::apdu::{is_successful_response, read_binary};
+use crate::error::Error;
+use crate::transport::Transport;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// Registered application provider id used in PC/SC Part 3 storage card ATRs.
+const PCSC_RID: [u8; 5] = [0xA0, 0x00, 0x00, 0x03, 0x06];
+
+/// Page holding the 16-bit one-way counter of MIFARE Ultralight C.
+pub const ULC_COUNTER_PAGE: usize = 0x29;
+
+/// Chips the library knows the memory layout of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Chip {
+    MifareUltralight,
+    MifareUltralightC,
+    Ntag213,
+    Ntag215,
+    Ntag216,
+    MifareClassic1K,
+    MifareClassic4K,
+}
+
+impl Chip {
+    pub const ALL: [Chip; 7] = [
+        Chip::MifareUltralight,
+        Chip::MifareUltralightC,
+        Chip::Ntag213,
+        Chip::Ntag215,
+        Chip::Ntag216,
+        Chip::MifareClassic1K,
+        Chip::MifareClassic4K,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Chip::MifareUltralight => "MIFARE Ultralight",
+            Chip::MifareUltralightC => "MIFARE Ultralight C",
+            Chip::Ntag213 => "NTAG213",
+            Chip::Ntag215 => "NTAG215",
+            Chip::Ntag216 => "NTAG216",
+            Chip::MifareClassic1K => "MIFARE Classic 1K",
+            Chip::MifareClassic4K => "MIFARE Classic 4K",
+        }
+    }
+
+    /// Short identifier used on the command line and in saved dumps.
+    pub fn id(self) -> &'static str {
+        match self {
+            Chip::MifareUltralight => "ultralight",
+            Chip::MifareUltralightC => "ultralight-c",
+            Chip::Ntag213 => "ntag213",
+            Chip::Ntag215 => "ntag215",
+            Chip::Ntag216 => "ntag216",
+            Chip::MifareClassic1K => "classic-1k",
+            Chip::MifareClassic4K => "classic-4k",
+        }
+    }
+
+    /// `true` for NFC Forum Type 2 tags (Ultralight and NTAG families).
+    pub fn is_type2(self) -> bool {
+        !self.is_classic()
+    }
+
+    pub fn is_classic(self) -> bool {
+        matches!(self, Chip::MifareClassic1K | Chip::MifareClassic4K)
+    }
+
+    pub fn is_ntag(self) -> bool {
+        matches!(self, Chip::Ntag213 | Chip::Ntag215 | Chip::Ntag216)
+    }
+
+    /// Size in bytes of one page (Type 2) or block (Classic).
+    pub fn block_size(self) -> usize {
+        if self.is_classic() {
+            16
+        } else {
+            4
+        }
+    }
+
+    /// Number of pages or blocks in the whole memory, including config pages.
+    pub fn block_count(self) -> usize {
+        match self {
+            Chip::MifareUltralight => 16,
+            Chip::MifareUltralightC => 48,
+            Chip::Ntag213 => 45,
+            Chip::Ntag215 => 135,
+            Chip::Ntag216 => 231,
+            Chip::MifareClassic1K => 64,
+            Chip::MifareClassic4K => 256,
+        }
+    }
+
+    pub fn memory_size(self) -> usize {
+        self.block_size() * self.block_count()
+    }
+
+    /// Pages holding user data (the NDEF area) on Type 2 tags.
+    pub fn user_pages(self) -> Range<usize> {
+        match self {
+            Chip::MifareUltralight => 4..16,
+            Chip::MifareUltralightC => 4..0x28,
+            Chip::Ntag213 => 4..0x28,
+            Chip::Ntag215 => 4..0x82,
+            Chip::Ntag216 => 4..0xE2,
+            Chip::MifareClassic1K | Chip::MifareClassic4K => 0..0,
+        }
+    }
+
+    /// Largest NDEF message that fits in the data area next to its TLV header
+    /// and a Terminator TLV. MIFARE Classic cards are assumed to be MAD
+    /// formatted with every sector but the MAD sectors given to NDEF.
+    pub fn ndef_capacity(self) -> usize {
+        let area = match self {
+            Chip::MifareClassic1K => 15 * 3 * 16,
+            Chip::MifareClassic4K => 30 * 3 * 16 + 8 * 15 * 16,
+            _ => self.user_pages().len() * 4,
+        };
+        // Tag byte, one or three length bytes, terminator.
+        if area - 3 < 0xFF {
+            area - 3
+        } else {
+            area - 5
+        }
+    }
+
+    /// Page holding the dynamic lock bytes, if the chip has any.
+    pub fn dynamic_lock_page(self) -> Option<usize> {
+        match self {
+            Chip::MifareUltralightC => Some(0x28),
+            Chip::Ntag213 => Some(0x28),
+            Chip::Ntag215 => Some(0x82),
+            Chip::Ntag216 => Some(0xE2),
+            _ => None,
+        }
+    }
+
+    /// Number of user pages write-protected by each dynamic lock bit.
+    pub fn dynamic_lock_pages_per_bit(self) -> usize {
+        match self {
+            Chip::MifareUltralightC => 4,
+            Chip::Ntag213 => 2,
+            Chip::Ntag215 | Chip::Ntag216 => 16,
+            _ => 0,
+        }
+    }
+
+    /// Pages after the dynamic lock bytes: CFG0, CFG1, PWD and PACK on NTAG,
+    /// counter, AUTH0, AUTH1 and the 3DES key on Ultralight C.
+    pub fn config_pages(self) -> Option<Range<usize>> {
+        match self {
+            Chip::MifareUltralightC => Some(0x29..0x30),
+            Chip::Ntag213 => Some(0x29..0x2D),
+            Chip::Ntag215 => Some(0x83..0x87),
+            Chip::Ntag216 => Some(0xE3..0xE7),
+            _ => None,
+        }
+    }
+
+    /// Number of sectors on MIFARE Classic chips, zero otherwise.
+    pub fn sector_count(self) -> usize {
+        match self {
+            Chip::MifareClassic1K => 16,
+            Chip::MifareClassic4K => 40,
+            _ => 0,
+        }
+    }
+
+    /// First block of a MIFARE Classic sector.
+    pub fn sector_first_block(self, sector: usize) -> usize {
+        if sector < 32 {
+            sector * 4
+        } else {
+            128 + (sector - 32) * 16
+        }
+    }
+
+    /// Number of blocks in a MIFARE Classic sector.
+    pub fn sector_block_count(self, sector: usize) -> usize {
+        if sector < 32 {
+            4
+        } else {
+            16
+        }
+    }
+
+    /// Sector a MIFARE Classic block belongs to.
+    pub fn sector_of(self, block: usize) -> usize {
+        if block < 128 {
+            block / 4
+        } else {
+            32 + (block - 128) / 16
+        }
+    }
+
+    /// Trailer block (keys and access bits) of a MIFARE Classic sector.
+    pub fn sector_trailer(self, sector: usize) -> usize {
+        self.sector_first_block(sector) + self.sector_block_count(sector) - 1
+    }
+
+    /// `true` when `block` is the trailer of its sector.
+    pub fn is_sector_trailer(self, block: usize) -> bool {
+        block == self.sector_trailer(self.sector_of(block))
+    }
+
+    /// Maps the data area size byte of a Type 2 capability container to a chip.
+    pub fn from_cc_size(size: u8) -> Option<Chip> {
+        match size {
+            0x06 => Some(Chip::MifareUltralight),
+            0x12 => Some(Chip::Ntag213),
+            0x3E => Some(Chip::Ntag215),
+            0x6D => Some(Chip::Ntag216),
+            _ => None,
+        }
+    }
+
+    /// Finds the chip whose whole memory is exactly `size` bytes.
+    pub fn from_memory_size(size: usize) -> Option<Chip> {
+        Chip::ALL
+            .into_iter()
+            .find(|chip| chip.memory_size() == size)
+    }
+}
+
+impl fmt::Display for Chip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Chip {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Chip::ALL
+            .into_iter()
+            .find(|chip| chip.id().eq_ignore_ascii_case(s) || chip.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown chip type: {}", s))
+    }
+}
+
+/// Extracts the PC/SC Part 3 card name (bytes 13-14) from a contactless ATR.
+pub fn atr_card_name(atr: &[u8]) -> Option<u16> {
+    if atr.len() < 15 || atr[0] != 0x3B || atr[5] != 0x4F || atr[7..12] != PCSC_RID {
+        return None;
+    }
+    Some(u16::from_be_bytes([atr[13], atr[14]]))
+}
+
+/// Card type named by the ATR alone, without talking to the card.
+pub fn atr_card_type(atr: &[u8]) -> &'static str {
+    match atr_card_name(atr) {
+        Some(0x0001) => "MIFARE Classic 1K",
+        Some(0x0002) => "MIFARE Classic 4K",
+        Some(0x0003) => "MIFARE Ultralight/NTAG",
+        Some(0x0026) => "MIFARE Mini",
+        Some(0x002F) => "Jewel",
+        Some(0x0030) => "Topaz",
+        Some(0x003A) => "MIFARE Ultralight C",
+        Some(_) => "Unknown storage card",
+        // 3B 8x 80 01: ISO 14443-4 card with historical bytes.
+        None if atr.len() >= 4
+            && atr[0] == 0x3B
+            && atr[1] & 0xF0 == 0x80
+            && atr[2..4] == [0x80, 0x01] =>
+        {
+            "ISO 14443-4"
+        }
+        None => "Unknown",
+    }
+}
+
+/// Works out which chip is on the reader from its ATR and, for Type 2 tags,
+/// the capability container or the highest readable page.
+pub fn detect_chip<T: Transport + ?Sized>(tx: &T, atr: &[u8]) -> Result<Chip, Error> {
+    match atr_card_name(atr) {
+        Some(0x0001) => return Ok(Chip::MifareClassic1K),
+        Some(0x0002) => return Ok(Chip::MifareClassic4K),
+        Some(0x003A) => return Ok(Chip::MifareUltralightC),
+        Some(0x0003) | None => {}
+        Some(name) => {
+            return Err(Error::Tag(format!(
+                "Unsupported card name {:04X} in ATR",
+                name
+            )))
+        }
+    }
+
+    let mut response_buf = [0; 256];
+    let response = tx.transmit(&read_binary(3, 0x04), &mut response_buf)?;
+    if is_successful_response(response) && response.len() >= 6 && response[0] == 0xE1 {
+        if let Some(chip) = Chip::from_cc_size(response[2]) {
+            return Ok(chip);
+        }
+    }
+
+    // Blank or unformatted tag: probe the last page of each candidate, largest first.
+    for chip in [Chip::Ntag216, Chip::Ntag215, Chip::Ntag213] {
+        let last_page = (chip.block_count() - 1) as u8;
+        let response = tx.transmit(&read_binary(last_page, 0x04), &mut response_buf)?;
+        if is_successful_response(response) {
+            return Ok(chip);
+        }
+    }
+    Ok(Chip::MifareUltralight)
+}
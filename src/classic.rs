@@ -0,0 +1,89 @@
+use crate::chip::Chip;
+
+/// Access conditions of the four groups in a sector trailer, each packed as
+/// `0bC1C2C3`, or `None` when the inverted copies do not match.
+pub fn access_conditions(trailer: &[u8]) -> Option<[u8; 4]> {
+    if trailer.len() < 9 {
+        return None;
+    }
+    let c1 = trailer[7] >> 4;
+    let c2 = trailer[8] & 0x0F;
+    let c3 = trailer[8] >> 4;
+
+    if trailer[6] & 0x0F != !c1 & 0x0F
+        || trailer[6] >> 4 != !c2 & 0x0F
+        || trailer[7] & 0x0F != !c3 & 0x0F
+    {
+        return None;
+    }
+
+    let mut conditions = [0; 4];
+    for (group, condition) in conditions.iter_mut().enumerate() {
+        *condition = ((c1 >> group) & 1) << 2 | ((c2 >> group) & 1) << 1 | ((c3 >> group) & 1);
+    }
+    Some(conditions)
+}
+
+/// Access group (0-2 for data, 3 for the trailer) that `block` falls into.
+pub fn access_group(chip: Chip, block: usize) -> usize {
+    let sector = chip.sector_of(block);
+    let offset = block - chip.sector_first_block(sector);
+    if chip.sector_block_count(sector) == 4 {
+        offset
+    } else {
+        (offset / 5).min(3)
+    }
+}
+
+/// `true` when a data block with access condition `condition` can be
+/// written after authenticating with key A.
+pub fn key_a_can_write(condition: u8) -> bool {
+    condition == 0b000
+}
+
+/// Decodes a value block, returning its signed value and address byte, or
+/// `None` when the redundant copies do not match.
+pub fn value_block(data: &[u8]) -> Option<(i32, u8)> {
+    if data.len() != 16 {
+        return None;
+    }
+    let value = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+    let inverted = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+    let copy = u32::from_le_bytes([data[8], data[9], data[10], data[11]]);
+
+    if inverted != !value || copy != value {
+        return None;
+    }
+    if data[12] != data[14] || data[13] != data[15] || data[13] != !data[12] {
+        return None;
+    }
+    Some((value as i32, data[12]))
+}
+
+/// Describes what an access condition allows on a data block.
+pub fn describe_data_access(condition: u8) -> &'static str {
+    match condition {
+        0b000 => "read/write/increment/decrement with key A or B",
+        0b010 => "read with key A or B, never written",
+        0b100 => "read with key A or B, write with key B",
+        0b110 => "read with key A or B, write/increment with key B, decrement with key A or B",
+        0b001 => "read/decrement with key A or B, never written",
+        0b011 => "read/write with key B",
+        0b101 => "read with key B, never written",
+        _ => "no access",
+    }
+}
+
+/// Describes what an access condition allows on a sector trailer.
+pub fn describe_trailer_access(condition: u8) -> &'static str {
+    match condition {
+        0b000 => "key A writes keys, access bits read-only",
+        0b010 => "keys and access bits read-only",
+        0b100 => "key B writes keys, access bits read-only",
+        0b110 => "everything frozen",
+        0b001 => "key A writes keys and access bits (transport configuration)",
+        0b011 => "key B writes keys and access bits",
+        0b101 => "key B writes access bits, keys frozen",
+        _ => "everything frozen",
+    }
+}
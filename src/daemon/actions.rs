@@ -0,0 +1,135 @@
+use super::Tap;
+use crate::error::Error;
+use serde_json::Value;
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long an HTTP endpoint gets to answer a POST.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a matching rule does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Runs a program with the tap in `NFC_*` environment variables.
+    Command(Vec<String>),
+    /// Appends one line per tap to a file.
+    Log(String),
+    /// POSTs the tap as JSON to an `http://` URL.
+    Post(String),
+}
+
+impl Action {
+    /// Parses `{"command": [...]}`, `{"log": "path"}` or `{"post": "url"}`.
+    pub fn from_json(value: &Value) -> Result<Action, Error> {
+        if let Some(command) = value["command"].as_array() {
+            let command: Vec<String> = command
+                .iter()
+                .filter_map(|arg| arg.as_str().map(str::to_string))
+                .collect();
+            if command.is_empty() {
+                return Err(Error::Invalid(
+                    "\"command\" needs at least the program name".into(),
+                ));
+            }
+            return Ok(Action::Command(command));
+        }
+        if let Some(path) = value["log"].as_str() {
+            return Ok(Action::Log(path.to_string()));
+        }
+        if let Some(url) = value["post"].as_str() {
+            parse_http_url(url)?;
+            return Ok(Action::Post(url.to_string()));
+        }
+        Err(Error::Invalid(format!("Unknown action: {}", value)))
+    }
+
+    pub fn run(&self, tap: &Tap, rule: &str) -> Result<(), Error> {
+        match self {
+            Action::Command(command) => {
+                let status = Command::new(&command[0])
+                    .args(&command[1..])
+                    .envs(tap.env(rule))
+                    .status()?;
+                if !status.success() {
+                    return Err(
+                        io::Error::other(format!("{} exited with {}", command[0], status)).into(),
+                    );
+                }
+            }
+            Action::Log(path) => {
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                writeln!(file, "{} {}", timestamp, tap.log_line(rule))?;
+            }
+            Action::Post(url) => {
+                let mut body = tap.to_json();
+                body["rule"] = rule.into();
+                let status = post_json(url, &body.to_string())?;
+                if !(200..300).contains(&status) {
+                    return Err(
+                        io::Error::other(format!("POST {} returned HTTP {}", url, status)).into(),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits `http://host[:port][/path]` into the address to connect to, the
+/// Host header and the path.
+fn parse_http_url(url: &str) -> Result<(String, String, String), Error> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| Error::Invalid(format!("Only http:// endpoints are supported: {}", url)))?;
+    let (host, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+    if host.is_empty() {
+        return Err(Error::Invalid(format!("URL has no host: {}", url)));
+    }
+    let address = if host.contains(':') && !host.ends_with(']') {
+        host.to_string()
+    } else {
+        format!("{}:80", host)
+    };
+    Ok((address, host.to_string(), path.to_string()))
+}
+
+/// Minimal HTTP/1.1 POST; returns the status code.
+fn post_json(url: &str, body: &str) -> Result<u16, Error> {
+    let (address, host, path) = parse_http_url(url)?;
+    let mut stream = TcpStream::connect(&address)?;
+    stream.set_read_timeout(Some(HTTP_TIMEOUT))?;
+    stream.set_write_timeout(Some(HTTP_TIMEOUT))?;
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        host,
+        body.len(),
+        body
+    )?;
+
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line)?;
+    status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid HTTP response from {}", url),
+            )
+            .into()
+        })
+}
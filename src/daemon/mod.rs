@@ -0,0 +1,216 @@
+mod actions;
+mod rules;
+
+pub use actions::Action;
+pub use rules::Rule;
+
+use crate::chip::Chip;
+use crate::dump::{ndef_message, read_dump};
+use crate::error::Error;
+use crate::hex;
+use crate::ndef::{decode_uri, parse_ndef_message, NdefRecord, SmartPoster};
+use crate::transport::Transport;
+use log::error;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Debounce window used when the configuration does not set `debounce_ms`.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// What was read from a tag presented to a reader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tap {
+    pub reader: String,
+    pub uid: Vec<u8>,
+    pub chip: Chip,
+    /// NDEF records, empty when the tag holds no valid message.
+    pub records: Vec<NdefRecord>,
+}
+
+impl Tap {
+    /// Reads the UID and NDEF message of the tag behind `tx`.
+    pub fn read<T: Transport + ?Sized>(tx: &T, atr: &[u8], reader: &str) -> Result<Tap, Error> {
+        let dump = read_dump(tx, atr)?;
+        let records = ndef_message(&dump)
+            .and_then(|message| parse_ndef_message(&message).ok())
+            .unwrap_or_default();
+        Ok(Tap {
+            reader: reader.to_string(),
+            uid: dump.uid,
+            chip: dump.chip,
+            records,
+        })
+    }
+
+    /// URIs of the URI records and Smart Posters.
+    pub fn uris(&self) -> Vec<String> {
+        self.records
+            .iter()
+            .filter_map(|record| {
+                if record.is_well_known(b"U") {
+                    decode_uri(&record.payload)
+                } else if record.is_well_known(b"Sp") {
+                    SmartPoster::from_record(record)
+                        .ok()
+                        .map(|poster| poster.uri)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn mime_types(&self) -> Vec<String> {
+        self.records
+            .iter()
+            .filter_map(|record| record.mime_type().map(str::to_string))
+            .collect()
+    }
+
+    /// Environment passed to command actions.
+    pub fn env(&self, rule: &str) -> Vec<(&'static str, String)> {
+        vec![
+            ("NFC_RULE", rule.to_string()),
+            ("NFC_READER", self.reader.clone()),
+            ("NFC_UID", hex::encode(&self.uid)),
+            ("NFC_CHIP", self.chip.id().to_string()),
+            (
+                "NFC_URI",
+                self.uris().into_iter().next().unwrap_or_default(),
+            ),
+            (
+                "NFC_MIME_TYPE",
+                self.mime_types().into_iter().next().unwrap_or_default(),
+            ),
+            ("NFC_RECORDS", self.records.len().to_string()),
+        ]
+    }
+
+    /// `rule reader uid first-uri`, as written by log actions.
+    pub fn log_line(&self, rule: &str) -> String {
+        format!(
+            "{} {:?} {} {}",
+            rule,
+            self.reader,
+            hex::encode(&self.uid),
+            self.uris().into_iter().next().unwrap_or_default()
+        )
+        .trim_end()
+        .to_string()
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "reader": self.reader,
+            "uid": hex::encode(&self.uid),
+            "chip": self.chip.id(),
+            "uris": self.uris(),
+            "records": self
+                .records
+                .iter()
+                .map(|record| json!({
+                    "tnf": record.tnf,
+                    "type": String::from_utf8_lossy(&record.record_type),
+                    "summary": record.summary(),
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Matches taps against the configured rules and runs their actions.
+#[derive(Debug)]
+pub struct Daemon {
+    pub rules: Vec<Rule>,
+    pub debounce: Duration,
+    /// Last time each tag was seen on each reader.
+    last_seen: HashMap<(String, Vec<u8>), Instant>,
+    /// Threads still running the actions of earlier taps.
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Daemon {
+    pub fn new(rules: Vec<Rule>, debounce: Duration) -> Daemon {
+        Daemon {
+            rules,
+            debounce,
+            last_seen: HashMap::new(),
+            workers: Vec::new(),
+        }
+    }
+
+    /// Parses a configuration such as
+    /// `{"debounce_ms": 2000, "rules": [{"name": "door", "uids": ["04A1B2C3D4E5F6"],
+    /// "uri_prefix": "https://", "mime_type": "application/json",
+    /// "actions": [{"command": ["open-door"]}, {"log": "taps.log"},
+    /// {"post": "http://127.0.0.1:8080/tap"}]}]}`.
+    pub fn from_config(text: &str) -> Result<Daemon, Error> {
+        let root: Value = serde_json::from_str(text)?;
+        let rules = root["rules"]
+            .as_array()
+            .ok_or_else(|| Error::Invalid("Configuration has no \"rules\" array".into()))?
+            .iter()
+            .map(Rule::from_json)
+            .collect::<Result<_, _>>()?;
+        let debounce = root["debounce_ms"]
+            .as_u64()
+            .map_or(DEFAULT_DEBOUNCE, Duration::from_millis);
+        Ok(Daemon::new(rules, debounce))
+    }
+
+    pub fn load(path: &Path) -> Result<Daemon, Error> {
+        Daemon::from_config(&std::fs::read_to_string(path)?)
+    }
+
+    /// `true` when the same tag was seen on the same reader less than the
+    /// debounce window ago. Every call counts as a sighting, so a tag that
+    /// flickers in and out of the field keeps being ignored until it has
+    /// been away for the whole window.
+    pub fn is_duplicate(&mut self, tap: &Tap, now: Instant) -> bool {
+        let key = (tap.reader.clone(), tap.uid.clone());
+        let previous = self.last_seen.insert(key, now);
+        previous.is_some_and(|seen| now.duration_since(seen) < self.debounce)
+    }
+
+    /// Starts the actions of every matching rule, unless the tap is a
+    /// duplicate. The actions run in order on a thread of their own, so a
+    /// slow command or endpoint does not hold up later taps. Failed actions
+    /// are reported and do not stop the others. Returns the names of the
+    /// rules that fired.
+    pub fn handle(&mut self, tap: &Tap, now: Instant) -> Vec<String> {
+        self.workers.retain(|worker| !worker.is_finished());
+        if self.is_duplicate(tap, now) {
+            return Vec::new();
+        }
+        let rules: Vec<Rule> = self
+            .rules
+            .iter()
+            .filter(|rule| rule.matches(tap))
+            .cloned()
+            .collect();
+        let fired = rules.iter().map(|rule| rule.name.clone()).collect();
+        if !rules.is_empty() {
+            let tap = tap.clone();
+            self.workers.push(std::thread::spawn(move || {
+                for rule in &rules {
+                    for action in &rule.actions {
+                        if let Err(err) = action.run(&tap, &rule.name) {
+                            error!("Rule {}: {:?} failed: {}", rule.name, action, err);
+                        }
+                    }
+                }
+            }));
+        }
+        fired
+    }
+
+    /// Waits until the actions of every tap handled so far have finished.
+    pub fn wait(&mut self) {
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
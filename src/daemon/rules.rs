@@ -0,0 +1,66 @@
+use super::Tap;
+use crate::error::Error;
+use crate::hex;
+use serde_json::Value;
+
+use super::actions::Action;
+
+/// A rule fires when every condition it sets holds for the tap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub name: String,
+    /// Tags allowed to trigger the rule; any tag when empty.
+    pub uids: Vec<Vec<u8>>,
+    /// Some URI record of the tag must start with this prefix.
+    pub uri_prefix: Option<String>,
+    /// Some MIME record of the tag must have this type (case-insensitive).
+    pub mime_type: Option<String>,
+    pub actions: Vec<Action>,
+}
+
+impl Rule {
+    pub fn matches(&self, tap: &Tap) -> bool {
+        let uid_ok = self.uids.is_empty() || self.uids.contains(&tap.uid);
+        let uri_ok = self
+            .uri_prefix
+            .as_ref()
+            .is_none_or(|prefix| tap.uris().iter().any(|uri| uri.starts_with(prefix)));
+        let mime_ok = self.mime_type.as_ref().is_none_or(|mime_type| {
+            tap.mime_types()
+                .iter()
+                .any(|other| other.eq_ignore_ascii_case(mime_type))
+        });
+        uid_ok && uri_ok && mime_ok
+    }
+
+    /// Parses one entry of the `rules` array of the daemon configuration.
+    pub fn from_json(value: &Value) -> Result<Rule, Error> {
+        let name = value["name"].as_str().unwrap_or("unnamed").to_string();
+        let uids = match value["uids"].as_array() {
+            Some(uids) => uids
+                .iter()
+                .map(|uid| {
+                    let uid = uid.as_str().ok_or_else(|| {
+                        Error::Invalid(format!("Rule {}: UIDs must be hex strings", name))
+                    })?;
+                    hex::decode(&uid.replace([':', ' '], ""))
+                })
+                .collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+        let actions = value["actions"]
+            .as_array()
+            .ok_or_else(|| Error::Invalid(format!("Rule {} has no \"actions\" array", name)))?
+            .iter()
+            .map(Action::from_json)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Rule {
+            uids,
+            uri_prefix: value["uri_prefix"].as_str().map(str::to_string),
+            mime_type: value["mime_type"].as_str().map(str::to_string),
+            actions,
+            name,
+        })
+    }
+}
@@ -0,0 +1,424 @@
+use super::{ndef_message, regions, Dump};
+use crate::chip::{Chip, ULC_COUNTER_PAGE};
+use crate::classic::{
+    access_conditions, describe_data_access, describe_trailer_access, value_block,
+};
+use crate::error::Error;
+use crate::hex;
+use crate::lock::locked_pages;
+use crate::ndef::{parse_ndef_message, NdefRecord};
+use serde_json::{json, Value};
+use std::fmt;
+use std::fmt::Write as _;
+use std::ops::Range;
+
+/// A page or block whose contents or readability differ between two dumps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockDiff {
+    pub index: usize,
+    pub before: Vec<u8>,
+    pub after: Vec<u8>,
+    pub readable_before: bool,
+    pub readable_after: bool,
+    /// Byte ranges inside the block that changed.
+    pub ranges: Vec<Range<usize>>,
+    /// Labels of the fields the changed bytes belong to.
+    pub fields: Vec<&'static str>,
+}
+
+/// A change with a known meaning.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    NdefRecordAdded {
+        index: usize,
+        record: String,
+    },
+    NdefRecordRemoved {
+        index: usize,
+        record: String,
+    },
+    NdefRecordChanged {
+        index: usize,
+        before: String,
+        after: String,
+    },
+    CounterChanged {
+        block: usize,
+        before: i64,
+        after: i64,
+    },
+    AccessBitsChanged {
+        sector: usize,
+        before: Option<[u8; 4]>,
+        after: Option<[u8; 4]>,
+    },
+    PagesLocked {
+        pages: Vec<usize>,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::NdefRecordAdded { index, record } => {
+                write!(f, "NDEF record {} added: {}", index, record)
+            }
+            Change::NdefRecordRemoved { index, record } => {
+                write!(f, "NDEF record {} removed: {}", index, record)
+            }
+            Change::NdefRecordChanged {
+                index,
+                before,
+                after,
+            } => write!(f, "NDEF record {} changed: {} -> {}", index, before, after),
+            Change::CounterChanged {
+                block,
+                before,
+                after,
+            } => write!(
+                f,
+                "Counter in block {} went from {} to {} ({:+})",
+                block,
+                before,
+                after,
+                after - before
+            ),
+            Change::AccessBitsChanged {
+                sector,
+                before,
+                after,
+            } => {
+                write!(f, "Sector {} access bits changed", sector)?;
+                match (before, after) {
+                    (Some(before), Some(after)) => {
+                        for group in (0..4).filter(|&group| before[group] != after[group]) {
+                            let describe = if group == 3 {
+                                describe_trailer_access
+                            } else {
+                                describe_data_access
+                            };
+                            write!(
+                                f,
+                                "; group {}: {} -> {}",
+                                group,
+                                describe(before[group]),
+                                describe(after[group])
+                            )?;
+                        }
+                        Ok(())
+                    }
+                    _ => write!(f, " (invalid access bits on one side)"),
+                }
+            }
+            Change::PagesLocked { pages } => write!(f, "Pages newly locked: {:?}", pages),
+        }
+    }
+}
+
+/// Differences between two dumps of the same chip type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpDiff {
+    pub chip: Chip,
+    pub blocks: Vec<BlockDiff>,
+    pub changes: Vec<Change>,
+}
+
+impl DumpDiff {
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn to_text(&self) -> String {
+        let unit = if self.chip.is_classic() {
+            "Block"
+        } else {
+            "Page"
+        };
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{} dumps: {} {}(s) changed",
+            self.chip,
+            self.blocks.len(),
+            unit.to_lowercase()
+        );
+
+        for block in &self.blocks {
+            let ranges: Vec<String> = block
+                .ranges
+                .iter()
+                .map(|range| match range.len() {
+                    1 => range.start.to_string(),
+                    _ => format!("{}-{}", range.start, range.end - 1),
+                })
+                .collect();
+            let detail = if ranges.is_empty() {
+                "readability changed".to_string()
+            } else if block.fields.is_empty() {
+                format!("bytes {}", ranges.join(", "))
+            } else {
+                format!("bytes {}; {}", ranges.join(", "), block.fields.join(", "))
+            };
+            let _ = writeln!(
+                out,
+                "{} {}: {} -> {}  ({})",
+                unit,
+                block.index,
+                side(&block.before, block.readable_before),
+                side(&block.after, block.readable_after),
+                detail
+            );
+        }
+
+        if !self.changes.is_empty() {
+            let _ = writeln!(out, "Decoded changes:");
+            for change in &self.changes {
+                let _ = writeln!(out, "  {}", change);
+            }
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let blocks: Vec<Value> = self
+            .blocks
+            .iter()
+            .map(|block| {
+                let ranges: Vec<[usize; 2]> = block
+                    .ranges
+                    .iter()
+                    .map(|range| [range.start, range.end])
+                    .collect();
+                json!({
+                    "index": block.index,
+                    "before": hex::encode(&block.before),
+                    "after": hex::encode(&block.after),
+                    "readable_before": block.readable_before,
+                    "readable_after": block.readable_after,
+                    "ranges": ranges,
+                    "fields": block.fields,
+                })
+            })
+            .collect();
+        let changes: Vec<Value> = self.changes.iter().map(change_json).collect();
+
+        let root = json!({
+            "chip": self.chip.id(),
+            "blocks": blocks,
+            "changes": changes,
+        });
+        serde_json::to_string_pretty(&root).expect("diff JSON is always serializable") + "\n"
+    }
+}
+
+fn side(data: &[u8], readable: bool) -> String {
+    if readable {
+        hex::encode(data)
+    } else {
+        "?".repeat(data.len() * 2)
+    }
+}
+
+fn change_json(change: &Change) -> Value {
+    let mut value = json!({ "kind": change_kind(change), "description": change.to_string() });
+    let details = match change {
+        Change::NdefRecordAdded { index, record } | Change::NdefRecordRemoved { index, record } => {
+            json!({ "index": index, "record": record })
+        }
+        Change::NdefRecordChanged {
+            index,
+            before,
+            after,
+        } => json!({ "index": index, "before": before, "after": after }),
+        Change::CounterChanged {
+            block,
+            before,
+            after,
+        } => json!({ "block": block, "before": before, "after": after }),
+        Change::AccessBitsChanged {
+            sector,
+            before,
+            after,
+        } => json!({ "sector": sector, "before": before, "after": after }),
+        Change::PagesLocked { pages } => json!({ "pages": pages }),
+    };
+    if let (Value::Object(value), Value::Object(details)) = (&mut value, details) {
+        value.extend(details);
+    }
+    value
+}
+
+fn change_kind(change: &Change) -> &'static str {
+    match change {
+        Change::NdefRecordAdded { .. } => "ndef_record_added",
+        Change::NdefRecordRemoved { .. } => "ndef_record_removed",
+        Change::NdefRecordChanged { .. } => "ndef_record_changed",
+        Change::CounterChanged { .. } => "counter_changed",
+        Change::AccessBitsChanged { .. } => "access_bits_changed",
+        Change::PagesLocked { .. } => "pages_locked",
+    }
+}
+
+/// Compares two dumps block by block and decodes what the changes mean
+/// where the layout is known: NDEF records, counters, access and lock bits.
+pub fn diff_dumps(before: &Dump, after: &Dump) -> Result<DumpDiff, Error> {
+    if before.chip != after.chip {
+        return Err(Error::Invalid(format!(
+            "Cannot compare a {} dump with a {} dump",
+            before.chip, after.chip
+        )));
+    }
+
+    let chip = before.chip;
+    let block_size = chip.block_size();
+    let before_regions = regions(before);
+    let after_regions = regions(after);
+    let mut blocks = Vec::new();
+
+    for (old, new) in before.blocks.iter().zip(&after.blocks) {
+        if old.data == new.data && old.readable == new.readable {
+            continue;
+        }
+
+        let ranges = changed_ranges(&old.data, &new.data);
+        let base = old.index * block_size;
+        let mut fields = Vec::new();
+        for range in &ranges {
+            for region in before_regions.iter().chain(&after_regions) {
+                let label = region.field.label();
+                if region.overlaps(base + range.start, base + range.end) && !fields.contains(&label)
+                {
+                    fields.push(label);
+                }
+            }
+        }
+
+        blocks.push(BlockDiff {
+            index: old.index,
+            before: old.data.clone(),
+            after: new.data.clone(),
+            readable_before: old.readable,
+            readable_after: new.readable,
+            ranges,
+            fields,
+        });
+    }
+
+    let mut changes = ndef_changes(before, after);
+    if chip.is_classic() {
+        changes.extend(classic_changes(chip, &blocks));
+    } else {
+        changes.extend(type2_changes(before, after));
+    }
+
+    Ok(DumpDiff {
+        chip,
+        blocks,
+        changes,
+    })
+}
+
+fn changed_ranges(before: &[u8], after: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for index in (0..before.len()).filter(|&index| before[index] != after[index]) {
+        match ranges.last_mut() {
+            Some(range) if range.end == index => range.end = index + 1,
+            _ => ranges.push(index..index + 1),
+        }
+    }
+    ranges
+}
+
+fn ndef_records(dump: &Dump) -> Vec<NdefRecord> {
+    ndef_message(dump)
+        .and_then(|message| parse_ndef_message(&message).ok())
+        .unwrap_or_default()
+}
+
+fn ndef_changes(before: &Dump, after: &Dump) -> Vec<Change> {
+    let old = ndef_records(before);
+    let new = ndef_records(after);
+    let mut changes = Vec::new();
+
+    for index in 0..old.len().max(new.len()) {
+        match (old.get(index), new.get(index)) {
+            (Some(old), Some(new)) if old != new => changes.push(Change::NdefRecordChanged {
+                index,
+                before: old.summary(),
+                after: new.summary(),
+            }),
+            (Some(old), None) => changes.push(Change::NdefRecordRemoved {
+                index,
+                record: old.summary(),
+            }),
+            (None, Some(new)) => changes.push(Change::NdefRecordAdded {
+                index,
+                record: new.summary(),
+            }),
+            _ => {}
+        }
+    }
+    changes
+}
+
+fn type2_changes(before: &Dump, after: &Dump) -> Vec<Change> {
+    let chip = before.chip;
+    let mut changes = Vec::new();
+
+    if chip == Chip::MifareUltralightC {
+        let counter = |dump: &Dump| {
+            let data = &dump.blocks[ULC_COUNTER_PAGE].data;
+            u16::from_le_bytes([data[0], data[1]]) as i64
+        };
+        if counter(before) != counter(after) {
+            changes.push(Change::CounterChanged {
+                block: ULC_COUNTER_PAGE,
+                before: counter(before),
+                after: counter(after),
+            });
+        }
+    }
+
+    let old = locked_pages(chip, &before.bytes());
+    let new = locked_pages(chip, &after.bytes());
+    let pages: Vec<usize> = (0..old.len())
+        .filter(|&page| new[page] && !old[page])
+        .collect();
+    if !pages.is_empty() {
+        changes.push(Change::PagesLocked { pages });
+    }
+    changes
+}
+
+fn classic_changes(chip: Chip, blocks: &[BlockDiff]) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    for block in blocks {
+        if chip.is_sector_trailer(block.index) {
+            let old = access_conditions(&block.before);
+            let new = access_conditions(&block.after);
+            if block
+                .ranges
+                .iter()
+                .any(|range| range.start < 10 && range.end > 6)
+                && old != new
+            {
+                changes.push(Change::AccessBitsChanged {
+                    sector: chip.sector_of(block.index),
+                    before: old,
+                    after: new,
+                });
+            }
+        } else if let (Some((old, _)), Some((new, _))) =
+            (value_block(&block.before), value_block(&block.after))
+        {
+            changes.push(Change::CounterChanged {
+                block: block.index,
+                before: old as i64,
+                after: new as i64,
+            });
+        }
+    }
+
+    changes
+}
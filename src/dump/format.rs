@@ -0,0 +1,329 @@
+use super::layout::{regions, Region};
+use super::Dump;
+use crate::chip::Chip;
+use crate::error::Error;
+use crate::hex;
+use serde_json::{json, Map, Value};
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name written into the `Created` field of JSON dumps.
+const CREATED_BY: &str = "rust-nfc-card-reader";
+
+/// Raw memory image, one block after another.
+pub fn to_bin(dump: &Dump) -> Vec<u8> {
+    dump.bytes()
+}
+
+/// Proxmark3 emulator format: one block per line as upper-case hex.
+pub fn to_eml(dump: &Dump) -> String {
+    dump.blocks
+        .iter()
+        .map(|block| hex::encode(&block.data) + "\n")
+        .collect()
+}
+
+/// Proxmark3-compatible JSON dump (`mfu` or `mfcard` file type).
+///
+/// The `Chip` and `Unreadable` fields are extensions that Proxmark3 ignores.
+pub fn to_json(dump: &Dump) -> String {
+    let blocks: Map<String, Value> = dump
+        .blocks
+        .iter()
+        .map(|block| {
+            (
+                block.index.to_string(),
+                Value::from(hex::encode(&block.data)),
+            )
+        })
+        .collect();
+    let unreadable: Vec<usize> = dump.unreadable_blocks().collect();
+
+    let mut root = json!({
+        "Created": CREATED_BY,
+        "FileType": if dump.chip.is_classic() { "mfcard" } else { "mfu" },
+        "Card": {
+            "UID": hex::encode(&dump.uid),
+            "ATR": hex::encode(&dump.atr),
+            "Chip": dump.chip.id(),
+        },
+        "blocks": blocks,
+        "Unreadable": unreadable,
+    });
+
+    if dump.chip.is_classic() {
+        let keys: Map<String, Value> = (0..dump.chip.sector_count())
+            .map(|sector| {
+                let trailer = &dump.blocks[dump.chip.sector_trailer(sector)];
+                let key_a = dump.sector_keys[sector]
+                    .map(|key| hex::encode(&key))
+                    .unwrap_or_default();
+                (
+                    sector.to_string(),
+                    json!({
+                        "KeyA": key_a,
+                        "KeyB": hex::encode(&trailer.data[10..16]),
+                        "AccessConditions": hex::encode(&trailer.data[6..10]),
+                    }),
+                )
+            })
+            .collect();
+        root["SectorKeys"] = Value::Object(keys);
+    }
+
+    serde_json::to_string_pretty(&root).expect("dump JSON is always serializable") + "\n"
+}
+
+/// Hex listing with ASCII column and the fields each block contains.
+pub fn to_listing(dump: &Dump) -> String {
+    let chip = dump.chip;
+    let block_size = chip.block_size();
+    let regions = regions(dump);
+    let unit = if chip.is_classic() { "Block" } else { "Page" };
+
+    let mut out = String::new();
+    let _ = writeln!(out, "Chip: {} ({} bytes)", chip, chip.memory_size());
+    let _ = writeln!(out, "UID:  {}", spaced_hex(&dump.uid));
+    let _ = writeln!(out, "ATR:  {}", spaced_hex(&dump.atr));
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "{:>5}  {:<width$}  {:<ascii$}  Fields",
+        unit,
+        "Data",
+        "ASCII",
+        width = block_size * 3 - 1,
+        ascii = block_size
+    );
+
+    for block in &dump.blocks {
+        if chip.is_classic() && block.index == chip.sector_first_block(chip.sector_of(block.index))
+        {
+            let _ = writeln!(out, "-- Sector {} --", chip.sector_of(block.index));
+        }
+
+        let (data, ascii) = if block.readable {
+            (spaced_hex(&block.data), printable(&block.data))
+        } else {
+            (vec!["??"; block_size].join(" "), " ".repeat(block_size))
+        };
+
+        let start = block.index * block_size;
+        let fields = fields_in(&regions, start, start + block_size);
+        let _ = writeln!(out, "{:>5}  {}  {}  {}", block.index, data, ascii, fields);
+    }
+
+    out.lines()
+        .map(|line| line.trim_end().to_string() + "\n")
+        .collect()
+}
+
+fn fields_in(regions: &[Region], start: usize, end: usize) -> String {
+    let mut labels: Vec<&str> = Vec::new();
+    for region in regions.iter().filter(|region| region.overlaps(start, end)) {
+        let label = region.field.label();
+        if !labels.contains(&label) {
+            labels.push(label);
+        }
+    }
+    labels.join(", ")
+}
+
+fn spaced_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn printable(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            }
+        })
+        .collect()
+}
+
+/// Writes `<base>.bin`, `<base>.eml`, `<base>.json` and `<base>.txt`.
+pub fn save_dump(dump: &Dump, base: &Path) -> io::Result<Vec<PathBuf>> {
+    let with_extension = |extension: &str| {
+        let mut path = base.as_os_str().to_owned();
+        path.push(".");
+        path.push(extension);
+        PathBuf::from(path)
+    };
+
+    let files = [
+        (with_extension("bin"), to_bin(dump)),
+        (with_extension("eml"), to_eml(dump).into_bytes()),
+        (with_extension("json"), to_json(dump).into_bytes()),
+        (with_extension("txt"), to_listing(dump).into_bytes()),
+    ];
+
+    let mut written = Vec::new();
+    for (path, contents) in files {
+        fs::write(&path, contents)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Builds a dump from a raw memory image. The chip is inferred from the
+/// image size when not given.
+pub fn from_bin(bytes: &[u8], chip: Option<Chip>) -> Result<Dump, Error> {
+    let chip = match chip.or_else(|| Chip::from_memory_size(bytes.len())) {
+        Some(chip) => chip,
+        None => {
+            return Err(Error::Invalid(format!(
+                "No known chip has {} bytes of memory",
+                bytes.len()
+            )))
+        }
+    };
+    if bytes.len() != chip.memory_size() {
+        return Err(Error::Invalid(format!(
+            "{} has {} bytes of memory, the image has {}",
+            chip,
+            chip.memory_size(),
+            bytes.len()
+        )));
+    }
+
+    let mut dump = Dump::empty(chip);
+    for (block, data) in dump.blocks.iter_mut().zip(bytes.chunks(chip.block_size())) {
+        block.data.copy_from_slice(data);
+        block.readable = true;
+    }
+    fill_in_identity(&mut dump);
+    Ok(dump)
+}
+
+/// Builds a dump from a Proxmark3 `.eml` file.
+pub fn from_eml(text: &str, chip: Option<Chip>) -> Result<Dump, Error> {
+    let mut bytes = Vec::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        bytes.extend(hex::decode(line)?);
+    }
+    from_bin(&bytes, chip)
+}
+
+/// Builds a dump from a Proxmark3 JSON file, honouring the `Chip` and
+/// `Unreadable` extensions when present. Blocks must be numbered from 0
+/// without gaps and all be one block long.
+pub fn from_json(text: &str, chip: Option<Chip>) -> Result<Dump, Error> {
+    let root: Value = serde_json::from_str(text)?;
+    let blocks = match root["blocks"].as_object() {
+        Some(blocks) => blocks,
+        None => return Err(Error::Invalid("JSON dump has no \"blocks\" object".into())),
+    };
+    let chip = match (chip, root["Card"]["Chip"].as_str()) {
+        (Some(chip), _) => Some(chip),
+        (None, Some(id)) => Some(id.parse::<Chip>().map_err(Error::Invalid)?),
+        (None, None) => None,
+    };
+
+    let mut indexed = Vec::new();
+    for (index, data) in blocks {
+        let index: usize = index
+            .parse()
+            .map_err(|_| Error::Invalid(format!("Invalid block index {:?}", index)))?;
+        let data = hex::decode(data.as_str().unwrap_or_default())?;
+        indexed.push((index, data));
+    }
+    indexed.sort_by_key(|(index, _)| *index);
+
+    // Without a chip every block must at least be as long as the first.
+    let block_size = chip
+        .map(Chip::block_size)
+        .or_else(|| indexed.first().map(|(_, data)| data.len()));
+    let mut bytes = Vec::new();
+    for (position, (index, data)) in indexed.into_iter().enumerate() {
+        if index < position {
+            return Err(Error::Invalid(format!(
+                "Block {} appears more than once",
+                index
+            )));
+        }
+        if index > position {
+            return Err(Error::Invalid(format!("Block {} is missing", position)));
+        }
+        if Some(data.len()) != block_size {
+            return Err(Error::Invalid(format!(
+                "Block {} has {} bytes, expected {}",
+                index,
+                data.len(),
+                block_size.unwrap_or_default()
+            )));
+        }
+        bytes.extend(data);
+    }
+
+    let mut dump = from_bin(&bytes, chip)?;
+
+    if let Some(unreadable) = root["Unreadable"].as_array() {
+        for index in unreadable.iter().filter_map(Value::as_u64) {
+            if let Some(block) = dump.blocks.get_mut(index as usize) {
+                block.readable = false;
+            }
+        }
+    }
+    if let Some(uid) = root["Card"]["UID"].as_str() {
+        dump.uid = hex::decode(uid)?;
+    }
+    if let Some(atr) = root["Card"]["ATR"].as_str() {
+        dump.atr = hex::decode(atr)?;
+    }
+    fill_in_identity(&mut dump);
+    Ok(dump)
+}
+
+/// Loads a `.bin`, `.eml` or `.json` dump, picking the format by extension.
+pub fn load_dump(path: &Path, chip: Option<Chip>) -> Result<Dump, Error> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    match extension.as_str() {
+        "bin" => from_bin(&fs::read(path)?, chip),
+        "eml" => from_eml(&fs::read_to_string(path)?, chip),
+        "json" => from_json(&fs::read_to_string(path)?, chip),
+        _ => Err(Error::Invalid(format!(
+            "Unsupported dump format: {}",
+            path.display()
+        ))),
+    }
+}
+
+/// Recovers the UID and Classic sector keys from the memory image itself.
+fn fill_in_identity(dump: &mut Dump) {
+    let chip = dump.chip;
+    if dump.uid.is_empty() {
+        dump.uid = if chip.is_classic() {
+            dump.blocks[0].data[..4].to_vec()
+        } else {
+            let mut uid = dump.blocks[0].data[..3].to_vec();
+            uid.extend_from_slice(&dump.blocks[1].data);
+            uid
+        };
+    }
+
+    // Sectors marked unreadable after `from_bin` lose the key it found.
+    for sector in 0..chip.sector_count() {
+        let trailer = &dump.blocks[chip.sector_trailer(sector)];
+        dump.sector_keys[sector] = trailer.readable.then(|| {
+            let mut key = [0; 6];
+            key.copy_from_slice(&trailer.data[..6]);
+            key
+        });
+    }
+}
@@ -0,0 +1,273 @@
+use super::Dump;
+use crate::chip::Chip;
+use crate::tlv::{find_ndef_tlv, parse_control_tlv, parse_tlvs, LOCK_CONTROL_TLV, NULL_TLV};
+
+/// NFC Forum application id of NDEF sectors in a MIFARE Application Directory.
+const NDEF_AID: u16 = 0x03E1;
+
+/// Meaning of a run of bytes in tag memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Uid,
+    Bcc,
+    Internal,
+    StaticLock,
+    CapabilityContainer,
+    Tlv(u8),
+    DynamicLock,
+    Config,
+    Password,
+    Pack,
+    Counter,
+    Auth,
+    Key,
+    Manufacturer,
+    Mad,
+    KeyA,
+    AccessBits,
+    KeyB,
+}
+
+impl Field {
+    pub fn label(self) -> &'static str {
+        match self {
+            Field::Uid => "UID",
+            Field::Bcc => "BCC",
+            Field::Internal => "Internal",
+            Field::StaticLock => "Static lock bytes",
+            Field::CapabilityContainer => "CC",
+            Field::Tlv(tag) => match tag {
+                0x01 => "Lock Control TLV",
+                0x02 => "Memory Control TLV",
+                0x03 => "NDEF TLV",
+                0xFD => "Proprietary TLV",
+                0xFE => "Terminator TLV",
+                _ => "Unknown TLV",
+            },
+            Field::DynamicLock => "Dynamic lock bytes",
+            Field::Config => "Config",
+            Field::Password => "PWD",
+            Field::Pack => "PACK",
+            Field::Counter => "Counter",
+            Field::Auth => "AUTH",
+            Field::Key => "Key",
+            Field::Manufacturer => "Manufacturer data",
+            Field::Mad => "MAD",
+            Field::KeyA => "Key A",
+            Field::AccessBits => "Access bits",
+            Field::KeyB => "Key B",
+        }
+    }
+}
+
+/// A field located at `start..start + len` in the dump's byte image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub field: Field,
+    pub start: usize,
+    pub len: usize,
+}
+
+impl Region {
+    pub fn end(&self) -> usize {
+        self.start + self.len
+    }
+
+    /// `true` when the region overlaps `start..end`.
+    pub fn overlaps(&self, start: usize, end: usize) -> bool {
+        self.start < end && start < self.end()
+    }
+}
+
+/// Locates the UID, lock bytes, CC, TLVs, config pages and sector trailers in
+/// a dump, sorted by start offset.
+pub fn regions(dump: &Dump) -> Vec<Region> {
+    let mut regions = if dump.chip.is_classic() {
+        classic_regions(dump)
+    } else {
+        type2_regions(dump)
+    };
+    regions.sort_by_key(|region| region.start);
+    regions
+}
+
+fn region(field: Field, start: usize, len: usize) -> Region {
+    Region { field, start, len }
+}
+
+fn type2_regions(dump: &Dump) -> Vec<Region> {
+    let chip = dump.chip;
+    let bytes = dump.bytes();
+    let mut regions = vec![
+        region(Field::Uid, 0, 3),
+        region(Field::Bcc, 3, 1),
+        region(Field::Uid, 4, 4),
+        region(Field::Bcc, 8, 1),
+        region(Field::Internal, 9, 1),
+        region(Field::StaticLock, 10, 2),
+        region(Field::CapabilityContainer, 12, 4),
+    ];
+
+    let user = chip.user_pages();
+    let user_start = user.start * 4;
+    let user_end = user.end * 4;
+    let mut dynamic_lock_from_tlv = false;
+
+    for tlv in parse_tlvs(&bytes[user_start..user_end]) {
+        if tlv.tag == NULL_TLV {
+            continue;
+        }
+        let start = user_start + tlv.offset;
+        let end = (user_start + tlv.end()).min(user_end);
+        regions.push(region(Field::Tlv(tlv.tag), start, end - start));
+
+        if tlv.tag == LOCK_CONTROL_TLV {
+            if let Some(area) = parse_control_tlv(tlv.value(&bytes[user_start..user_end])) {
+                let len = area.size.div_ceil(8);
+                if area.byte_address + len <= bytes.len() {
+                    regions.push(region(Field::DynamicLock, area.byte_address, len));
+                    dynamic_lock_from_tlv = true;
+                }
+            }
+        }
+    }
+
+    if let (Some(page), false) = (chip.dynamic_lock_page(), dynamic_lock_from_tlv) {
+        let len = if chip == Chip::MifareUltralightC {
+            2
+        } else {
+            3
+        };
+        regions.push(region(Field::DynamicLock, page * 4, len));
+    }
+
+    if let Some(config) = chip.config_pages() {
+        let base = config.start * 4;
+        if chip == Chip::MifareUltralightC {
+            regions.push(region(Field::Counter, base, 2));
+            regions.push(region(Field::Auth, base + 4, 1));
+            regions.push(region(Field::Auth, base + 8, 1));
+            regions.push(region(Field::Key, base + 12, 16));
+        } else {
+            regions.push(region(Field::Config, base, 8));
+            regions.push(region(Field::Password, base + 8, 4));
+            regions.push(region(Field::Pack, base + 12, 2));
+        }
+    }
+
+    regions
+}
+
+fn classic_regions(dump: &Dump) -> Vec<Region> {
+    let chip = dump.chip;
+    let bytes = dump.bytes();
+    let mut regions = Vec::new();
+
+    let uid_len = if dump.uid.len() == 7 { 7 } else { 4 };
+    regions.push(region(Field::Uid, 0, uid_len));
+    if uid_len == 4 {
+        regions.push(region(Field::Bcc, 4, 1));
+        regions.push(region(Field::Manufacturer, 5, 11));
+    } else {
+        regions.push(region(Field::Manufacturer, 7, 9));
+    }
+
+    for sector in 0..chip.sector_count() {
+        let base = chip.sector_trailer(sector) * 16;
+        regions.push(region(Field::KeyA, base, 6));
+        regions.push(region(Field::AccessBits, base + 6, 4));
+        regions.push(region(Field::KeyB, base + 10, 6));
+    }
+
+    let Some(offsets) = data_area(dump) else {
+        return regions;
+    };
+    regions.push(region(Field::Mad, 16, 32));
+    if chip == Chip::MifareClassic4K {
+        regions.push(region(Field::Mad, 64 * 16, 48));
+    }
+
+    let data: Vec<u8> = offsets.iter().map(|&offset| bytes[offset]).collect();
+
+    for tlv in parse_tlvs(&data) {
+        if tlv.tag == NULL_TLV {
+            continue;
+        }
+        let end = tlv.end().min(data.len());
+        // Split the TLV into one region per contiguous run of bytes.
+        let mut run_start = tlv.offset;
+        for position in tlv.offset + 1..=end {
+            if position == end || offsets[position] != offsets[position - 1] + 1 {
+                regions.push(region(
+                    Field::Tlv(tlv.tag),
+                    offsets[run_start],
+                    position - run_start,
+                ));
+                run_start = position;
+            }
+        }
+    }
+
+    regions
+}
+
+/// Byte offsets, in order, of the TLV data area: the user pages of a Type 2
+/// tag, or the data blocks of the NDEF sectors listed in a MIFARE Classic
+/// MAD (trailers skipped). `None` for Classic cards without a MAD.
+pub fn data_area(dump: &Dump) -> Option<Vec<usize>> {
+    let chip = dump.chip;
+    if chip.is_type2() {
+        let user = chip.user_pages();
+        return Some((user.start * 4..user.end * 4).collect());
+    }
+
+    let sectors = mad_ndef_sectors(dump, &dump.bytes())?;
+    Some(
+        sectors
+            .into_iter()
+            .flat_map(|sector| {
+                let first = chip.sector_first_block(sector);
+                (first..chip.sector_trailer(sector)).flat_map(|block| block * 16..block * 16 + 16)
+            })
+            .collect(),
+    )
+}
+
+/// Contents of the first NDEF TLV in the dump's data area.
+pub fn ndef_message(dump: &Dump) -> Option<Vec<u8>> {
+    let bytes = dump.bytes();
+    let data: Vec<u8> = data_area(dump)?
+        .into_iter()
+        .map(|offset| bytes[offset])
+        .collect();
+    let tlv = find_ndef_tlv(&data)?;
+    Some(tlv.value(&data).to_vec())
+}
+
+/// Sectors the MIFARE Application Directory assigns to NDEF, or `None` when
+/// the card carries no MAD.
+fn mad_ndef_sectors(dump: &Dump, bytes: &[u8]) -> Option<Vec<usize>> {
+    // GPB of sector 0 has the DA (MAD available) bit set on MAD-formatted cards.
+    if !dump.blocks[3].readable || bytes[3 * 16 + 9] & 0x80 == 0 {
+        return None;
+    }
+
+    let mut sectors = Vec::new();
+    // MAD1: blocks 1-2 hold CRC, info byte, then one AID per sector 1..=15.
+    for sector in 1..16 {
+        let offset = 16 + sector * 2;
+        if u16::from_le_bytes([bytes[offset], bytes[offset + 1]]) == NDEF_AID {
+            sectors.push(sector);
+        }
+    }
+    // MAD2: sector 16 blocks 64-66 hold CRC, info byte, then sectors 17..=39.
+    if dump.chip == Chip::MifareClassic4K && dump.blocks[64].readable {
+        for sector in 17..40 {
+            let offset = 64 * 16 + (sector - 16) * 2;
+            if u16::from_le_bytes([bytes[offset], bytes[offset + 1]]) == NDEF_AID {
+                sectors.push(sector);
+            }
+        }
+    }
+    Some(sectors)
+}
@@ -0,0 +1,185 @@
+mod diff;
+mod format;
+mod layout;
+mod restore;
+
+pub use diff::{diff_dumps, BlockDiff, Change, DumpDiff};
+pub use format::{
+    from_bin, from_eml, from_json, load_dump, save_dump, to_bin, to_eml, to_json, to_listing,
+};
+pub use layout::{data_area, ndef_message, regions, Field, Region};
+pub use restore::{plan_restore, restore, Conflict, RestorePlan, SkipReason};
+
+use crate::apdu::{
+    general_authenticate, get_uid, is_successful_response, load_key, read_binary, response_data,
+};
+use crate::chip::{detect_chip, Chip};
+use crate::error::Error;
+use crate::ntag::read_pages;
+use crate::transport::Transport;
+use log::{info, warn};
+
+/// Keys tried, in order, when authenticating MIFARE Classic sectors: transport
+/// default, MAD key and NFC Forum NDEF key, then all zeros.
+pub const DEFAULT_CLASSIC_KEYS: [[u8; 6]; 4] = [
+    [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+    [0xA0, 0xA1, 0xA2, 0xA3, 0xA4, 0xA5],
+    [0xD3, 0xF7, 0xD3, 0xF7, 0xD3, 0xF7],
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+];
+
+/// One page (Type 2) or block (MIFARE Classic) of a dump.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub index: usize,
+    /// Block contents; all zeros when the block could not be read.
+    pub data: Vec<u8>,
+    pub readable: bool,
+}
+
+/// Full memory image of a tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dump {
+    pub chip: Chip,
+    pub uid: Vec<u8>,
+    pub atr: Vec<u8>,
+    pub blocks: Vec<Block>,
+    /// Key A that opened each sector, MIFARE Classic only.
+    pub sector_keys: Vec<Option<[u8; 6]>>,
+}
+
+impl Dump {
+    /// Creates a dump with every block present but unread.
+    pub fn empty(chip: Chip) -> Dump {
+        Dump {
+            chip,
+            uid: Vec::new(),
+            atr: Vec::new(),
+            blocks: (0..chip.block_count())
+                .map(|index| Block {
+                    index,
+                    data: vec![0; chip.block_size()],
+                    readable: false,
+                })
+                .collect(),
+            sector_keys: vec![None; chip.sector_count()],
+        }
+    }
+
+    /// The whole memory as one byte string; unreadable blocks read as zeros.
+    pub fn bytes(&self) -> Vec<u8> {
+        self.blocks
+            .iter()
+            .flat_map(|block| block.data.iter().copied())
+            .collect()
+    }
+
+    pub fn unreadable_blocks(&self) -> impl Iterator<Item = usize> + '_ {
+        self.blocks
+            .iter()
+            .filter(|block| !block.readable)
+            .map(|block| block.index)
+    }
+}
+
+/// Reads the full memory of the tag on `tx`, flagging pages or blocks that
+/// could not be read instead of stopping at the first failure.
+pub fn read_dump<T: Transport + ?Sized>(tx: &T, atr: &[u8]) -> Result<Dump, Error> {
+    let chip = detect_chip(tx, atr)?;
+    info!("Detected chip: {}", chip);
+
+    let mut dump = Dump::empty(chip);
+    dump.atr = atr.to_vec();
+
+    let mut response_buf = [0; 256];
+    let response = tx.transmit(&get_uid(), &mut response_buf)?;
+    if is_successful_response(response) {
+        dump.uid = response_data(response).to_vec();
+    }
+
+    if chip.is_classic() {
+        read_classic_blocks(tx, &mut dump)?;
+    } else {
+        read_type2_pages(tx, &mut dump)?;
+        if dump.uid.is_empty() && dump.blocks[0].readable && dump.blocks[1].readable {
+            dump.uid.extend_from_slice(&dump.blocks[0].data[..3]);
+            dump.uid.extend_from_slice(&dump.blocks[1].data);
+        }
+    }
+
+    info!(
+        "Finished reading card memory. Total bytes: {}, unreadable blocks: {}",
+        chip.memory_size(),
+        dump.unreadable_blocks().count()
+    );
+    Ok(dump)
+}
+
+fn read_type2_pages<T: Transport + ?Sized>(tx: &T, dump: &mut Dump) -> Result<(), Error> {
+    let pages = read_pages(tx, dump.chip, 0..dump.blocks.len())?;
+    for (block, data) in dump.blocks.iter_mut().zip(pages) {
+        if let Some(data) = data {
+            block.data.copy_from_slice(&data);
+            block.readable = true;
+        }
+    }
+    Ok(())
+}
+
+fn read_classic_blocks<T: Transport + ?Sized>(tx: &T, dump: &mut Dump) -> Result<(), Error> {
+    let chip = dump.chip;
+
+    for sector in 0..chip.sector_count() {
+        let first_block = chip.sector_first_block(sector);
+        let key = authenticate_sector(tx, first_block, &DEFAULT_CLASSIC_KEYS)?;
+        dump.sector_keys[sector] = key;
+
+        let Some(key) = key else {
+            warn!("No known key opens sector {}.", sector);
+            continue;
+        };
+
+        for index in first_block..first_block + chip.sector_block_count(sector) {
+            let mut response_buf = [0; 256];
+            let response = tx.transmit(&read_binary(index as u8, 0x10), &mut response_buf)?;
+            let block = &mut dump.blocks[index];
+
+            if is_successful_response(response) && response.len() >= 18 {
+                block.data.copy_from_slice(&response[..16]);
+                block.readable = true;
+                // Key A always reads back as zeros; fill in the one we used.
+                if chip.is_sector_trailer(index) {
+                    block.data[..6].copy_from_slice(&key);
+                }
+            } else {
+                warn!("Failed to read block {}: {:02X?}", index, response);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Tries each key as key A on the sector containing `block`, returning the
+/// first one that authenticates.
+pub fn authenticate_sector<T: Transport + ?Sized>(
+    tx: &T,
+    block: usize,
+    keys: &[[u8; 6]],
+) -> Result<Option<[u8; 6]>, Error> {
+    for key in keys {
+        let mut response_buf = [0; 256];
+        let response = tx.transmit(&load_key(0x00, key), &mut response_buf)?;
+        if !is_successful_response(response) {
+            return Err(Error::status("Reader refused to load key", response));
+        }
+
+        let response = tx.transmit(
+            &general_authenticate(block as u8, 0x60, 0x00),
+            &mut response_buf,
+        )?;
+        if is_successful_response(response) {
+            return Ok(Some(*key));
+        }
+    }
+    Ok(None)
+}
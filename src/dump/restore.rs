@@ -0,0 +1,257 @@
+use super::{authenticate_sector, Block, Dump};
+use crate::apdu::{is_successful_response, log_response, update_binary};
+use crate::chip::Chip;
+use crate::classic::{access_conditions, access_group, key_a_can_write};
+use crate::error::Error;
+use crate::lock::locked_pages;
+use crate::transport::Transport;
+use log::info;
+use std::fmt;
+
+/// Why a block of the source dump is not written to the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    Uid,
+    LockBytes,
+    Config,
+    SectorTrailer,
+    Unreadable,
+    Unchanged,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SkipReason::Uid => "UID / manufacturer data",
+            SkipReason::LockBytes => "lock bytes",
+            SkipReason::Config => "configuration",
+            SkipReason::SectorTrailer => "sector trailer",
+            SkipReason::Unreadable => "unreadable in dump",
+            SkipReason::Unchanged => "already matches",
+        })
+    }
+}
+
+/// Something on the target tag that would make the restore fail part-way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conflict {
+    Locked { block: usize },
+    PasswordProtected { block: usize, auth0: u8 },
+    OneTimeProgrammable { block: usize },
+    SectorInaccessible { sector: usize },
+    WriteForbidden { block: usize },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::Locked { block } => write!(f, "Page {} is locked", block),
+            Conflict::PasswordProtected { block, auth0 } => write!(
+                f,
+                "Page {} is password protected (AUTH0 = {:#04X})",
+                block, auth0
+            ),
+            Conflict::OneTimeProgrammable { block } => write!(
+                f,
+                "Page {} has one-time-programmable bits set that the dump does not",
+                block
+            ),
+            Conflict::SectorInaccessible { sector } => {
+                write!(f, "Sector {} cannot be opened with any known key", sector)
+            }
+            Conflict::WriteForbidden { block } => {
+                write!(f, "Block {} access bits forbid writing with key A", block)
+            }
+        }
+    }
+}
+
+/// What a restore will do, worked out before anything is written.
+#[derive(Debug, Clone, Default)]
+pub struct RestorePlan {
+    pub writes: Vec<Block>,
+    pub skipped: Vec<(usize, SkipReason)>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Compares the `source` dump with a fresh dump of the `target` tag and
+/// decides which blocks to write. UID, lock and config pages and sector
+/// trailers are never written; locked or protected target blocks are
+/// reported as conflicts.
+pub fn plan_restore(source: &Dump, target: &Dump) -> Result<RestorePlan, Error> {
+    if source.chip != target.chip {
+        return Err(Error::Tag(format!(
+            "Dump is from a {} but the target tag is a {}",
+            source.chip, target.chip
+        )));
+    }
+
+    if source.chip.is_classic() {
+        Ok(plan_classic(source, target))
+    } else {
+        Ok(plan_type2(source, target))
+    }
+}
+
+fn plan_type2(source: &Dump, target: &Dump) -> RestorePlan {
+    let chip = source.chip;
+    let target_bytes = target.bytes();
+    let locked = locked_pages(chip, &target_bytes);
+    let auth0 = password_protected_from(chip, &target_bytes);
+    let mut plan = RestorePlan::default();
+
+    for (block, current) in source.blocks.iter().zip(&target.blocks) {
+        let page = block.index;
+        let reason = if page < 2 {
+            Some(SkipReason::Uid)
+        } else if page == 2 || Some(page) == chip.dynamic_lock_page() {
+            Some(SkipReason::LockBytes)
+        } else if chip
+            .config_pages()
+            .is_some_and(|pages| pages.contains(&page))
+        {
+            Some(SkipReason::Config)
+        } else if !block.readable {
+            Some(SkipReason::Unreadable)
+        } else if current.readable && current.data == block.data {
+            Some(SkipReason::Unchanged)
+        } else {
+            None
+        };
+
+        if let Some(reason) = reason {
+            plan.skipped.push((page, reason));
+            continue;
+        }
+
+        // The CC is one-time programmable: bits can be set but never cleared.
+        if page == 3
+            && current
+                .data
+                .iter()
+                .zip(&block.data)
+                .any(|(t, s)| t & !s != 0)
+        {
+            plan.conflicts
+                .push(Conflict::OneTimeProgrammable { block: page });
+        } else if locked[page] {
+            plan.conflicts.push(Conflict::Locked { block: page });
+        } else if let Some(auth0) = auth0.filter(|&auth0| page >= auth0 as usize) {
+            plan.conflicts
+                .push(Conflict::PasswordProtected { block: page, auth0 });
+        }
+        plan.writes.push(block.clone());
+    }
+    plan
+}
+
+/// AUTH0 of the target when it protects at least one existing page.
+fn password_protected_from(chip: Chip, memory: &[u8]) -> Option<u8> {
+    let auth0 = match chip {
+        Chip::MifareUltralightC => memory[0x2A * 4],
+        _ if chip.is_ntag() => memory[chip.config_pages()?.start * 4 + 3],
+        _ => return None,
+    };
+    ((auth0 as usize) < chip.block_count()).then_some(auth0)
+}
+
+fn plan_classic(source: &Dump, target: &Dump) -> RestorePlan {
+    let chip = source.chip;
+    let mut plan = RestorePlan::default();
+
+    for sector in 0..chip.sector_count() {
+        let first_block = chip.sector_first_block(sector);
+        let trailer = &target.blocks[chip.sector_trailer(sector)];
+        let conditions = access_conditions(&trailer.data);
+        let mut inaccessible_reported = false;
+
+        for block in &source.blocks[first_block..=chip.sector_trailer(sector)] {
+            let current = &target.blocks[block.index];
+            let reason = if block.index == 0 {
+                Some(SkipReason::Uid)
+            } else if chip.is_sector_trailer(block.index) {
+                Some(SkipReason::SectorTrailer)
+            } else if !block.readable {
+                Some(SkipReason::Unreadable)
+            } else if current.readable && current.data == block.data {
+                Some(SkipReason::Unchanged)
+            } else {
+                None
+            };
+
+            if let Some(reason) = reason {
+                plan.skipped.push((block.index, reason));
+                continue;
+            }
+
+            if target.sector_keys[sector].is_none() {
+                if !inaccessible_reported {
+                    plan.conflicts.push(Conflict::SectorInaccessible { sector });
+                    inaccessible_reported = true;
+                }
+            } else if !conditions.is_some_and(|conditions| {
+                key_a_can_write(conditions[access_group(chip, block.index)])
+            }) {
+                plan.conflicts
+                    .push(Conflict::WriteForbidden { block: block.index });
+            }
+            plan.writes.push(block.clone());
+        }
+    }
+    plan
+}
+
+/// Writes the blocks of `plan` to the tag. Refuses to start when the plan
+/// has conflicts, so a tag is never left half-written for a known reason.
+pub fn restore<T: Transport + ?Sized>(
+    tx: &T,
+    plan: &RestorePlan,
+    target: &Dump,
+) -> Result<(), Error> {
+    if let Some(Conflict::Locked { block }) = plan
+        .conflicts
+        .iter()
+        .find(|conflict| matches!(conflict, Conflict::Locked { .. }))
+    {
+        return Err(Error::LockedPage(*block));
+    }
+    if !plan.conflicts.is_empty() {
+        return Err(Error::Tag(format!(
+            "Restore aborted: {} conflict(s) on the target tag",
+            plan.conflicts.len()
+        )));
+    }
+
+    let chip = target.chip;
+    let mut authenticated_sector = None;
+
+    for block in &plan.writes {
+        if chip.is_classic() {
+            let sector = chip.sector_of(block.index);
+            if authenticated_sector != Some(sector) {
+                let keys: Vec<[u8; 6]> = target.sector_keys[sector].into_iter().collect();
+                if authenticate_sector(tx, block.index, &keys)?.is_none() {
+                    return Err(Error::AuthFailed(format!("No key opens sector {}", sector)));
+                }
+                authenticated_sector = Some(sector);
+            }
+        }
+
+        let mut response_buf = [0; 256];
+        let response = tx.transmit(
+            &update_binary(block.index as u8, &block.data),
+            &mut response_buf,
+        )?;
+        log_response(&format!("Write block {} response", block.index), response);
+
+        if !is_successful_response(response) {
+            return Err(Error::status(
+                format!("Failed to write block {}", block.index),
+                response,
+            ));
+        }
+    }
+
+    info!("Restored {} block(s).", plan.writes.len());
+    Ok(())
+}
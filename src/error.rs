@@ -0,0 +1,82 @@
+use crate::chip::Chip;
+
+/// Everything the library can fail with, grouped so callers can tell a
+/// missing card from a refused password or a malformed NDEF message.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// No reader is connected, or the named one is gone.
+    #[error("No card reader available")]
+    NoReader,
+    #[error("No card on the reader")]
+    NoCard,
+    #[error("The card was removed")]
+    CardRemoved,
+    /// Another application holds the card exclusively.
+    #[error("The card is in use by another application")]
+    SharingViolation,
+    /// Any other PC/SC failure.
+    #[error("PC/SC error: {0}")]
+    Pcsc(#[source] pcsc::Error),
+
+    /// The reader or card answered an APDU with a failure status word.
+    #[error("{context}: status {:02X} {:02X}", sw[0], sw[1])]
+    Status { context: String, sw: [u8; 2] },
+    /// The reader answered a pass-through command in a form it should not.
+    #[error("{0}")]
+    Reader(String),
+    /// PWD_AUTH, a Classic key or a PACK check was refused.
+    #[error("Authentication failed: {0}")]
+    AuthFailed(String),
+    #[error("Page {0} is locked")]
+    LockedPage(usize),
+    #[error("{needed} byte message does not fit on {chip} ({capacity} bytes)")]
+    CapacityExceeded {
+        needed: usize,
+        capacity: usize,
+        chip: Chip,
+    },
+    /// The tag refused or garbled a command, or does not support it.
+    #[error("{0}")]
+    Tag(String),
+
+    /// Malformed or invalid NDEF data.
+    #[error("{0}")]
+    Ndef(String),
+    /// Bad arguments or file contents: hex, job lists, rules, dumps.
+    #[error("{0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+impl Error {
+    /// A failed `response`, keeping its status word.
+    pub fn status(context: impl Into<String>, response: &[u8]) -> Error {
+        let sw = match response {
+            [.., sw1, sw2] => [*sw1, *sw2],
+            _ => [0; 2],
+        };
+        Error::Status {
+            context: context.into(),
+            sw,
+        }
+    }
+}
+
+impl From<pcsc::Error> for Error {
+    fn from(err: pcsc::Error) -> Error {
+        match err {
+            pcsc::Error::NoReadersAvailable
+            | pcsc::Error::UnknownReader
+            | pcsc::Error::ReaderUnavailable => Error::NoReader,
+            pcsc::Error::NoSmartcard => Error::NoCard,
+            pcsc::Error::RemovedCard => Error::CardRemoved,
+            pcsc::Error::SharingViolation => Error::SharingViolation,
+            // What PasswordTransport reports when its PWD_AUTH is refused.
+            pcsc::Error::CardNotAuthenticated => Error::AuthFailed(err.to_string()),
+            err => Error::Pcsc(err),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use crate::error::Error;
+
+/// Upper-case hex without separators, e.g. `04A1B2`.
+pub fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+/// Parses hex digits, ignoring whitespace.
+pub fn decode(text: &str) -> Result<Vec<u8>, Error> {
+    let digits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err(Error::Invalid(format!(
+            "Odd number of hex digits in {:?}",
+            text
+        )));
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair: String = pair.iter().collect();
+            u8::from_str_radix(&pair, 16)
+                .map_err(|_| Error::Invalid(format!("Invalid hex byte {:?}", pair)))
+        })
+        .collect()
+}
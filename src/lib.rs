@@ -0,0 +1,24 @@
+pub mod apdu;
+#[cfg(feature = "http-api")]
+pub mod api;
+#[cfg(feature = "async")]
+pub mod async_api;
+pub mod batch;
+pub mod chip;
+pub mod classic;
+pub mod daemon;
+pub mod dump;
+pub mod error;
+pub mod hex;
+pub mod lock;
+pub mod monitor;
+pub mod ndef;
+pub mod ntag;
+pub mod passthrough;
+pub mod session;
+pub mod sim;
+pub mod tlv;
+pub mod trace;
+pub mod transport;
+
+pub use error::Error;
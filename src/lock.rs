@@ -0,0 +1,334 @@
+use crate::chip::Chip;
+use crate::error::Error;
+use crate::ntag::{read_page, write_page};
+use crate::tlv::{parse_control_tlv, parse_tlvs, ControlArea, LOCK_CONTROL_TLV};
+use crate::transport::Transport;
+use log::{debug, info, warn};
+use std::ops::Range;
+
+/// Byte address of the two static lock bytes (page 2, bytes 2-3).
+pub const STATIC_LOCK_ADDRESS: usize = 10;
+
+/// Block-lock bits of the first static lock byte; once set they freeze the
+/// page lock bits of page 3, pages 4-9 and pages 10-15.
+pub const STATIC_BLOCK_LOCK_BITS: u8 = 0x07;
+
+/// Number of page lock bits in the static lock bytes (page 3, pages 4-15).
+const STATIC_PAGE_LOCK_BITS: usize = 13;
+
+/// A lock bit and the pages it write-protects once set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockBit {
+    /// Byte address of the lock byte in tag memory.
+    pub byte_address: usize,
+    pub bit: u8,
+    pub pages: Range<usize>,
+}
+
+impl LockBit {
+    pub fn is_set(&self, memory: &[u8]) -> bool {
+        memory
+            .get(self.byte_address)
+            .is_some_and(|byte| byte & (1 << self.bit) != 0)
+    }
+}
+
+/// Page lock bits of a Type 2 `chip`: the static bits in page 2 followed by
+/// the dynamic bits of the chip's default layout.
+pub fn lock_bits(chip: Chip) -> Vec<LockBit> {
+    let mut bits = Vec::new();
+    if !chip.is_type2() {
+        return bits;
+    }
+
+    // Static lock byte 0: bit 3 locks the CC, bits 4-7 lock pages 4-7.
+    for bit in 3..8 {
+        let page = bit as usize;
+        bits.push(LockBit {
+            byte_address: STATIC_LOCK_ADDRESS,
+            bit,
+            pages: page..page + 1,
+        });
+    }
+    // Static lock byte 1: bits 0-7 lock pages 8-15.
+    for bit in 0..8 {
+        let page = 8 + bit as usize;
+        bits.push(LockBit {
+            byte_address: STATIC_LOCK_ADDRESS + 1,
+            bit,
+            pages: page..page + 1,
+        });
+    }
+
+    if let Some(lock_page) = chip.dynamic_lock_page() {
+        bits.extend(dynamic_lock_bits(chip, lock_page * 4));
+    }
+    bits
+}
+
+/// Dynamic lock bits starting at `byte_address`, covering the user pages
+/// beyond page 15 in the chip's lock granularity.
+pub fn dynamic_lock_bits(chip: Chip, byte_address: usize) -> Vec<LockBit> {
+    let pages_per_bit = chip.dynamic_lock_pages_per_bit();
+    let user_end = chip.user_pages().end;
+    let mut bits = Vec::new();
+
+    if chip == Chip::MifareUltralightC {
+        // Bits 0 and 4 are block-lock bits; the others lock four pages each.
+        for (index, bit) in [1, 2, 3, 5, 6, 7].into_iter().enumerate() {
+            let start = 16 + index * pages_per_bit;
+            bits.push(LockBit {
+                byte_address,
+                bit,
+                pages: start..(start + pages_per_bit).min(user_end),
+            });
+        }
+        return bits;
+    }
+
+    let mut start = 16;
+    let mut index = 0;
+    while pages_per_bit > 0 && start < user_end {
+        bits.push(LockBit {
+            byte_address: byte_address + index / 8,
+            bit: (index % 8) as u8,
+            pages: start..(start + pages_per_bit).min(user_end),
+        });
+        start += pages_per_bit;
+        index += 1;
+    }
+    bits
+}
+
+/// Pages that can no longer be written: the UID pages plus every page
+/// covered by a set lock bit in `memory`. Dynamic lock bits are found as in
+/// [`tag_dynamic_lock_bits`].
+pub fn locked_pages(chip: Chip, memory: &[u8]) -> Vec<bool> {
+    let mut locked = vec![false; chip.block_count()];
+    if !chip.is_type2() {
+        return locked;
+    }
+
+    locked[0] = true;
+    locked[1] = true;
+    let static_bits = lock_bits(chip).into_iter().take(STATIC_PAGE_LOCK_BITS);
+    for lock_bit in static_bits.chain(tag_dynamic_lock_bits(chip, memory)) {
+        if lock_bit.is_set(memory) {
+            for page in lock_bit.pages {
+                locked[page] = true;
+            }
+        }
+    }
+    locked
+}
+
+/// Dynamic lock bits of the tag whose memory is `memory`: from its Lock
+/// Control TLV when there is one, and from the chip's default layout
+/// otherwise. A TLV whose lock bytes are not inside the chip's dynamic lock
+/// page, or past the end of `memory`, is ignored, so a malformed tag can
+/// never have lock bits set over its data.
+pub fn tag_dynamic_lock_bits(chip: Chip, memory: &[u8]) -> Vec<LockBit> {
+    let Some(lock_page) = chip.dynamic_lock_page() else {
+        return Vec::new();
+    };
+    let lock_bytes = lock_page * 4..(lock_page * 4 + 4).min(memory.len());
+    let user = chip.user_pages();
+    let tlv_area = memory
+        .get(user.start * 4..user.end * 4)
+        .and_then(|data| {
+            parse_tlvs(data)
+                .into_iter()
+                .find(|tlv| tlv.tag == LOCK_CONTROL_TLV)
+                .and_then(|tlv| parse_control_tlv(tlv.value(data)))
+        })
+        .filter(|area| {
+            lock_bytes.start <= area.byte_address
+                && area.byte_address + area.size.div_ceil(8) <= lock_bytes.end
+        });
+    match tlv_area {
+        Some(area) => dynamic_lock_bits_from_tlv(chip, &area),
+        None => dynamic_lock_bits(chip, lock_page * 4),
+    }
+}
+
+/// Dynamic lock bits described by a Lock Control TLV, limited to the
+/// chip's user pages beyond page 15.
+pub fn dynamic_lock_bits_from_tlv(chip: Chip, area: &ControlArea) -> Vec<LockBit> {
+    let user_end = chip.user_pages().end * 4;
+    (0..area.size)
+        .map(|index| {
+            let start = 64 + index * area.bytes_locked_per_bit;
+            let end = (start + area.bytes_locked_per_bit).min(user_end);
+            LockBit {
+                byte_address: area.byte_address + index / 8,
+                bit: (index % 8) as u8,
+                pages: start / 4..end.div_ceil(4),
+            }
+        })
+        .filter(|lock_bit| !lock_bit.pages.is_empty())
+        .collect()
+}
+
+/// Capability container value for "no write access" (CC byte 3).
+pub const CC_READ_ONLY: u8 = 0x0F;
+
+/// One page write performed by [`make_read_only`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockWrite {
+    pub page: usize,
+    pub before: [u8; 4],
+    pub after: [u8; 4],
+    /// What each newly set bit does, as `(byte index in page, bit, meaning)`.
+    pub bits: Vec<(usize, u8, String)>,
+}
+
+/// The writes that make a Type 2 tag read-only, in the order they must
+/// happen: CC first, then dynamic lock bits, then the static lock bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadOnlyPlan {
+    pub chip: Chip,
+    pub writes: Vec<LockWrite>,
+}
+
+impl ReadOnlyPlan {
+    /// Lists every byte that changes and every bit that will be set.
+    pub fn describe(&self) -> String {
+        let mut out = format!(
+            "Making {} read-only is irreversible. Planned writes:\n",
+            self.chip
+        );
+        for write in &self.writes {
+            out += &format!(
+                "  Page {}: {:02X?} -> {:02X?}\n",
+                write.page, write.before, write.after
+            );
+            for (byte, bit, meaning) in &write.bits {
+                out += &format!("    byte {} bit {}: {}\n", byte, bit, meaning);
+            }
+        }
+        if self.writes.is_empty() {
+            out += "  Nothing to do, the tag is already read-only.\n";
+        }
+        out
+    }
+}
+
+/// Works out which bits make the tag read-only from a copy of its `memory`,
+/// which must reach the dynamic lock page. Dynamic lock bits come from
+/// [`tag_dynamic_lock_bits`].
+pub fn plan_read_only(chip: Chip, memory: &[u8]) -> Result<ReadOnlyPlan, Error> {
+    if !chip.is_type2() {
+        return Err(Error::Tag(format!("{} is not a Type 2 tag", chip)));
+    }
+    let lock_page = chip.dynamic_lock_page().unwrap_or(STATIC_LOCK_ADDRESS / 4);
+    if memory.len() < (chip.user_pages().end * 4).max(lock_page * 4 + 4) {
+        return Err(Error::Tag("Tag memory copy is too short".into()));
+    }
+
+    let mut pending: Vec<(usize, u8, String)> = Vec::new();
+
+    // CC byte 3: write access 0x0F. The CC is OTP, so bits are only ever added.
+    for bit in 0..4 {
+        pending.push((
+            15,
+            bit,
+            "CC write access 0x0F (no write access)".to_string(),
+        ));
+    }
+
+    for lock_bit in tag_dynamic_lock_bits(chip, memory) {
+        pending.push((
+            lock_bit.byte_address,
+            lock_bit.bit,
+            format!(
+                "locks pages {}-{}",
+                lock_bit.pages.start,
+                lock_bit.pages.end - 1
+            ),
+        ));
+    }
+
+    let block_locks = [
+        "freezes the CC lock bit",
+        "freezes the lock bits of pages 4-9",
+        "freezes the lock bits of pages 10-15",
+    ];
+    for (bit, meaning) in block_locks.into_iter().enumerate() {
+        pending.push((STATIC_LOCK_ADDRESS, bit as u8, meaning.to_string()));
+    }
+    for lock_bit in lock_bits(chip).into_iter().take(STATIC_PAGE_LOCK_BITS) {
+        let meaning = if lock_bit.pages.start == 3 {
+            "locks page 3 (CC)".to_string()
+        } else {
+            format!("locks page {}", lock_bit.pages.start)
+        };
+        pending.push((lock_bit.byte_address, lock_bit.bit, meaning));
+    }
+
+    let mut writes: Vec<LockWrite> = Vec::new();
+    for (byte_address, bit, meaning) in pending {
+        let page = byte_address / 4;
+        if memory[byte_address] & (1 << bit) != 0 {
+            continue;
+        }
+        if writes.last().map(|write| write.page) != Some(page) {
+            let mut before = [0; 4];
+            before.copy_from_slice(&memory[page * 4..page * 4 + 4]);
+            writes.push(LockWrite {
+                page,
+                before,
+                after: before,
+                bits: Vec::new(),
+            });
+        }
+        let write = writes.last_mut().expect("pushed above");
+        write.after[byte_address % 4] |= 1 << bit;
+        write.bits.push((byte_address % 4, bit, meaning));
+    }
+
+    Ok(ReadOnlyPlan { chip, writes })
+}
+
+/// Permanently write-protects a Type 2 tag: CC write access 0x0F, dynamic
+/// lock bits and static lock bytes.
+///
+/// Nothing is written when `dry_run` is set or when `confirm` returns
+/// `false` for the plan; the plan is returned either way.
+pub fn make_read_only<T, F>(
+    tx: &T,
+    chip: Chip,
+    dry_run: bool,
+    confirm: F,
+) -> Result<ReadOnlyPlan, Error>
+where
+    T: Transport + ?Sized,
+    F: FnOnce(&ReadOnlyPlan) -> bool,
+{
+    let mut memory = vec![0; chip.user_pages().end * 4];
+    for page in 0..chip.user_pages().end {
+        memory[page * 4..page * 4 + 4].copy_from_slice(&read_page(tx, page)?);
+    }
+    if let Some(page) = chip.dynamic_lock_page() {
+        memory.resize((page + 1) * 4, 0);
+        memory[page * 4..page * 4 + 4].copy_from_slice(&read_page(tx, page)?);
+    }
+
+    let plan = plan_read_only(chip, &memory)?;
+    debug!("Read-only plan:\n{}", plan.describe().trim_end());
+
+    if dry_run {
+        info!("Dry run: nothing written.");
+        return Ok(plan);
+    }
+    if !confirm(&plan) {
+        warn!("Not confirmed: nothing written.");
+        return Ok(plan);
+    }
+
+    for write in &plan.writes {
+        write_page(tx, write.page, &write.after)?;
+        debug!("Page {} written.", write.page);
+    }
+    info!("{} is now read-only.", chip);
+    Ok(plan)
+}
@@ -1,72 +1,621 @@
 use pcsc::*;
-fn start_reading() -> Result<(), Box<dyn std::error::Error>> {
+use rust_nfc_card_reader::batch::{self, Batch, Status};
+use rust_nfc_card_reader::chip::{detect_chip, Chip};
+use rust_nfc_card_reader::daemon::{Daemon, Tap};
+use rust_nfc_card_reader::dump::{
+    diff_dumps, load_dump, ndef_message, plan_restore, read_dump, restore, save_dump, to_listing,
+    Conflict, SkipReason,
+};
+use rust_nfc_card_reader::lock::make_read_only;
+use rust_nfc_card_reader::monitor::{watch_readers, ReaderEvent};
+use rust_nfc_card_reader::ndef::{
+    encode_ndef_message, parse_ndef_message_strict, Contact, NdefRecord, VcardVersion,
+    WifiCredential,
+};
+use rust_nfc_card_reader::ntag::{self, MirrorMode, Password, PasswordTransport, Protection};
+use rust_nfc_card_reader::passthrough::ReaderTransport;
+use rust_nfc_card_reader::trace::Tracer;
+use rust_nfc_card_reader::transport::Transport;
+use rust_nfc_card_reader::Error;
+use std::io::Write;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+/// Set by `--trace`; every transport opened afterwards records into it.
+static TRACER: OnceLock<Arc<Tracer>> = OnceLock::new();
+
+fn start_reading(password: Option<Password>) -> Result<(), Error> {
     print!("Starting reading... ");
 
     let ctx = Context::establish(Scope::User)?;
     println!("PC/SC context established.");
 
+    let (mut card, atr, reader) = connect_first_card(&ctx)?;
+    println!("Card connected.");
+
+    let tx: Transaction = card.transaction()?;
+    println!("Transaction started.");
+
+    let dump = read_dump(&*open_transport(&tx, &reader, password), &atr)?;
+
+    print!("{}", to_listing(&dump));
+    if let Some(message) = ndef_message(&dump) {
+        match parse_ndef_message_strict(&message) {
+            Ok(records) => {
+                for (index, record) in records.iter().enumerate() {
+                    println!("NDEF record {}: {}", index, record.summary());
+                }
+            }
+            Err(violations) => {
+                for violation in violations {
+                    println!("Invalid NDEF message, {}", violation);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Wraps the transaction with the pass-through that suits `reader` and, when
+/// a password was given on the command line, authenticates before reads and
+/// writes. With `--trace` the raw exchanges are recorded underneath both.
+fn open_transport<'a>(
+    tx: &'a Transaction<'a>,
+    reader: &str,
+    password: Option<Password>,
+) -> Box<dyn Transport + 'a> {
+    let card: Box<dyn Transport + 'a> = match TRACER.get() {
+        Some(tracer) => Box::new(tracer.wrap(tx, reader)),
+        None => Box::new(tx),
+    };
+    let transport = ReaderTransport::new(card, reader);
+    log::debug!("Pass-through: {}", transport.pass_through().name());
+    match password {
+        Some(password) => Box::new(PasswordTransport::new(transport, password)),
+        None => Box::new(transport),
+    }
+}
+
+/// Connects to the card on the first reader and returns it with its ATR and
+/// the reader name.
+fn connect_first_card(ctx: &Context) -> Result<(Card, Vec<u8>, String), Error> {
     let mut readers_buf = [0; 2048];
-    let mut readers = ctx.list_readers(&mut readers_buf)?;
-    let reader = match readers.next() {
+    let reader = match ctx.list_readers(&mut readers_buf)?.next() {
         Some(reader) => reader,
-        None => {
-            println!("No readers are connected.");
-            return Ok(());
-        }
+        None => return Err(Error::NoReader),
     };
     println!("Using reader: {:?}", reader);
 
-    let mut card = ctx.connect(reader, ShareMode::Shared, Protocols::ANY)?;
-    println!("Card connected.");
+    let card = ctx.connect(reader, ShareMode::Shared, Protocols::ANY)?;
+    let atr = card.status2_owned()?.atr().to_vec();
+    Ok((card, atr, reader.to_string_lossy().into_owned()))
+}
 
-    let mut tx: Transaction = card.transaction()?;
-    println!("Transaction started.");
+/// Dumps the card on the first reader to `<base>.bin`, `.eml`, `.json` and `.txt`.
+fn dump_card(base: &Path, password: Option<Password>) -> Result<(), Error> {
+    let ctx = Context::establish(Scope::User)?;
+    let (mut card, atr, reader) = connect_first_card(&ctx)?;
+    let tx = card.transaction()?;
+
+    let dump = read_dump(&*open_transport(&tx, &reader, password), &atr)?;
+    for path in save_dump(&dump, base)? {
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+/// Writes a saved dump to the card on the first reader, checking the target
+/// for locked or protected blocks before anything is written.
+fn restore_card(path: &Path, chip: Option<Chip>, password: Option<Password>) -> Result<(), Error> {
+    let source = load_dump(path, chip)?;
+    println!("Loaded {} dump of UID {:02X?}", source.chip, source.uid);
+
+    let ctx = Context::establish(Scope::User)?;
+    let (mut card, atr, reader) = connect_first_card(&ctx)?;
+    let tx = card.transaction()?;
+    let transport = open_transport(&tx, &reader, password);
+
+    let target = read_dump(&*transport, &atr)?;
+    let mut plan = plan_restore(&source, &target)?;
+    if password.is_some() {
+        // Protected pages are writable once the transport has authenticated.
+        plan.conflicts
+            .retain(|conflict| !matches!(conflict, Conflict::PasswordProtected { .. }));
+    }
+
+    for (block, reason) in plan
+        .skipped
+        .iter()
+        .filter(|(_, reason)| *reason != SkipReason::Unchanged)
+    {
+        println!("Skipping block {}: {}", block, reason);
+    }
+    for conflict in &plan.conflicts {
+        eprintln!("Conflict: {}", conflict);
+    }
+    println!("{} block(s) to write.", plan.writes.len());
+
+    restore(&*transport, &plan, &target)
+}
+
+/// Password-protects the NTAG on the first reader from page `auth0` on.
+fn protect_card(
+    password: Option<Password>,
+    auth0: u8,
+    protection: Protection,
+) -> Result<(), Error> {
+    let password =
+        password.ok_or_else(|| Error::Invalid("protect needs --password PWD[:PACK]".into()))?;
+    let ctx = Context::establish(Scope::User)?;
+    let (mut card, atr, reader) = connect_first_card(&ctx)?;
+    let tx = card.transaction()?;
+
+    let transport = open_transport(&tx, &reader, None);
+    let chip = detect_chip(&*transport, &atr)?;
+    ntag::protect(&*transport, chip, &password, auth0, protection)?;
+    println!("{} protected from page {} ({:?}).", chip, auth0, protection);
+    Ok(())
+}
+
+/// Authenticates and removes password protection from the NTAG on the first reader.
+fn unprotect_card(password: Option<Password>) -> Result<(), Error> {
+    let password =
+        password.ok_or_else(|| Error::Invalid("unprotect needs --password PWD[:PACK]".into()))?;
+    let ctx = Context::establish(Scope::User)?;
+    let (mut card, atr, reader) = connect_first_card(&ctx)?;
+    let tx = card.transaction()?;
+
+    let transport = open_transport(&tx, &reader, Some(password));
+    let chip = detect_chip(&*transport, &atr)?;
+    ntag::remove_protection(&*transport, chip)?;
+    println!("{} protection removed.", chip);
+    Ok(())
+}
+
+/// Permanently write-protects the Type 2 tag on the first reader. Without
+/// `--yes` the user has to type `LOCK` to go ahead.
+fn lock_card(password: Option<Password>, dry_run: bool, yes: bool) -> Result<(), Error> {
+    let ctx = Context::establish(Scope::User)?;
+    let (mut card, atr, reader) = connect_first_card(&ctx)?;
+    let tx = card.transaction()?;
+    let transport = open_transport(&tx, &reader, password);
+
+    let chip = detect_chip(&*transport, &atr)?;
+    let plan = make_read_only(&*transport, chip, dry_run, |plan| {
+        print!("{}", plan.describe());
+        if yes {
+            return true;
+        }
+        print!("Type LOCK to make the tag permanently read-only: ");
+        let _ = std::io::stdout().flush();
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer).is_ok() && answer.trim() == "LOCK"
+    })?;
+    if dry_run {
+        print!("{}", plan.describe());
+    }
+    Ok(())
+}
+
+/// Checks the NXP originality signature of the tag on the first reader.
+fn verify_card(password: Option<Password>) -> Result<(), Error> {
+    let ctx = Context::establish(Scope::User)?;
+    let (mut card, atr, reader) = connect_first_card(&ctx)?;
+    let tx = card.transaction()?;
+    let transport = open_transport(&tx, &reader, password);
+
+    let chip = detect_chip(&*transport, &atr)?;
+    let report = ntag::verify_originality(&*transport, chip)?;
+    println!("UID: {:02X?}", report.uid);
+    if let Some(signature) = report.signature {
+        println!("Signature: {:02X?}", signature);
+    }
+    println!("{}: {}", report.chip, report.result);
+    Ok(())
+}
+
+/// Prints the NFC counter and mirror configuration of the NTAG on the first reader.
+fn show_counter(password: Option<Password>) -> Result<(), Error> {
+    let ctx = Context::establish(Scope::User)?;
+    let (mut card, atr, reader) = connect_first_card(&ctx)?;
+    let tx = card.transaction()?;
+    let transport = open_transport(&tx, &reader, password);
+
+    let chip = detect_chip(&*transport, &atr)?;
+    println!("NFC counter: {}", ntag::read_counter(&*transport)?);
+    let mirror = ntag::read_mirror(&*transport, chip)?;
+    println!(
+        "Mirror: {:?} at page {} byte {}",
+        mirror.mode, mirror.page, mirror.byte
+    );
+    Ok(())
+}
+
+/// Writes `template` as a URI whose `{mirror}` marker the tag fills with its
+/// UID and/or NFC counter.
+fn write_mirror(password: Option<Password>, mode: MirrorMode, template: &str) -> Result<(), Error> {
+    let ctx = Context::establish(Scope::User)?;
+    let (mut card, atr, reader) = connect_first_card(&ctx)?;
+    let tx = card.transaction()?;
+    let transport = open_transport(&tx, &reader, password);
+
+    let chip = detect_chip(&*transport, &atr)?;
+    ntag::write_mirrored_uri(&*transport, chip, template, mode)?;
+    Ok(())
+}
 
-    let data = read_entire_card(&mut tx, 1024, 16)?;
+/// Writes the records `build` returns for the detected chip to the Type 2
+/// tag on the first reader as one NDEF message.
+fn write_records(
+    password: Option<Password>,
+    build: impl FnOnce(Chip) -> Vec<NdefRecord>,
+) -> Result<(), Error> {
+    let ctx = Context::establish(Scope::User)?;
+    let (mut card, atr, reader) = connect_first_card(&ctx)?;
+    let tx = card.transaction()?;
+    let transport = open_transport(&tx, &reader, password);
 
-    println!("Collected data: {:?}", data);
+    let chip = detect_chip(&*transport, &atr)?;
+    let records = build(chip);
+    let message = encode_ndef_message(&records)?;
+    ntag::write_ndef_message(&*transport, chip, &message)?;
+    for record in &records {
+        println!("Wrote {}", record.summary());
+    }
+    println!("{} bytes written to {}.", message.len(), chip);
     Ok(())
 }
 
-fn read_entire_card(
-    tx: &mut Transaction,
-    total_memory_size: usize,
-    block_size: usize,
-) -> Result<Vec<u8>, Error> {
-    println!("Reading entire NFC card memory...");
-
-    let mut data = Vec::new();
-    let total_blocks = (total_memory_size + block_size - 1) / block_size;
-
-    for block in 0..total_blocks {
-        let apdu_command = [
-            0x00,                 // CLA
-            0xB0,                 // INS: Read binary
-            (block >> 8) as u8,   // P1: High byte of block address
-            (block & 0xFF) as u8, // P2: Low byte of block address
-            block_size as u8,     // Le: Number of bytes to read
-        ];
-
-        let mut response_buffer = [0; MAX_BUFFER_SIZE];
-        let response = tx.transmit(&apdu_command, &mut response_buffer)?;
-
-        if response.len() >= 2 && &response[response.len() - 2..] == [0x90, 0x00] {
-            data.extend_from_slice(&response[..response.len() - 2]);
-            println!("Block {}: {:?}", block, &response[..response.len() - 2]);
-        } else {
-            println!("Failed to read block {}: Response: {:?}", block, response);
-            break;
+/// Writes the contact in a `.vcf` file as a `text/vcard` record.
+fn write_vcard(
+    password: Option<Password>,
+    path: &Path,
+    version: VcardVersion,
+) -> Result<(), Error> {
+    let contact = Contact::parse_vcard(&std::fs::read_to_string(path)?)?;
+    write_records(password, |chip| vec![contact.to_record_for(chip, version)])
+}
+
+/// Shows the NTAG configuration, or applies `field=value` edits to it.
+fn edit_config(password: Option<Password>, edits: &[String]) -> Result<(), Error> {
+    let ctx = Context::establish(Scope::User)?;
+    let (mut card, atr, reader) = connect_first_card(&ctx)?;
+    let tx = card.transaction()?;
+    let transport = open_transport(&tx, &reader, password);
+
+    let chip = detect_chip(&*transport, &atr)?;
+    let original = ntag::read_config(&*transport, chip)?;
+    let mut updated = original;
+    for edit in edits {
+        let (field, value) = edit
+            .split_once('=')
+            .ok_or_else(|| Error::Invalid(format!("Expected field=value, got {}", edit)))?;
+        updated.set(field, value)?;
+    }
+
+    if !edits.is_empty() {
+        ntag::write_config(&*transport, chip, &original, &updated)?;
+    }
+    print!("{}", updated);
+    Ok(())
+}
+
+/// Watches every reader, including ones plugged in later, and hands each
+/// tag that is presented to the daemon's rules.
+fn run_daemon(config: &Path, password: Option<Password>) -> Result<(), Error> {
+    let mut daemon = Daemon::load(config)?;
+    println!(
+        "Loaded {} rule(s), debounce {} ms.",
+        daemon.rules.len(),
+        daemon.debounce.as_millis()
+    );
+
+    let ctx = Context::establish(Scope::User)?;
+    watch_readers(&ctx, |event| {
+        match event {
+            ReaderEvent::Attached(reader) => println!("Watching reader: {:?}", reader),
+            ReaderEvent::CardInserted { reader, .. } => match read_tap(&ctx, &reader, password) {
+                Ok(tap) => {
+                    let fired = daemon.handle(&tap, Instant::now());
+                    println!(
+                        "Tag {} on {}: {}",
+                        rust_nfc_card_reader::hex::encode(&tap.uid),
+                        reader,
+                        if fired.is_empty() {
+                            "no rule fired".to_string()
+                        } else {
+                            fired.join(", ")
+                        }
+                    );
+                }
+                Err(err) => eprintln!("Reading the tag on {} failed: {}", reader, err),
+            },
+            ReaderEvent::Detached(_) | ReaderEvent::CardRemoved(_) => {}
         }
+        true
+    })?;
+    Ok(())
+}
+
+/// Connects to the card on `reader` and reads its UID and NDEF message.
+fn read_tap(ctx: &Context, reader: &str, password: Option<Password>) -> Result<Tap, Error> {
+    let name = std::ffi::CString::new(reader).map_err(|_| Error::NoReader)?;
+    let mut card = ctx.connect(&name, ShareMode::Shared, Protocols::ANY)?;
+    let atr = card.status2_owned()?.atr().to_vec();
+    let tx = card.transaction()?;
+    let transport = open_transport(&tx, reader, password);
+    let tap = Tap::read(&*transport, &atr, reader)?;
+    Ok(tap)
+}
+
+/// Provisions every tag presented on any reader with the next job from
+/// `input`, until all jobs are done. Results go to `report`, which also lets
+/// an interrupted run carry on.
+fn run_batch(input: &Path, report: &Path, options: batch::Options) -> Result<(), Error> {
+    let mut batch = Batch::open(batch::load_jobs(input)?, report, options)?;
+    println!("{} job(s) to do.", batch.remaining());
+    if batch.next_job().is_none() {
+        return Ok(());
     }
 
+    let ctx = Context::establish(Scope::User)?;
+    watch_readers(&ctx, |event| {
+        match event {
+            ReaderEvent::Attached(reader) => println!("Watching reader: {:?}", reader),
+            ReaderEvent::CardInserted { reader, atr } => {
+                match provision_tag(&ctx, &mut batch, &reader, &atr) {
+                    Ok(Some(entry)) => {
+                        let uid = rust_nfc_card_reader::hex::encode(&entry.uid);
+                        match entry.status {
+                            Status::Provisioned => {
+                                println!("{} on {}: job {} done", uid, reader, entry.id)
+                            }
+                            Status::Failed | Status::AlreadyProvisioned => eprintln!(
+                                "{} on {}: {}",
+                                uid,
+                                reader,
+                                entry.error.unwrap_or_default()
+                            ),
+                        }
+                        println!("{} job(s) left.", batch.remaining());
+                    }
+                    Ok(None) => {}
+                    Err(err) => eprintln!("Provisioning the tag on {} failed: {}", reader, err),
+                }
+            }
+            ReaderEvent::Detached(_) | ReaderEvent::CardRemoved(_) => {}
+        }
+        batch.next_job().is_some()
+    })?;
+    Ok(())
+}
+
+fn provision_tag(
+    ctx: &Context,
+    batch: &mut Batch,
+    reader: &str,
+    atr: &[u8],
+) -> Result<Option<batch::Entry>, Error> {
+    let name = std::ffi::CString::new(reader).map_err(|_| Error::NoReader)?;
+    let mut card = ctx.connect(&name, ShareMode::Shared, Protocols::ANY)?;
+    let tx = card.transaction()?;
+    // The batch authenticates by itself where it protects tags.
+    let transport = open_transport(&tx, reader, None);
+    let entry = batch.provision(&*transport, atr, reader)?;
+    Ok(entry)
+}
+
+/// Serves the HTTP/JSON API on `[address]`, localhost port 8080 by default.
+#[cfg(feature = "http-api")]
+fn serve_api(args: &[String], password: Option<Password>) -> Result<(), Error> {
+    use rust_nfc_card_reader::api::{ApiServer, PcscReaders};
+
+    let mut args = args.to_vec();
+    let allow_origin = take_option(&mut args, "--allow-origin");
+    let address = args.first().map_or("127.0.0.1:8080", String::as_str);
+
+    let listener = std::net::TcpListener::bind(address)?;
     println!(
-        "Finished reading card memory. Total bytes read: {}",
-        data.len()
+        "Serving the reader API on http://{}",
+        listener.local_addr()?
     );
-    Ok(data)
+    let mut server = ApiServer::new(Arc::new(PcscReaders::establish(password)?));
+    server.allow_origin = allow_origin;
+    server.serve(listener)
+}
+
+/// Removes `--name value` from `args` and returns the value.
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let position = args.iter().position(|arg| arg == name)?;
+    args.remove(position);
+    (position < args.len()).then(|| args.remove(position))
+}
+
+/// Prints the differences between two saved dumps as text or JSON.
+fn diff_files(before: &Path, after: &Path, json: bool) -> Result<(), Error> {
+    let diff = diff_dumps(&load_dump(before, None)?, &load_dump(after, None)?)?;
+    if json {
+        print!("{}", diff.to_json());
+    } else {
+        print!("{}", diff.to_text());
+    }
+    Ok(())
+}
+
+/// Exit status for each kind of failure, so scripts can tell a missing
+/// card from a refused password without parsing the message.
+fn exit_code(err: &Error) -> u8 {
+    match err {
+        Error::Invalid(_) | Error::Json(_) => 2,
+        Error::NoReader => 3,
+        Error::NoCard => 4,
+        Error::CardRemoved => 5,
+        Error::SharingViolation => 6,
+        Error::Pcsc(_) => 7,
+        Error::Status { .. } => 8,
+        Error::AuthFailed(_) => 9,
+        Error::LockedPage(_) => 10,
+        Error::CapacityExceeded { .. } => 11,
+        Error::Tag(_) => 12,
+        Error::Ndef(_) => 13,
+        Error::Io(_) => 14,
+        Error::Reader(_) => 15,
+    }
 }
 
-fn main() {
-    println!("Hello, world!");
-    start_reading().expect("TODO: panic message");
+fn main() -> ExitCode {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .format(|out, record| match record.level() {
+            log::Level::Info => writeln!(out, "{}", record.args()),
+            level => writeln!(out, "{}: {}", level, record.args()),
+        })
+        .init();
+
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let trace = take_option(&mut args, "--trace");
+    if trace.is_some() {
+        let _ = TRACER.set(Tracer::new());
+    }
+    let password = match take_option(&mut args, "--password")
+        .map(|value| value.parse::<Password>())
+        .transpose()
+    {
+        Ok(password) => password,
+        Err(err) => {
+            eprintln!("Invalid --password: {}", err);
+            return ExitCode::from(exit_code(&err));
+        }
+    };
+
+    let result = match args.first().map(String::as_str) {
+        Some("dump") => match args.get(1) {
+            Some(base) => dump_card(Path::new(base), password),
+            None => Err(Error::Invalid("Usage: dump <output base name>".into())),
+        },
+        Some("restore") => match (args.get(1), args.get(2).map(|chip| chip.parse::<Chip>())) {
+            (Some(path), None) => restore_card(Path::new(path), None, password),
+            (Some(path), Some(Ok(chip))) => restore_card(Path::new(path), Some(chip), password),
+            (Some(_), Some(Err(err))) => Err(Error::Invalid(err)),
+            (None, _) => Err(Error::Invalid("Usage: restore <dump file> [chip]".into())),
+        },
+        Some("diff") => match (args.get(1), args.get(2)) {
+            (Some(before), Some(after)) => diff_files(
+                Path::new(before),
+                Path::new(after),
+                args.iter().any(|arg| arg == "--json"),
+            ),
+            _ => Err(Error::Invalid(
+                "Usage: diff <before> <after> [--json]".into(),
+            )),
+        },
+        Some("protect") => {
+            let auth0 = args.get(1).map(|auth0| auth0.parse::<u8>());
+            let protection = match args.get(2).map(String::as_str) {
+                None | Some("w") => Some(Protection::Write),
+                Some("rw") => Some(Protection::ReadWrite),
+                Some(_) => None,
+            };
+            match (auth0, protection) {
+                (Some(Ok(auth0)), Some(protection)) => protect_card(password, auth0, protection),
+                _ => Err(Error::Invalid(
+                    "Usage: --password PWD[:PACK] protect <auth0 page> [w|rw]".into(),
+                )),
+            }
+        }
+        Some("unprotect") => unprotect_card(password),
+        Some("verify") => verify_card(password),
+        Some("counter") => show_counter(password),
+        Some("config") => edit_config(password, &args[1..]),
+        Some("mirror") => {
+            let mode = match args.get(1).map(String::as_str) {
+                Some("uid") => Some(MirrorMode::Uid),
+                Some("cnt") => Some(MirrorMode::Counter),
+                Some("uid-cnt") => Some(MirrorMode::UidCounter),
+                _ => None,
+            };
+            match (mode, args.get(2)) {
+                (Some(mode), Some(template)) => write_mirror(password, mode, template),
+                _ => Err(Error::Invalid(
+                    "Usage: mirror <uid|cnt|uid-cnt> <URI with {mirror}>".into(),
+                )),
+            }
+        }
+        Some("vcard") => match args.get(1) {
+            Some(path) => {
+                let version = if args.iter().any(|arg| arg == "--v4") {
+                    VcardVersion::V4
+                } else {
+                    VcardVersion::V3
+                };
+                write_vcard(password, Path::new(path), version)
+            }
+            None => Err(Error::Invalid("Usage: vcard <contact.vcf> [--v4]".into())),
+        },
+        Some("wifi") => match args.get(1) {
+            Some(ssid) => {
+                let key = args.get(2).map(String::as_str).unwrap_or_default();
+                WifiCredential::new(ssid, key).and_then(|credential| {
+                    write_records(password, |_| vec![NdefRecord::wifi(&credential)])
+                })
+            }
+            None => Err(Error::Invalid(
+                "Usage: wifi <SSID> [WPA2 passphrase]".into(),
+            )),
+        },
+        Some("daemon") => match args.get(1) {
+            Some(config) => run_daemon(Path::new(config), password),
+            None => Err(Error::Invalid("Usage: daemon <rules.json>".into())),
+        },
+        #[cfg(feature = "http-api")]
+        Some("serve") => serve_api(&args[1..], password),
+        Some("batch") => {
+            let protect = match take_option(&mut args, "--protect").map(|auth0| auth0.parse::<u8>())
+            {
+                Some(Ok(auth0)) => match password {
+                    Some(password) => Ok(Some((password, auth0))),
+                    None => Err(Error::Invalid(
+                        "--protect needs --password PWD[:PACK]".into(),
+                    )),
+                },
+                Some(Err(err)) => Err(Error::Invalid(format!("Invalid --protect page: {}", err))),
+                None => Ok(None),
+            };
+            let options = protect.map(|protect| batch::Options {
+                protect,
+                lock: args.iter().any(|arg| arg == "--lock"),
+            });
+            match (options, args.get(1), args.get(2)) {
+                (Ok(options), Some(input), Some(report)) => {
+                    run_batch(Path::new(input), Path::new(report), options)
+                }
+                (Err(err), _, _) => Err(err),
+                _ => Err(Error::Invalid(
+                    "Usage: batch <jobs.csv|jobs.jsonl> <report.jsonl> [--protect <auth0 page>] [--lock]"
+                        .into(),
+                )),
+            }
+        }
+        Some("lock") => lock_card(
+            password,
+            args.iter().any(|arg| arg == "--dry-run"),
+            args.iter().any(|arg| arg == "--yes"),
+        ),
+        _ => start_reading(password),
+    };
+    // Saved on failure too: that is when a trace is wanted.
+    if let (Some(path), Some(tracer)) = (trace, TRACER.get()) {
+        match tracer.save(Path::new(&path)) {
+            Ok(()) => println!("Trace saved to {}", path),
+            Err(err) => eprintln!("Could not save the trace to {}: {}", path, err),
+        }
+    }
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            ExitCode::from(exit_code(&err))
+        }
+    }
 }
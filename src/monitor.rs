@@ -0,0 +1,80 @@
+use pcsc::{Context, Error, ReaderState, State, PNP_NOTIFICATION};
+
+/// A reader or card coming or going, as reported by [`watch_readers`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReaderEvent {
+    /// A reader was found, either at start-up or when plugged in.
+    Attached(String),
+    Detached(String),
+    CardInserted {
+        reader: String,
+        atr: Vec<u8>,
+    },
+    CardRemoved(String),
+}
+
+/// Watches every reader, including ones plugged in later, and calls
+/// `on_event` for each change until it returns `false`.
+///
+/// Readers and cards already present are reported first. Blocks in
+/// `SCardGetStatusChange` between changes; [`Context::cancel`] from another
+/// thread ends the watch with [`Error::Cancelled`].
+pub fn watch_readers(
+    ctx: &Context,
+    mut on_event: impl FnMut(ReaderEvent) -> bool,
+) -> Result<(), Error> {
+    let mut states = vec![ReaderState::new(PNP_NOTIFICATION(), State::UNAWARE)];
+    loop {
+        let mut events = Vec::new();
+        states.retain(|state| {
+            let gone = state.name() != PNP_NOTIFICATION()
+                && state
+                    .event_state()
+                    .intersects(State::UNKNOWN | State::IGNORE);
+            if gone {
+                let name = state.name().to_string_lossy().into_owned();
+                events.push(ReaderEvent::Detached(name));
+            }
+            !gone
+        });
+        let names = match ctx.list_readers_owned() {
+            Ok(names) => names,
+            Err(Error::NoReadersAvailable) => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        for name in names {
+            if !states.iter().any(|state| state.name() == name.as_c_str()) {
+                events.push(ReaderEvent::Attached(name.to_string_lossy().into_owned()));
+                states.push(ReaderState::new(name, State::UNAWARE));
+            }
+        }
+        if !events.into_iter().all(&mut on_event) {
+            return Ok(());
+        }
+
+        for state in &mut states {
+            state.sync_current_state();
+        }
+        ctx.get_status_change(None, &mut states)?;
+
+        for state in states
+            .iter()
+            .filter(|state| state.name() != PNP_NOTIFICATION())
+        {
+            let was_present = state.current_state().contains(State::PRESENT);
+            let is_present = state.event_state().contains(State::PRESENT);
+            let reader = state.name().to_string_lossy().into_owned();
+            let event = match (was_present, is_present) {
+                (false, true) => ReaderEvent::CardInserted {
+                    reader,
+                    atr: state.atr().to_vec(),
+                },
+                (true, false) => ReaderEvent::CardRemoved(reader),
+                _ => continue,
+            };
+            if !on_event(event) {
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use super::{NdefRecord, TNF_MIME_MEDIA};
+use crate::error::Error;
+
+pub const MIME_BLUETOOTH_EP_OOB: &str = "application/vnd.bluetooth.ep.oob";
+pub const MIME_BLUETOOTH_LE_OOB: &str = "application/vnd.bluetooth.le.oob";
+
+/// EIR and AD data types (Bluetooth Assigned Numbers).
+const SHORTENED_LOCAL_NAME: u8 = 0x08;
+const COMPLETE_LOCAL_NAME: u8 = 0x09;
+const CLASS_OF_DEVICE: u8 = 0x0D;
+const SECURITY_MANAGER_TK: u8 = 0x10;
+const LE_DEVICE_ADDRESS: u8 = 0x1B;
+const LE_ROLE: u8 = 0x1C;
+
+/// BR/EDR out-of-band data ("application/vnd.bluetooth.ep.oob").
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BluetoothEpOob {
+    /// Device address as written for people, most significant byte first.
+    pub address: [u8; 6],
+    /// Complete local name. A shortened name is read into it as well.
+    pub local_name: Option<String>,
+    /// 24-bit Class of Device.
+    pub class_of_device: Option<u32>,
+    /// Other EIR structures as `(type, data)`, kept in order.
+    pub other: Vec<(u8, Vec<u8>)>,
+}
+
+impl BluetoothEpOob {
+    pub fn new(address: [u8; 6]) -> BluetoothEpOob {
+        BluetoothEpOob {
+            address,
+            ..BluetoothEpOob::default()
+        }
+    }
+
+    /// OOB data length (little endian, counting itself), the address in
+    /// little-endian order, then EIR structures. Fails when a structure or
+    /// the whole payload is too long for its length field.
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        let mut eir = Vec::new();
+        if let Some(name) = &self.local_name {
+            push_structure(&mut eir, COMPLETE_LOCAL_NAME, name.as_bytes())?;
+        }
+        if let Some(class) = self.class_of_device {
+            push_structure(&mut eir, CLASS_OF_DEVICE, &class.to_le_bytes()[..3])?;
+        }
+        for (kind, data) in &self.other {
+            push_structure(&mut eir, *kind, data)?;
+        }
+
+        let length = u16::try_from(2 + 6 + eir.len()).map_err(|_| {
+            Error::Ndef(format!(
+                "Bluetooth OOB data of {} bytes does not fit its 2-byte length",
+                2 + 6 + eir.len()
+            ))
+        })?;
+        let mut payload = length.to_le_bytes().to_vec();
+        payload.extend(self.address.iter().rev());
+        payload.extend(eir);
+        Ok(payload)
+    }
+
+    pub fn decode(payload: &[u8]) -> Result<BluetoothEpOob, Error> {
+        let length = match payload {
+            [low, high, ..] => u16::from_le_bytes([*low, *high]) as usize,
+            _ => return Err(Error::Ndef("Bluetooth OOB data has no length".into())),
+        };
+        if length < 8 || length > payload.len() {
+            return Err(Error::Ndef(format!(
+                "Bluetooth OOB length {} does not match the {} byte payload",
+                length,
+                payload.len()
+            )));
+        }
+
+        let mut oob = BluetoothEpOob::new(reversed_address(&payload[2..8]));
+        let mut shortened_name = None;
+        for (kind, data) in parse_structures(&payload[8..length])? {
+            match kind {
+                COMPLETE_LOCAL_NAME => oob.local_name = Some(utf8(data)),
+                SHORTENED_LOCAL_NAME => shortened_name = Some(utf8(data)),
+                CLASS_OF_DEVICE => {
+                    let [a, b, c] = data else {
+                        return Err(Error::Ndef("Class of Device must be 3 bytes".into()));
+                    };
+                    oob.class_of_device = Some(u32::from_le_bytes([*a, *b, *c, 0]));
+                }
+                _ => oob.other.push((kind, data.to_vec())),
+            }
+        }
+        oob.local_name = oob.local_name.or(shortened_name);
+        Ok(oob)
+    }
+
+    pub fn to_record(&self) -> Result<NdefRecord, Error> {
+        Ok(NdefRecord::new(
+            TNF_MIME_MEDIA,
+            MIME_BLUETOOTH_EP_OOB.as_bytes(),
+            self.encode()?,
+        ))
+    }
+
+    pub fn from_record(record: &NdefRecord) -> Result<BluetoothEpOob, Error> {
+        if !has_mime_type(record, MIME_BLUETOOTH_EP_OOB) {
+            return Err(Error::Ndef(format!(
+                "Not a Bluetooth OOB record: {}",
+                record.summary()
+            )));
+        }
+        BluetoothEpOob::decode(&record.payload)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LeAddressType {
+    #[default]
+    Public,
+    Random,
+}
+
+/// LE Role AD type values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeRole {
+    PeripheralOnly,
+    CentralOnly,
+    PeripheralPreferred,
+    CentralPreferred,
+}
+
+impl LeRole {
+    fn code(self) -> u8 {
+        match self {
+            LeRole::PeripheralOnly => 0x00,
+            LeRole::CentralOnly => 0x01,
+            LeRole::PeripheralPreferred => 0x02,
+            LeRole::CentralPreferred => 0x03,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<LeRole> {
+        match code {
+            0x00 => Some(LeRole::PeripheralOnly),
+            0x01 => Some(LeRole::CentralOnly),
+            0x02 => Some(LeRole::PeripheralPreferred),
+            0x03 => Some(LeRole::CentralPreferred),
+            _ => None,
+        }
+    }
+}
+
+/// Bluetooth LE out-of-band data ("application/vnd.bluetooth.le.oob").
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BluetoothLeOob {
+    /// Device address as written for people, most significant byte first.
+    pub address: [u8; 6],
+    pub address_type: LeAddressType,
+    pub role: LeRole,
+    pub local_name: Option<String>,
+    /// Security Manager Temporary Key for OOB legacy pairing.
+    pub security_manager_tk: Option<[u8; 16]>,
+    /// Other AD structures as `(type, data)`, kept in order.
+    pub other: Vec<(u8, Vec<u8>)>,
+}
+
+impl BluetoothLeOob {
+    pub fn new(address: [u8; 6], address_type: LeAddressType, role: LeRole) -> BluetoothLeOob {
+        BluetoothLeOob {
+            address,
+            address_type,
+            role,
+            local_name: None,
+            security_manager_tk: None,
+            other: Vec::new(),
+        }
+    }
+
+    /// AD structures only; unlike the BR/EDR payload there is no length prefix.
+    /// Fails when a structure is too long for its length byte.
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        let mut payload = Vec::new();
+        let mut address: Vec<u8> = self.address.iter().rev().copied().collect();
+        address.push(match self.address_type {
+            LeAddressType::Public => 0x00,
+            LeAddressType::Random => 0x01,
+        });
+        push_structure(&mut payload, LE_DEVICE_ADDRESS, &address)?;
+        push_structure(&mut payload, LE_ROLE, &[self.role.code()])?;
+        if let Some(tk) = &self.security_manager_tk {
+            push_structure(&mut payload, SECURITY_MANAGER_TK, tk)?;
+        }
+        if let Some(name) = &self.local_name {
+            push_structure(&mut payload, COMPLETE_LOCAL_NAME, name.as_bytes())?;
+        }
+        for (kind, data) in &self.other {
+            push_structure(&mut payload, *kind, data)?;
+        }
+        Ok(payload)
+    }
+
+    pub fn decode(payload: &[u8]) -> Result<BluetoothLeOob, Error> {
+        let mut address = None;
+        let mut role = None;
+        let mut oob = BluetoothLeOob::new([0; 6], LeAddressType::Public, LeRole::PeripheralOnly);
+        let mut shortened_name = None;
+        for (kind, data) in parse_structures(payload)? {
+            match kind {
+                LE_DEVICE_ADDRESS => {
+                    if data.len() != 7 {
+                        return Err(Error::Ndef(
+                            "LE Bluetooth Device Address must be 7 bytes".into(),
+                        ));
+                    }
+                    address = Some(reversed_address(&data[..6]));
+                    oob.address_type = if data[6] & 0x01 != 0 {
+                        LeAddressType::Random
+                    } else {
+                        LeAddressType::Public
+                    };
+                }
+                LE_ROLE => {
+                    let code = *data
+                        .first()
+                        .ok_or_else(|| Error::Ndef("Empty LE Role".into()))?;
+                    role =
+                        Some(LeRole::from_code(code).ok_or_else(|| {
+                            Error::Ndef(format!("Unknown LE Role {:#04X}", code))
+                        })?);
+                }
+                SECURITY_MANAGER_TK => {
+                    oob.security_manager_tk =
+                        Some(data.try_into().map_err(|_| {
+                            Error::Ndef("Security Manager TK must be 16 bytes".into())
+                        })?);
+                }
+                COMPLETE_LOCAL_NAME => oob.local_name = Some(utf8(data)),
+                SHORTENED_LOCAL_NAME => shortened_name = Some(utf8(data)),
+                _ => oob.other.push((kind, data.to_vec())),
+            }
+        }
+        oob.address = address
+            .ok_or_else(|| Error::Ndef("LE OOB data has no LE Bluetooth Device Address".into()))?;
+        oob.role = role.ok_or_else(|| Error::Ndef("LE OOB data has no LE Role".into()))?;
+        oob.local_name = oob.local_name.or(shortened_name);
+        Ok(oob)
+    }
+
+    pub fn to_record(&self) -> Result<NdefRecord, Error> {
+        Ok(NdefRecord::new(
+            TNF_MIME_MEDIA,
+            MIME_BLUETOOTH_LE_OOB.as_bytes(),
+            self.encode()?,
+        ))
+    }
+
+    pub fn from_record(record: &NdefRecord) -> Result<BluetoothLeOob, Error> {
+        if !has_mime_type(record, MIME_BLUETOOTH_LE_OOB) {
+            return Err(Error::Ndef(format!(
+                "Not a Bluetooth LE OOB record: {}",
+                record.summary()
+            )));
+        }
+        BluetoothLeOob::decode(&record.payload)
+    }
+}
+
+/// `AA:BB:CC:DD:EE:FF`.
+pub fn format_bluetooth_address(address: &[u8; 6]) -> String {
+    address
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+pub(super) fn has_mime_type(record: &NdefRecord, mime_type: &str) -> bool {
+    record
+        .mime_type()
+        .is_some_and(|other| other.eq_ignore_ascii_case(mime_type))
+}
+
+/// Appends one EIR or AD structure. Its length byte counts the type, so the
+/// value can be at most 254 bytes.
+fn push_structure(data: &mut Vec<u8>, kind: u8, value: &[u8]) -> Result<(), Error> {
+    if value.len() > 254 {
+        return Err(Error::Ndef(format!(
+            "Bluetooth data structure {:#04X} is {} bytes long, the limit is 254",
+            kind,
+            value.len()
+        )));
+    }
+    data.push(value.len() as u8 + 1);
+    data.push(kind);
+    data.extend_from_slice(value);
+    Ok(())
+}
+
+/// Splits EIR or AD data into `(type, data)` structures. A zero length ends
+/// the significant part.
+fn parse_structures(mut data: &[u8]) -> Result<Vec<(u8, &[u8])>, Error> {
+    let mut structures = Vec::new();
+    while let [length, rest @ ..] = data {
+        let length = *length as usize;
+        if length == 0 {
+            break;
+        }
+        if rest.len() < length {
+            return Err(Error::Ndef(format!(
+                "Bluetooth data structure of {} bytes overruns the payload",
+                length
+            )));
+        }
+        structures.push((rest[0], &rest[1..length]));
+        data = &rest[length..];
+    }
+    Ok(structures)
+}
+
+fn reversed_address(bytes: &[u8]) -> [u8; 6] {
+    let mut address = [0; 6];
+    for (target, byte) in address.iter_mut().zip(bytes.iter().rev()) {
+        *target = *byte;
+    }
+    address
+}
+
+fn utf8(data: &[u8]) -> String {
+    String::from_utf8_lossy(data).into_owned()
+}
@@ -0,0 +1,21 @@
+use crate::error::Error;
+
+/// External type of Android Application Records.
+pub const ANDROID_APP_TYPE: &str = "android.com:pkg";
+
+/// Normalises an NFC Forum external type name (`domain:type`) to lower case,
+/// since external types compare case-insensitively, and checks its syntax.
+pub fn normalize_external_type(name: &str) -> Result<String, Error> {
+    let (domain, kind) = name
+        .split_once(':')
+        .ok_or_else(|| Error::Ndef(format!("External type {:?} is not domain:type", name)))?;
+    let valid_domain = !domain.is_empty()
+        && domain
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b".-".contains(&byte));
+    let valid_type = !kind.is_empty() && kind.bytes().all(|byte| byte.is_ascii_graphic());
+    if !valid_domain || !valid_type || name.len() > 255 {
+        return Err(Error::Ndef(format!("Invalid external type {:?}", name)));
+    }
+    Ok(name.to_ascii_lowercase())
+}
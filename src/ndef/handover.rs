@@ -0,0 +1,326 @@
+use super::{encode_ndef_message, parse_ndef_message, NdefRecord, TNF_WELL_KNOWN};
+use crate::error::Error;
+
+/// Connection Handover 1.3, the version written into new Hs and Hr records.
+pub const HANDOVER_VERSION: u8 = 0x13;
+
+/// Carrier Power State of an Alternative Carrier record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CarrierPowerState {
+    Inactive,
+    Active,
+    Activating,
+    Unknown,
+}
+
+impl CarrierPowerState {
+    fn code(self) -> u8 {
+        match self {
+            CarrierPowerState::Inactive => 0x00,
+            CarrierPowerState::Active => 0x01,
+            CarrierPowerState::Activating => 0x02,
+            CarrierPowerState::Unknown => 0x03,
+        }
+    }
+
+    fn from_code(code: u8) -> CarrierPowerState {
+        match code & 0x03 {
+            0x00 => CarrierPowerState::Inactive,
+            0x01 => CarrierPowerState::Active,
+            0x02 => CarrierPowerState::Activating,
+            _ => CarrierPowerState::Unknown,
+        }
+    }
+}
+
+/// Alternative Carrier ("ac"): points at a carrier record by its ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlternativeCarrier {
+    pub power_state: CarrierPowerState,
+    /// ID of the carrier configuration or Handover Carrier record.
+    pub carrier_data_reference: Vec<u8>,
+    /// IDs of records with auxiliary data for this carrier.
+    pub auxiliary_data_references: Vec<Vec<u8>>,
+}
+
+impl AlternativeCarrier {
+    /// Fails when a reference, or the number of auxiliary references, does
+    /// not fit its length byte.
+    pub fn to_record(&self) -> Result<NdefRecord, Error> {
+        let mut payload = vec![self.power_state.code()];
+        push_prefixed(
+            &mut payload,
+            "Carrier data reference",
+            &self.carrier_data_reference,
+        )?;
+        let count = u8::try_from(self.auxiliary_data_references.len()).map_err(|_| {
+            Error::Ndef(format!(
+                "Alternative Carrier has {} auxiliary data references, the limit is 255",
+                self.auxiliary_data_references.len()
+            ))
+        })?;
+        payload.push(count);
+        for reference in &self.auxiliary_data_references {
+            push_prefixed(&mut payload, "Auxiliary data reference", reference)?;
+        }
+        Ok(NdefRecord::new(TNF_WELL_KNOWN, b"ac", payload))
+    }
+
+    pub fn from_record(record: &NdefRecord) -> Result<AlternativeCarrier, Error> {
+        if !record.is_well_known(b"ac") {
+            return Err(Error::Ndef(format!(
+                "Not an Alternative Carrier record: {}",
+                record.summary()
+            )));
+        }
+        let mut reader = Reader(&record.payload);
+        let power_state = CarrierPowerState::from_code(reader.byte("power state")?);
+        let carrier_data_reference = reader.prefixed("carrier data reference")?.to_vec();
+        let count = reader.byte("auxiliary data reference count")?;
+        let auxiliary_data_references = (0..count)
+            .map(|_| {
+                reader
+                    .prefixed("auxiliary data reference")
+                    .map(<[u8]>::to_vec)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(AlternativeCarrier {
+            power_state,
+            carrier_data_reference,
+            auxiliary_data_references,
+        })
+    }
+}
+
+/// Handover Carrier ("Hc"): names a carrier without its full configuration,
+/// as used in Handover Requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandoverCarrier {
+    /// TNF of `carrier_type`, e.g. `TNF_MIME_MEDIA`.
+    pub carrier_type_format: u8,
+    pub carrier_type: Vec<u8>,
+    pub carrier_data: Vec<u8>,
+}
+
+impl HandoverCarrier {
+    /// Fails when the carrier type is longer than 255 bytes.
+    pub fn to_record(&self) -> Result<NdefRecord, Error> {
+        let mut payload = vec![self.carrier_type_format & 0x07];
+        push_prefixed(&mut payload, "Carrier type", &self.carrier_type)?;
+        payload.extend_from_slice(&self.carrier_data);
+        Ok(NdefRecord::new(TNF_WELL_KNOWN, b"Hc", payload))
+    }
+
+    pub fn from_record(record: &NdefRecord) -> Result<HandoverCarrier, Error> {
+        if !record.is_well_known(b"Hc") {
+            return Err(Error::Ndef(format!(
+                "Not a Handover Carrier record: {}",
+                record.summary()
+            )));
+        }
+        let mut reader = Reader(&record.payload);
+        let carrier_type_format = reader.byte("carrier type format")? & 0x07;
+        let carrier_type = reader.prefixed("carrier type")?.to_vec();
+        Ok(HandoverCarrier {
+            carrier_type_format,
+            carrier_type,
+            carrier_data: reader.0.to_vec(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandoverKind {
+    /// Handover Select ("Hs"), written to static handover tags.
+    Select,
+    /// Handover Request ("Hr").
+    Request,
+}
+
+impl HandoverKind {
+    fn record_type(self) -> &'static [u8] {
+        match self {
+            HandoverKind::Select => b"Hs",
+            HandoverKind::Request => b"Hr",
+        }
+    }
+}
+
+/// Handover Select or Request record with its nested Alternative Carriers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Handover {
+    pub kind: HandoverKind,
+    /// Major version in the high nibble, minor in the low one.
+    pub version: u8,
+    /// Random number of the Collision Resolution ("cr") record of a request.
+    pub collision_resolution: Option<u16>,
+    pub carriers: Vec<AlternativeCarrier>,
+}
+
+impl Handover {
+    pub fn select(carriers: Vec<AlternativeCarrier>) -> Handover {
+        Handover {
+            kind: HandoverKind::Select,
+            version: HANDOVER_VERSION,
+            collision_resolution: None,
+            carriers,
+        }
+    }
+
+    pub fn request(random: u16, carriers: Vec<AlternativeCarrier>) -> Handover {
+        Handover {
+            kind: HandoverKind::Request,
+            version: HANDOVER_VERSION,
+            collision_resolution: Some(random),
+            carriers,
+        }
+    }
+
+    /// Fails when one of the Alternative Carriers cannot be encoded.
+    pub fn to_record(&self) -> Result<NdefRecord, Error> {
+        let mut records = Vec::new();
+        if let Some(random) = self.collision_resolution {
+            records.push(NdefRecord::new(
+                TNF_WELL_KNOWN,
+                b"cr",
+                random.to_be_bytes().to_vec(),
+            ));
+        }
+        for carrier in &self.carriers {
+            records.push(carrier.to_record()?);
+        }
+
+        let mut payload = vec![self.version];
+        payload.extend(encode_ndef_message(&records)?);
+        Ok(NdefRecord::new(
+            TNF_WELL_KNOWN,
+            self.kind.record_type(),
+            payload,
+        ))
+    }
+
+    /// Decodes an Hs or Hr record. Records other than "ac" and "cr" in the
+    /// nested message, such as "err", are ignored.
+    pub fn from_record(record: &NdefRecord) -> Result<Handover, Error> {
+        let kind = if record.is_well_known(b"Hs") {
+            HandoverKind::Select
+        } else if record.is_well_known(b"Hr") {
+            HandoverKind::Request
+        } else {
+            return Err(Error::Ndef(format!(
+                "Not a Handover record: {}",
+                record.summary()
+            )));
+        };
+        let (&version, nested) = record
+            .payload
+            .split_first()
+            .ok_or_else(|| Error::Ndef("Handover record has no version".into()))?;
+        if version >> 4 != 1 {
+            return Err(Error::Ndef(format!(
+                "Unsupported Connection Handover version {}.{}",
+                version >> 4,
+                version & 0x0F
+            )));
+        }
+
+        let mut handover = Handover {
+            kind,
+            version,
+            collision_resolution: None,
+            carriers: Vec::new(),
+        };
+        for sub_record in parse_ndef_message(nested)? {
+            if sub_record.is_well_known(b"ac") {
+                handover
+                    .carriers
+                    .push(AlternativeCarrier::from_record(&sub_record)?);
+            } else if sub_record.is_well_known(b"cr") {
+                let random: [u8; 2] = sub_record.payload[..].try_into().map_err(|_| {
+                    Error::Ndef("Collision Resolution record must be 2 bytes".into())
+                })?;
+                handover.collision_resolution = Some(u16::from_be_bytes(random));
+            }
+        }
+        if kind == HandoverKind::Request
+            && handover.collision_resolution.is_none()
+            && version >= 0x12
+        {
+            return Err(Error::Ndef(
+                "Handover Request has no Collision Resolution record".into(),
+            ));
+        }
+        Ok(handover)
+    }
+
+    /// The record among `records` that `carrier` points at.
+    pub fn carrier_record<'a>(
+        carrier: &AlternativeCarrier,
+        records: &'a [NdefRecord],
+    ) -> Option<&'a NdefRecord> {
+        records
+            .iter()
+            .find(|record| record.id == carrier.carrier_data_reference)
+    }
+}
+
+/// A static handover message: a Handover Select record followed by the
+/// carrier records, which get IDs "0", "1"... when they have none. Fails
+/// when a carrier ID is longer than 255 bytes.
+pub fn handover_select(
+    carriers: Vec<(CarrierPowerState, NdefRecord)>,
+) -> Result<Vec<NdefRecord>, Error> {
+    let mut alternatives = Vec::new();
+    let mut records = Vec::new();
+    for (index, (power_state, mut record)) in carriers.into_iter().enumerate() {
+        if record.id.is_empty() {
+            record.id = index.to_string().into_bytes();
+        }
+        alternatives.push(AlternativeCarrier {
+            power_state,
+            carrier_data_reference: record.id.clone(),
+            auxiliary_data_references: Vec::new(),
+        });
+        records.push(record);
+    }
+    records.insert(0, Handover::select(alternatives).to_record()?);
+    Ok(records)
+}
+
+/// Appends `value` after its one-byte length.
+fn push_prefixed(payload: &mut Vec<u8>, field: &str, value: &[u8]) -> Result<(), Error> {
+    let length = u8::try_from(value.len()).map_err(|_| {
+        Error::Ndef(format!(
+            "{} is {} bytes long, the limit is 255",
+            field,
+            value.len()
+        ))
+    })?;
+    payload.push(length);
+    payload.extend_from_slice(value);
+    Ok(())
+}
+
+/// Reads length-prefixed fields from a record payload.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self, field: &str) -> Result<u8, Error> {
+        let (&byte, rest) = self
+            .0
+            .split_first()
+            .ok_or_else(|| Error::Ndef(format!("Missing {}", field)))?;
+        self.0 = rest;
+        Ok(byte)
+    }
+
+    fn prefixed(&mut self, field: &str) -> Result<&'a [u8], Error> {
+        let length = self.byte(field)? as usize;
+        if self.0.len() < length {
+            return Err(Error::Ndef(format!("Truncated {}", field)));
+        }
+        let (value, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Ok(value)
+    }
+}
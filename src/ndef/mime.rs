@@ -0,0 +1,35 @@
+use crate::error::Error;
+
+pub const MIME_JSON: &str = "application/json";
+
+/// Checks that `mime_type` is a `type/subtype` media type (RFC 2046),
+/// optionally followed by `;` parameters without spaces.
+pub fn validate_mime_type(mime_type: &str) -> Result<(), Error> {
+    let (kind, subtype) = mime_type
+        .split_once('/')
+        .ok_or_else(|| Error::Ndef(format!("MIME type {:?} has no subtype", mime_type)))?;
+    let token = |part: &str| {
+        !part.is_empty()
+            && part
+                .bytes()
+                .all(|byte| byte.is_ascii_graphic() && !b"()<>@,;:\\\"/[]?=".contains(&byte))
+    };
+    let (subtype, parameters) = subtype.split_once(';').unwrap_or((subtype, ""));
+    if !token(kind) || !token(subtype) || parameters.contains(char::is_whitespace) {
+        return Err(Error::Ndef(format!("Invalid MIME type {:?}", mime_type)));
+    }
+    if mime_type.len() > 255 {
+        return Err(Error::Ndef("MIME type is longer than 255 bytes".into()));
+    }
+    Ok(())
+}
+
+/// `true` for media types whose payload is readable text.
+pub fn is_text_mime_type(mime_type: &str) -> bool {
+    let mime_type = mime_type.to_ascii_lowercase();
+    mime_type.starts_with("text/")
+        || mime_type.starts_with(MIME_JSON)
+        || mime_type.ends_with("+json")
+        || mime_type.ends_with("+xml")
+        || mime_type.starts_with("application/xml")
+}
@@ -0,0 +1,445 @@
+mod bluetooth;
+mod external;
+mod handover;
+mod mime;
+mod smart_poster;
+mod text;
+mod uri;
+mod validate;
+mod vcard;
+mod wifi;
+
+pub use bluetooth::{
+    format_bluetooth_address, BluetoothEpOob, BluetoothLeOob, LeAddressType, LeRole,
+    MIME_BLUETOOTH_EP_OOB, MIME_BLUETOOTH_LE_OOB,
+};
+pub use external::{normalize_external_type, ANDROID_APP_TYPE};
+pub use handover::{
+    handover_select, AlternativeCarrier, CarrierPowerState, Handover, HandoverCarrier,
+    HandoverKind, HANDOVER_VERSION,
+};
+pub use mime::{is_text_mime_type, validate_mime_type, MIME_JSON};
+pub use smart_poster::{Action, SmartPoster};
+pub use text::{decode_text, encode_text};
+pub use uri::{decode_uri, encode_uri, encode_uri_reserving, URI_PREFIXES};
+pub use validate::{validate_ndef_message, Violation, ViolationKind};
+pub use vcard::{Contact, Phone, VcardVersion, MIME_VCARD};
+pub use wifi::{AuthType, EncryptionType, WifiCredential, MIME_WSC};
+
+use crate::chip::Chip;
+use crate::error::Error;
+use serde_json::Value;
+
+/// Type Name Format values (NDEF 1.0 section 3.2.6).
+pub const TNF_EMPTY: u8 = 0x00;
+pub const TNF_WELL_KNOWN: u8 = 0x01;
+pub const TNF_MIME_MEDIA: u8 = 0x02;
+pub const TNF_ABSOLUTE_URI: u8 = 0x03;
+pub const TNF_EXTERNAL: u8 = 0x04;
+pub const TNF_UNKNOWN: u8 = 0x05;
+pub const TNF_UNCHANGED: u8 = 0x06;
+
+/// Record header flags.
+pub const FLAG_MB: u8 = 0x80;
+pub const FLAG_ME: u8 = 0x40;
+pub const FLAG_CF: u8 = 0x20;
+pub const FLAG_SR: u8 = 0x10;
+pub const FLAG_IL: u8 = 0x08;
+
+/// One NDEF record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NdefRecord {
+    pub tnf: u8,
+    pub record_type: Vec<u8>,
+    pub id: Vec<u8>,
+    pub payload: Vec<u8>,
+}
+
+impl NdefRecord {
+    pub fn new(tnf: u8, record_type: &[u8], payload: Vec<u8>) -> NdefRecord {
+        NdefRecord {
+            tnf,
+            record_type: record_type.to_vec(),
+            id: Vec::new(),
+            payload,
+        }
+    }
+
+    /// Well-known URI ("U") record.
+    pub fn uri(uri: &str) -> NdefRecord {
+        NdefRecord::new(TNF_WELL_KNOWN, b"U", encode_uri(uri))
+    }
+
+    /// Well-known Text ("T") record.
+    pub fn text(language: &str, text: &str) -> Result<NdefRecord, Error> {
+        Ok(NdefRecord::new(
+            TNF_WELL_KNOWN,
+            b"T",
+            encode_text(language, text)?,
+        ))
+    }
+
+    /// MIME media record (TNF 2), e.g. `image/png` or `text/vcard`.
+    pub fn mime(mime_type: &str, payload: Vec<u8>) -> Result<NdefRecord, Error> {
+        validate_mime_type(mime_type)?;
+        Ok(NdefRecord::new(
+            TNF_MIME_MEDIA,
+            mime_type.as_bytes(),
+            payload,
+        ))
+    }
+
+    /// `application/json` record holding `value` in compact form.
+    pub fn json(value: &Value) -> NdefRecord {
+        NdefRecord::new(
+            TNF_MIME_MEDIA,
+            MIME_JSON.as_bytes(),
+            value.to_string().into_bytes(),
+        )
+    }
+
+    /// NFC Forum External type record (TNF 4), `domain:type`.
+    pub fn external(name: &str, payload: Vec<u8>) -> Result<NdefRecord, Error> {
+        let name = normalize_external_type(name)?;
+        Ok(NdefRecord::new(TNF_EXTERNAL, name.as_bytes(), payload))
+    }
+
+    /// Android Application Record that makes Android open `package`.
+    pub fn android_app(package: &str) -> NdefRecord {
+        NdefRecord::new(
+            TNF_EXTERNAL,
+            ANDROID_APP_TYPE.as_bytes(),
+            package.as_bytes().to_vec(),
+        )
+    }
+
+    /// The media type of a MIME record.
+    pub fn mime_type(&self) -> Option<&str> {
+        if self.tnf != TNF_MIME_MEDIA {
+            return None;
+        }
+        std::str::from_utf8(&self.record_type).ok()
+    }
+
+    /// The `domain:type` name of an External type record.
+    pub fn external_type(&self) -> Option<&str> {
+        if self.tnf != TNF_EXTERNAL {
+            return None;
+        }
+        std::str::from_utf8(&self.record_type).ok()
+    }
+
+    /// The package name of an Android Application Record.
+    pub fn android_package(&self) -> Option<&str> {
+        let name = self.external_type()?;
+        if !name.eq_ignore_ascii_case(ANDROID_APP_TYPE) {
+            return None;
+        }
+        std::str::from_utf8(&self.payload).ok()
+    }
+
+    /// Parses the payload of an `application/json` (or `+json`) record.
+    pub fn json_value(&self) -> Option<Result<Value, serde_json::Error>> {
+        let mime_type = self.mime_type()?.to_ascii_lowercase();
+        if mime_type != MIME_JSON && !mime_type.ends_with("+json") {
+            return None;
+        }
+        Some(serde_json::from_slice(&self.payload))
+    }
+
+    /// `text/vcard` record for `contact`.
+    pub fn vcard(contact: &Contact, version: VcardVersion) -> NdefRecord {
+        contact.to_record(version)
+    }
+
+    /// `application/vnd.wfa.wsc` record holding one Wi-Fi network.
+    pub fn wifi(credential: &WifiCredential) -> NdefRecord {
+        credential.to_record()
+    }
+
+    /// Well-known Smart Poster ("Sp") record.
+    pub fn smart_poster(poster: &SmartPoster) -> Result<NdefRecord, Error> {
+        poster.to_record()
+    }
+
+    /// `true` for a well-known record of the given type, e.g. `b"U"`.
+    pub fn is_well_known(&self, record_type: &[u8]) -> bool {
+        self.tnf == TNF_WELL_KNOWN && self.record_type == record_type
+    }
+
+    /// One-line human readable description of the record.
+    pub fn summary(&self) -> String {
+        if self.is_well_known(b"U") {
+            if let Some(uri) = decode_uri(&self.payload) {
+                return format!("URI {}", uri);
+            }
+        }
+        if self.is_well_known(b"T") {
+            if let Some((language, text)) = decode_text(&self.payload) {
+                return format!("Text [{}] {}", language, text);
+            }
+        }
+        if self.is_well_known(b"Sp") {
+            if let Ok(poster) = SmartPoster::from_record(self) {
+                return match poster.title("en") {
+                    Some(title) => format!("Smart Poster {} \"{}\"", poster.uri, title),
+                    None => format!("Smart Poster {}", poster.uri),
+                };
+            }
+        }
+        if let Some(package) = self.android_package() {
+            return format!("Android app {}", package);
+        }
+        if self.mime_type().is_some_and(vcard::is_vcard_mime_type) {
+            if let Ok(contact) = Contact::from_record(self) {
+                return format!("Contact {}", contact.formatted_name());
+            }
+        }
+        if self
+            .mime_type()
+            .is_some_and(|mime_type| mime_type.eq_ignore_ascii_case(MIME_WSC))
+        {
+            if let Ok(credential) = WifiCredential::decode(&self.payload) {
+                return format!("Wi-Fi {} ({})", credential.ssid, credential.auth_type);
+            }
+        }
+        if self.is_well_known(b"Hs") || self.is_well_known(b"Hr") {
+            if let Ok(handover) = Handover::from_record(self) {
+                let kind = match handover.kind {
+                    HandoverKind::Select => "Select",
+                    HandoverKind::Request => "Request",
+                };
+                return format!(
+                    "Handover {} {}.{}, {} carrier(s)",
+                    kind,
+                    handover.version >> 4,
+                    handover.version & 0x0F,
+                    handover.carriers.len()
+                );
+            }
+        }
+        if bluetooth::has_mime_type(self, MIME_BLUETOOTH_EP_OOB) {
+            if let Ok(oob) = BluetoothEpOob::decode(&self.payload) {
+                return format!(
+                    "Bluetooth {} {}",
+                    format_bluetooth_address(&oob.address),
+                    oob.local_name.unwrap_or_default()
+                )
+                .trim_end()
+                .to_string();
+            }
+        }
+        if bluetooth::has_mime_type(self, MIME_BLUETOOTH_LE_OOB) {
+            if let Ok(oob) = BluetoothLeOob::decode(&self.payload) {
+                return format!(
+                    "Bluetooth LE {} {}",
+                    format_bluetooth_address(&oob.address),
+                    oob.local_name.unwrap_or_default()
+                )
+                .trim_end()
+                .to_string();
+            }
+        }
+        if let Some(Ok(value)) = self.json_value() {
+            return format!("JSON {}", value);
+        }
+        if let Some(mime_type) = self.mime_type() {
+            return match std::str::from_utf8(&self.payload) {
+                Ok(text) if is_text_mime_type(mime_type) => format!("{} {}", mime_type, text),
+                _ => format!("{}, {} bytes", mime_type, self.payload.len()),
+            };
+        }
+        if let Some(name) = self.external_type() {
+            return format!("External {}, {} bytes", name, self.payload.len());
+        }
+        format!(
+            "TNF {:#X}, type {:?}, {} byte payload",
+            self.tnf,
+            String::from_utf8_lossy(&self.record_type),
+            self.payload.len()
+        )
+    }
+}
+
+/// Parses the record at the start of `data`, returning it together with
+/// its header byte and the number of bytes it occupies. Fields follow the
+/// header in the order type length, payload length, ID length (when IL is
+/// set), type, ID, payload. Chunks are returned as they are.
+pub fn parse_ndef_record(data: &[u8]) -> Option<(NdefRecord, u8, usize)> {
+    let header = *data.first()?;
+    let type_length = *data.get(1)? as usize;
+    let mut offset = 2;
+
+    let payload_length = if header & FLAG_SR != 0 {
+        let length = *data.get(offset)? as usize;
+        offset += 1;
+        length
+    } else {
+        let bytes = data.get(offset..offset + 4)?;
+        offset += 4;
+        u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
+    };
+
+    let id_length = if header & FLAG_IL != 0 {
+        let length = *data.get(offset)? as usize;
+        offset += 1;
+        length
+    } else {
+        0
+    };
+
+    let record_type = data.get(offset..offset + type_length)?.to_vec();
+    offset += type_length;
+    let id = data.get(offset..offset + id_length)?.to_vec();
+    offset += id_length;
+    let payload = data
+        .get(offset..offset.checked_add(payload_length)?)?
+        .to_vec();
+    offset += payload_length;
+
+    let record = NdefRecord {
+        tnf: header & 0x07,
+        record_type,
+        id,
+        payload,
+    };
+    Some((record, header, offset))
+}
+
+/// Parses every record of an NDEF message, reassembling chunked records
+/// (NDEF 1.0 section 3.2.3) into one logical record each.
+pub fn parse_ndef_message(data: &[u8]) -> Result<Vec<NdefRecord>, Error> {
+    let mut records = Vec::new();
+    let mut chunked: Option<NdefRecord> = None;
+    let mut offset = 0;
+
+    while offset < data.len() {
+        let (record, header, length) = parse_ndef_record(&data[offset..]).ok_or_else(|| {
+            Error::Ndef(format!(
+                "Invalid or incomplete NDEF record at offset {}.",
+                offset
+            ))
+        })?;
+        let chunk_follows = header & FLAG_CF != 0;
+
+        match chunked.as_mut() {
+            None if record.tnf == TNF_UNCHANGED => {
+                return Err(Error::Ndef(format!(
+                    "NDEF record at offset {} uses TNF 0x06 (Unchanged) outside a chunked record.",
+                    offset
+                )))
+            }
+            None if chunk_follows => chunked = Some(record),
+            None => records.push(record),
+            Some(_) if record.tnf != TNF_UNCHANGED => {
+                return Err(Error::Ndef(format!(
+                    "Chunk at offset {} must use TNF 0x06 (Unchanged), not {:#04X}.",
+                    offset, record.tnf
+                )))
+            }
+            Some(_) if !record.record_type.is_empty() || header & FLAG_IL != 0 => {
+                return Err(Error::Ndef(format!(
+                    "Chunk at offset {} has a type or ID; only the first chunk may.",
+                    offset
+                )))
+            }
+            Some(first) => {
+                first.payload.extend_from_slice(&record.payload);
+                if !chunk_follows {
+                    records.extend(chunked.take());
+                }
+            }
+        }
+
+        offset += length;
+        if header & FLAG_ME != 0 {
+            if chunk_follows {
+                return Err(Error::Ndef(format!(
+                    "Chunk at offset {} ends the message before its record is complete.",
+                    offset - length
+                )));
+            }
+            break;
+        }
+    }
+
+    if chunked.is_some() {
+        return Err(Error::Ndef(
+            "NDEF message ends inside a chunked record.".into(),
+        ));
+    }
+    Ok(records)
+}
+
+/// Strict variant of [`parse_ndef_message`]: any violation of the record
+/// rules rejects the whole message.
+pub fn parse_ndef_message_strict(data: &[u8]) -> Result<Vec<NdefRecord>, Vec<Violation>> {
+    let violations = validate_ndef_message(data);
+    match parse_ndef_message(data) {
+        Ok(records) if violations.is_empty() => Ok(records),
+        _ => Err(violations),
+    }
+}
+
+/// Warning for an NDEF message of `length` bytes that does not fit on `chip`.
+pub fn capacity_warning(chip: Chip, length: usize) -> Option<String> {
+    let capacity = chip.ndef_capacity();
+    (length > capacity).then(|| {
+        format!(
+            "Warning: the {} byte NDEF message exceeds the {} byte capacity of {}.",
+            length, capacity, chip
+        )
+    })
+}
+
+/// Encodes `records` as one NDEF message, using short records where possible.
+/// Fails when a record type or ID does not fit its one-byte length field.
+pub fn encode_ndef_message(records: &[NdefRecord]) -> Result<Vec<u8>, Error> {
+    let mut message = Vec::new();
+
+    for (index, record) in records.iter().enumerate() {
+        if record.record_type.len() > 255 {
+            return Err(Error::Ndef(format!(
+                "Type of record {} is {} bytes long, the limit is 255",
+                index,
+                record.record_type.len()
+            )));
+        }
+        if record.id.len() > 255 {
+            return Err(Error::Ndef(format!(
+                "ID of record {} is {} bytes long, the limit is 255",
+                index,
+                record.id.len()
+            )));
+        }
+        let mut header = record.tnf & 0x07;
+        if index == 0 {
+            header |= FLAG_MB;
+        }
+        if index + 1 == records.len() {
+            header |= FLAG_ME;
+        }
+        if record.payload.len() < 256 {
+            header |= FLAG_SR;
+        }
+        if !record.id.is_empty() {
+            header |= FLAG_IL;
+        }
+
+        message.push(header);
+        message.push(record.record_type.len() as u8);
+        if header & FLAG_SR != 0 {
+            message.push(record.payload.len() as u8);
+        } else {
+            message.extend_from_slice(&(record.payload.len() as u32).to_be_bytes());
+        }
+        if !record.id.is_empty() {
+            message.push(record.id.len() as u8);
+        }
+        message.extend_from_slice(&record.record_type);
+        message.extend_from_slice(&record.id);
+        message.extend_from_slice(&record.payload);
+    }
+
+    Ok(message)
+}
@@ -0,0 +1,167 @@
+use super::{
+    decode_text, decode_uri, encode_ndef_message, parse_ndef_message, NdefRecord, TNF_MIME_MEDIA,
+    TNF_WELL_KNOWN,
+};
+use crate::error::Error;
+
+/// Recommended action of a Smart Poster ("act" record).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Open the URI, dial the number, send the message...
+    Do,
+    /// Save it for later.
+    Save,
+    /// Open it for editing.
+    Edit,
+}
+
+impl Action {
+    fn code(self) -> u8 {
+        match self {
+            Action::Do => 0x00,
+            Action::Save => 0x01,
+            Action::Edit => 0x02,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Action> {
+        match code {
+            0x00 => Some(Action::Do),
+            0x01 => Some(Action::Save),
+            0x02 => Some(Action::Edit),
+            _ => None,
+        }
+    }
+}
+
+/// Smart Poster ("Sp"): a URI with titles, an action and optional
+/// size, type and icons, carried as a nested NDEF message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmartPoster {
+    pub uri: String,
+    /// `(language, text)` pairs, at most one per language.
+    pub titles: Vec<(String, String)>,
+    pub action: Option<Action>,
+    /// Size in bytes of the object the URI points to ("s").
+    pub size: Option<u32>,
+    /// MIME type of the object the URI points to ("t").
+    pub mime_type: Option<String>,
+    /// Icon records, MIME `image/*` or `video/*`.
+    pub icons: Vec<NdefRecord>,
+}
+
+impl SmartPoster {
+    pub fn new(uri: &str) -> SmartPoster {
+        SmartPoster {
+            uri: uri.to_string(),
+            titles: Vec::new(),
+            action: None,
+            size: None,
+            mime_type: None,
+            icons: Vec::new(),
+        }
+    }
+
+    /// Title in `language`, falling back to the first one.
+    pub fn title(&self, language: &str) -> Option<&str> {
+        self.titles
+            .iter()
+            .find(|(lang, _)| lang.eq_ignore_ascii_case(language))
+            .or_else(|| self.titles.first())
+            .map(|(_, text)| text.as_str())
+    }
+
+    /// The nested message: URI, titles, action, size, type, then icons.
+    pub fn records(&self) -> Result<Vec<NdefRecord>, Error> {
+        let mut records = vec![NdefRecord::uri(&self.uri)];
+        for (language, text) in &self.titles {
+            records.push(NdefRecord::text(language, text)?);
+        }
+        if let Some(action) = self.action {
+            records.push(NdefRecord::new(TNF_WELL_KNOWN, b"act", vec![action.code()]));
+        }
+        if let Some(size) = self.size {
+            records.push(NdefRecord::new(
+                TNF_WELL_KNOWN,
+                b"s",
+                size.to_be_bytes().to_vec(),
+            ));
+        }
+        if let Some(mime_type) = &self.mime_type {
+            records.push(NdefRecord::new(
+                TNF_WELL_KNOWN,
+                b"t",
+                mime_type.as_bytes().to_vec(),
+            ));
+        }
+        records.extend(self.icons.iter().cloned());
+        Ok(records)
+    }
+
+    /// Fails when a title's language code or an icon record's type or ID is
+    /// too long to encode.
+    pub fn to_record(&self) -> Result<NdefRecord, Error> {
+        Ok(NdefRecord::new(
+            TNF_WELL_KNOWN,
+            b"Sp",
+            encode_ndef_message(&self.records()?)?,
+        ))
+    }
+
+    /// Decodes a Smart Poster record. Unknown sub-records are ignored, as the
+    /// specification asks.
+    pub fn from_record(record: &NdefRecord) -> Result<SmartPoster, Error> {
+        if !record.is_well_known(b"Sp") {
+            return Err(Error::Ndef(format!(
+                "Not a Smart Poster record: {}",
+                record.summary()
+            )));
+        }
+
+        let mut uri = None;
+        let mut poster = SmartPoster::new("");
+        for sub_record in parse_ndef_message(&record.payload)? {
+            if sub_record.is_well_known(b"U") {
+                if uri.is_some() {
+                    return Err(Error::Ndef(
+                        "Smart Poster has more than one URI record".into(),
+                    ));
+                }
+                uri = Some(
+                    decode_uri(&sub_record.payload)
+                        .ok_or_else(|| Error::Ndef("Invalid Smart Poster URI".into()))?,
+                );
+            } else if sub_record.is_well_known(b"T") {
+                let title = decode_text(&sub_record.payload)
+                    .ok_or_else(|| Error::Ndef("Invalid Smart Poster title".into()))?;
+                poster.titles.push(title);
+            } else if sub_record.is_well_known(b"act") {
+                let code = *sub_record
+                    .payload
+                    .first()
+                    .ok_or_else(|| Error::Ndef("Empty action record".into()))?;
+                poster.action = Some(Action::from_code(code).ok_or_else(|| {
+                    Error::Ndef(format!("Unknown Smart Poster action {:#04X}", code))
+                })?);
+            } else if sub_record.is_well_known(b"s") {
+                let size: [u8; 4] = sub_record.payload[..]
+                    .try_into()
+                    .map_err(|_| Error::Ndef("Size record must be 4 bytes".into()))?;
+                poster.size = Some(u32::from_be_bytes(size));
+            } else if sub_record.is_well_known(b"t") {
+                poster.mime_type = Some(
+                    String::from_utf8(sub_record.payload)
+                        .map_err(|err| Error::Ndef(err.to_string()))?,
+                );
+            } else if sub_record.tnf == TNF_MIME_MEDIA
+                && (sub_record.record_type.starts_with(b"image/")
+                    || sub_record.record_type.starts_with(b"video/"))
+            {
+                poster.icons.push(sub_record);
+            }
+        }
+
+        poster.uri = uri.ok_or_else(|| Error::Ndef("Smart Poster has no URI record".into()))?;
+        Ok(poster)
+    }
+}
@@ -0,0 +1,38 @@
+use crate::error::Error;
+
+/// Decodes a Text record payload into its language code and text.
+pub fn decode_text(payload: &[u8]) -> Option<(String, String)> {
+    let (&status, rest) = payload.split_first()?;
+    let language_length = (status & 0x3F) as usize;
+    if rest.len() < language_length {
+        return None;
+    }
+    let (language, text) = rest.split_at(language_length);
+    let language = String::from_utf8_lossy(language).to_string();
+
+    let text = if status & 0x80 != 0 {
+        let units: Vec<u16> = text
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(text).to_string()
+    };
+    Some((language, text))
+}
+
+/// Encodes a UTF-8 Text record payload. The status byte leaves six bits for
+/// the length of the language code, so codes over 63 bytes are rejected.
+pub fn encode_text(language: &str, text: &str) -> Result<Vec<u8>, Error> {
+    if language.len() > 0x3F {
+        return Err(Error::Ndef(format!(
+            "Language code {:?} is longer than 63 bytes",
+            language
+        )));
+    }
+    let mut payload = vec![language.len() as u8];
+    payload.extend_from_slice(language.as_bytes());
+    payload.extend_from_slice(text.as_bytes());
+    Ok(payload)
+}
@@ -0,0 +1,74 @@
+/// URI identifier codes of the NFC Forum URI record type, indexed by code.
+pub const URI_PREFIXES: [&str; 36] = [
+    "",
+    "http://www.",
+    "https://www.",
+    "http://",
+    "https://",
+    "tel:",
+    "mailto:",
+    "ftp://anonymous:anonymous@",
+    "ftp://ftp.",
+    "ftps://",
+    "sftp://",
+    "smb://",
+    "nfs://",
+    "ftp://",
+    "dav://",
+    "news:",
+    "telnet://",
+    "imap:",
+    "rtsp://",
+    "urn:",
+    "pop:",
+    "sip:",
+    "sips:",
+    "tftp:",
+    "btspp://",
+    "btl2cap://",
+    "btgoep://",
+    "tcpobex://",
+    "irdaobex://",
+    "file://",
+    "urn:epc:id:",
+    "urn:epc:tag:",
+    "urn:epc:pat:",
+    "urn:epc:raw:",
+    "urn:epc:",
+    "urn:nfc:",
+];
+
+/// Expands a URI record payload into the full URI.
+pub fn decode_uri(payload: &[u8]) -> Option<String> {
+    let (&code, rest) = payload.split_first()?;
+    let prefix = URI_PREFIXES.get(code as usize)?;
+    Some(format!("{}{}", prefix, String::from_utf8_lossy(rest)))
+}
+
+/// Encodes `uri` as a URI record payload using the longest matching prefix.
+pub fn encode_uri(uri: &str) -> Vec<u8> {
+    let (code, prefix) = URI_PREFIXES
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, prefix)| uri.starts_with(*prefix))
+        .max_by_key(|(_, prefix)| prefix.len())
+        .unwrap_or((0, &""));
+
+    let mut payload = vec![code as u8];
+    payload.extend_from_slice(&uri.as_bytes()[prefix.len()..]);
+    payload
+}
+
+/// Encodes a URI template with `len` placeholder `0` characters in place of
+/// `marker`, returning the payload and the placeholder's offset within it.
+pub fn encode_uri_reserving(template: &str, marker: &str, len: usize) -> Option<(Vec<u8>, usize)> {
+    let position = template.find(marker)?;
+    let uri = template.replacen(marker, &"0".repeat(len), 1);
+    let payload = encode_uri(&uri);
+
+    // The identifier code byte replaces the abbreviated prefix.
+    let prefix_len = uri.len() + 1 - payload.len();
+    let offset = (position + 1).checked_sub(prefix_len)?;
+    (offset >= 1).then_some((payload, offset))
+}
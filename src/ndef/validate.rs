@@ -0,0 +1,278 @@
+use super::{
+    FLAG_CF, FLAG_IL, FLAG_MB, FLAG_ME, FLAG_SR, TNF_ABSOLUTE_URI, TNF_EMPTY, TNF_EXTERNAL,
+    TNF_MIME_MEDIA, TNF_UNCHANGED, TNF_UNKNOWN, TNF_WELL_KNOWN,
+};
+use std::fmt;
+
+/// One NDEF 1.0 rule that a message breaks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViolationKind {
+    MissingMessageBegin,
+    UnexpectedMessageBegin,
+    MissingMessageEnd,
+    /// Bytes after the record that sets ME.
+    TrailingData {
+        length: usize,
+    },
+    /// TNF 0x07 is reserved.
+    ReservedTnf,
+    /// Empty, Unknown and Unchanged records have no type; the others need one.
+    TypeLengthMismatch {
+        tnf: u8,
+        type_length: usize,
+    },
+    EmptyWithPayload {
+        payload_length: usize,
+    },
+    EmptyWithId,
+    /// A field runs past the end of the data.
+    Overrun {
+        field: &'static str,
+        needed: usize,
+        available: usize,
+    },
+    UnchangedOutsideChunk,
+    /// A middle or terminating chunk that does not use TNF 0x06.
+    ChunkTnf {
+        tnf: u8,
+    },
+    ChunkTypeOrId,
+    /// ME is set on a chunk that announces another chunk.
+    MessageEndInChunk,
+    /// The data ends before the terminating chunk.
+    UnterminatedChunk,
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViolationKind::MissingMessageBegin => write!(f, "first record does not set MB"),
+            ViolationKind::UnexpectedMessageBegin => write!(f, "MB set on a later record"),
+            ViolationKind::MissingMessageEnd => write!(f, "last record does not set ME"),
+            ViolationKind::TrailingData { length } => {
+                write!(f, "{} byte(s) after the record that sets ME", length)
+            }
+            ViolationKind::ReservedTnf => write!(f, "TNF 0x07 is reserved"),
+            ViolationKind::TypeLengthMismatch { tnf, type_length } => write!(
+                f,
+                "TNF {:#04X} does not allow type length {}",
+                tnf, type_length
+            ),
+            ViolationKind::EmptyWithPayload { payload_length } => write!(
+                f,
+                "TNF Empty record carries a {} byte payload",
+                payload_length
+            ),
+            ViolationKind::EmptyWithId => write!(f, "TNF Empty record carries an ID"),
+            ViolationKind::Overrun {
+                field,
+                needed,
+                available,
+            } => write!(
+                f,
+                "{} needs {} byte(s) but only {} remain",
+                field, needed, available
+            ),
+            ViolationKind::UnchangedOutsideChunk => {
+                write!(f, "TNF 0x06 (Unchanged) outside a chunked record")
+            }
+            ViolationKind::ChunkTnf { tnf } => {
+                write!(f, "chunk uses TNF {:#04X} instead of 0x06", tnf)
+            }
+            ViolationKind::ChunkTypeOrId => write!(f, "only the first chunk may have a type or ID"),
+            ViolationKind::MessageEndInChunk => write!(f, "ME set before the last chunk"),
+            ViolationKind::UnterminatedChunk => write!(f, "message ends inside a chunked record"),
+        }
+    }
+}
+
+/// A violation with the offset of the offending byte in the message and the
+/// index of the record it belongs to. Every chunk counts as its own record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub offset: usize,
+    pub record: usize,
+    pub kind: ViolationKind,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "record {} at offset {}: {}",
+            self.record, self.offset, self.kind
+        )
+    }
+}
+
+/// Checks `data` against the NDEF 1.0 record rules and returns every
+/// violation found. Scanning stops at the first field that runs past the end
+/// of the data. Empty data is accepted, as an empty NDEF TLV is the usual
+/// way to store an empty message.
+pub fn validate_ndef_message(data: &[u8]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let mut offset = 0;
+    let mut record = 0;
+    let mut in_chunk = false;
+    let mut ended = false;
+    let mut truncated = false;
+    let mut last_header = 0;
+
+    while offset < data.len() {
+        let mut report = |offset, kind| {
+            violations.push(Violation {
+                offset,
+                record,
+                kind,
+            })
+        };
+        let start = offset;
+        last_header = start;
+        let header = data[start];
+        let tnf = header & 0x07;
+
+        if record == 0 && header & FLAG_MB == 0 {
+            report(start, ViolationKind::MissingMessageBegin);
+        }
+        if record > 0 && header & FLAG_MB != 0 {
+            report(start, ViolationKind::UnexpectedMessageBegin);
+        }
+
+        let mut position = start + 1;
+        let Some(type_length) = take(data, &mut position, 1) else {
+            report(position, overrun(data, position, 1, "type length"));
+            truncated = true;
+            break;
+        };
+        let type_length = type_length[0] as usize;
+        let payload_length_offset = position;
+        let payload_length_size = if header & FLAG_SR != 0 { 1 } else { 4 };
+        let Some(payload_length) = take(data, &mut position, payload_length_size) else {
+            report(
+                position,
+                overrun(data, position, payload_length_size, "payload length"),
+            );
+            truncated = true;
+            break;
+        };
+        let payload_length = payload_length
+            .iter()
+            .fold(0usize, |length, &byte| (length << 8) | byte as usize);
+        let id_length = if header & FLAG_IL != 0 {
+            let Some(id_length) = take(data, &mut position, 1) else {
+                report(position, overrun(data, position, 1, "ID length"));
+                truncated = true;
+                break;
+            };
+            id_length[0] as usize
+        } else {
+            0
+        };
+
+        if tnf == 0x07 {
+            report(start, ViolationKind::ReservedTnf);
+        }
+        if in_chunk {
+            if tnf != TNF_UNCHANGED {
+                report(start, ViolationKind::ChunkTnf { tnf });
+            }
+            if type_length != 0 || header & FLAG_IL != 0 {
+                report(start + 1, ViolationKind::ChunkTypeOrId);
+            }
+        } else if tnf == TNF_UNCHANGED {
+            report(start, ViolationKind::UnchangedOutsideChunk);
+        } else {
+            let needs_type = matches!(
+                tnf,
+                TNF_WELL_KNOWN | TNF_MIME_MEDIA | TNF_ABSOLUTE_URI | TNF_EXTERNAL
+            );
+            let has_no_type = matches!(tnf, TNF_EMPTY | TNF_UNKNOWN);
+            if (needs_type && type_length == 0) || (has_no_type && type_length != 0) {
+                report(
+                    start + 1,
+                    ViolationKind::TypeLengthMismatch { tnf, type_length },
+                );
+            }
+        }
+        if tnf == TNF_EMPTY {
+            if payload_length != 0 {
+                report(
+                    payload_length_offset,
+                    ViolationKind::EmptyWithPayload { payload_length },
+                );
+            }
+            if id_length != 0 {
+                report(
+                    payload_length_offset + payload_length_size,
+                    ViolationKind::EmptyWithId,
+                );
+            }
+        }
+        if header & FLAG_ME != 0 && header & FLAG_CF != 0 {
+            report(start, ViolationKind::MessageEndInChunk);
+        }
+
+        for (field, length) in [
+            ("type", type_length),
+            ("ID", id_length),
+            ("payload", payload_length),
+        ] {
+            if take(data, &mut position, length).is_none() {
+                report(position, overrun(data, position, length, field));
+                truncated = true;
+                break;
+            }
+        }
+        if truncated {
+            break;
+        }
+
+        in_chunk = header & FLAG_CF != 0;
+        offset = position;
+        record += 1;
+        if header & FLAG_ME != 0 {
+            ended = true;
+            break;
+        }
+    }
+
+    let mut report = |offset, kind| {
+        violations.push(Violation {
+            offset,
+            record: record.saturating_sub(1),
+            kind,
+        })
+    };
+    if ended && offset < data.len() {
+        report(
+            offset,
+            ViolationKind::TrailingData {
+                length: data.len() - offset,
+            },
+        );
+    }
+    if !ended && !truncated && record > 0 {
+        if in_chunk {
+            report(data.len(), ViolationKind::UnterminatedChunk);
+        }
+        report(last_header, ViolationKind::MissingMessageEnd);
+    }
+    violations
+}
+
+/// Advances `position` past `length` bytes and returns them, or `None` when
+/// fewer remain.
+fn take<'a>(data: &'a [u8], position: &mut usize, length: usize) -> Option<&'a [u8]> {
+    let end = position.checked_add(length)?;
+    let bytes = data.get(*position..end)?;
+    *position = end;
+    Some(bytes)
+}
+
+fn overrun(data: &[u8], position: usize, needed: usize, field: &'static str) -> ViolationKind {
+    ViolationKind::Overrun {
+        field,
+        needed,
+        available: data.len().saturating_sub(position),
+    }
+}
@@ -0,0 +1,339 @@
+use super::{capacity_warning, encode_ndef_message, NdefRecord, TNF_MIME_MEDIA};
+use crate::chip::Chip;
+use crate::error::Error;
+use log::warn;
+use std::fmt;
+
+pub const MIME_VCARD: &str = "text/vcard";
+/// Older media types phones still write for contacts.
+const VCARD_ALIASES: [&str; 2] = ["text/x-vcard", "text/directory"];
+/// Longest content line before folding (RFC 6350 section 3.2).
+const MAX_LINE: usize = 75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VcardVersion {
+    /// RFC 2426.
+    V3,
+    /// RFC 6350.
+    V4,
+}
+
+impl fmt::Display for VcardVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            VcardVersion::V3 => "3.0",
+            VcardVersion::V4 => "4.0",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Phone {
+    pub number: String,
+    /// `TYPE` values such as `cell` or `work`, in lower case.
+    pub kinds: Vec<String>,
+}
+
+impl Phone {
+    pub fn new(number: &str, kinds: &[&str]) -> Phone {
+        Phone {
+            number: number.to_string(),
+            kinds: kinds.iter().map(|kind| kind.to_ascii_lowercase()).collect(),
+        }
+    }
+}
+
+/// The contact fields a business-card tag carries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Contact {
+    pub given_name: String,
+    pub family_name: String,
+    pub phones: Vec<Phone>,
+    pub emails: Vec<String>,
+    pub organization: Option<String>,
+    pub url: Option<String>,
+}
+
+impl Contact {
+    /// Given and family name as shown to the user (FN).
+    pub fn formatted_name(&self) -> String {
+        [self.given_name.as_str(), self.family_name.as_str()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Encodes the contact as a vCard with CRLF line endings and folded lines.
+    pub fn to_vcard(&self, version: VcardVersion) -> String {
+        let mut lines = vec![
+            "BEGIN:VCARD".to_string(),
+            format!("VERSION:{}", version),
+            format!(
+                "N:{};{};;;",
+                escape(&self.family_name),
+                escape(&self.given_name)
+            ),
+            format!("FN:{}", escape(&self.formatted_name())),
+        ];
+        if let Some(organization) = &self.organization {
+            lines.push(format!("ORG:{}", escape(organization)));
+        }
+        for phone in &self.phones {
+            let kinds = if phone.kinds.is_empty() {
+                String::new()
+            } else {
+                format!(";TYPE={}", phone.kinds.join(","))
+            };
+            lines.push(match version {
+                VcardVersion::V3 => format!("TEL{}:{}", kinds, escape(&phone.number)),
+                VcardVersion::V4 => format!(
+                    "TEL;VALUE=uri{}:tel:{}",
+                    kinds,
+                    phone.number.replace(char::is_whitespace, "")
+                ),
+            });
+        }
+        for email in &self.emails {
+            lines.push(match version {
+                VcardVersion::V3 => format!("EMAIL;TYPE=INTERNET:{}", escape(email)),
+                VcardVersion::V4 => format!("EMAIL:{}", escape(email)),
+            });
+        }
+        if let Some(url) = &self.url {
+            lines.push(format!("URL:{}", url));
+        }
+        lines.push("END:VCARD".to_string());
+
+        lines.iter().map(|line| fold(line) + "\r\n").collect()
+    }
+
+    /// Parses a vCard 3.0 or 4.0. Properties other than N, FN, TEL, EMAIL,
+    /// ORG and URL are ignored; only the organization name of ORG is kept.
+    pub fn parse_vcard(text: &str) -> Result<Contact, Error> {
+        let unfolded = text
+            .replace("\r\n ", "")
+            .replace("\r\n\t", "")
+            .replace("\n ", "")
+            .replace("\n\t", "");
+        let mut lines = unfolded.lines().filter(|line| !line.trim().is_empty());
+
+        if !lines
+            .next()
+            .is_some_and(|line| line.trim().eq_ignore_ascii_case("BEGIN:VCARD"))
+        {
+            return Err(Error::Ndef("vCard does not start with BEGIN:VCARD".into()));
+        }
+
+        let mut contact = Contact::default();
+        let mut version = None;
+        let mut formatted_name = None;
+        let mut has_name = false;
+        let mut ended = false;
+        for line in lines {
+            let (head, value) = split_property(line)
+                .ok_or_else(|| Error::Ndef(format!("vCard line without a value: {:?}", line)))?;
+            let mut parameters = head.split(';');
+            let name = parameters.next().unwrap_or_default();
+            // Drop the group prefix, e.g. "item1.TEL".
+            let name = name.rsplit('.').next().unwrap_or(name).to_ascii_uppercase();
+            let kinds = || types(parameters.clone());
+
+            match name.as_str() {
+                "END" if value.trim().eq_ignore_ascii_case("VCARD") => {
+                    ended = true;
+                    break;
+                }
+                "VERSION" => version = Some(value.trim().to_string()),
+                "N" => {
+                    let mut parts = split_unescaped(value, ';').into_iter();
+                    contact.family_name = parts.next().unwrap_or_default();
+                    contact.given_name = parts.next().unwrap_or_default();
+                    has_name = true;
+                }
+                "FN" => formatted_name = Some(unescape(value)),
+                "ORG" => contact.organization = split_unescaped(value, ';').into_iter().next(),
+                "TEL" => {
+                    let number = unescape(value);
+                    let number = match number.get(..4) {
+                        Some(scheme) if scheme.eq_ignore_ascii_case("tel:") => {
+                            number[4..].to_string()
+                        }
+                        _ => number,
+                    };
+                    contact.phones.push(Phone {
+                        number,
+                        kinds: kinds(),
+                    });
+                }
+                "EMAIL" => contact.emails.push(unescape(value)),
+                "URL" => contact.url = Some(unescape(value)),
+                _ => {}
+            }
+        }
+
+        if !ended {
+            return Err(Error::Ndef("vCard has no END:VCARD".into()));
+        }
+        match version.as_deref() {
+            Some("3.0" | "4.0") => {}
+            Some(version) => {
+                return Err(Error::Ndef(format!(
+                    "Unsupported vCard version {}",
+                    version
+                )))
+            }
+            None => return Err(Error::Ndef("vCard has no VERSION".into())),
+        }
+        if !has_name {
+            match formatted_name {
+                Some(name) => contact.given_name = name,
+                None => return Err(Error::Ndef("vCard has neither N nor FN".into())),
+            }
+        }
+        Ok(contact)
+    }
+
+    pub fn to_record(&self, version: VcardVersion) -> NdefRecord {
+        NdefRecord::new(
+            TNF_MIME_MEDIA,
+            MIME_VCARD.as_bytes(),
+            self.to_vcard(version).into_bytes(),
+        )
+    }
+
+    /// Like [`Contact::to_record`], logging a warning when the resulting
+    /// message does not fit on `chip`.
+    pub fn to_record_for(&self, chip: Chip, version: VcardVersion) -> NdefRecord {
+        let record = self.to_record(version);
+        let length = encode_ndef_message(std::slice::from_ref(&record))
+            .expect("text/vcard record")
+            .len();
+        if let Some(warning) = capacity_warning(chip, length) {
+            warn!("{}", warning);
+        }
+        record
+    }
+
+    /// Decodes a `text/vcard` (or `text/x-vcard`) record.
+    pub fn from_record(record: &NdefRecord) -> Result<Contact, Error> {
+        if !record.mime_type().is_some_and(is_vcard_mime_type) {
+            return Err(Error::Ndef(format!(
+                "Not a vCard record: {}",
+                record.summary()
+            )));
+        }
+        let text =
+            std::str::from_utf8(&record.payload).map_err(|err| Error::Ndef(err.to_string()))?;
+        Contact::parse_vcard(text)
+    }
+}
+
+/// `true` for `text/vcard` and the older aliases, ignoring parameters.
+pub(super) fn is_vcard_mime_type(mime_type: &str) -> bool {
+    let essence = mime_type.split(';').next().unwrap_or_default().trim();
+    essence.eq_ignore_ascii_case(MIME_VCARD)
+        || VCARD_ALIASES
+            .iter()
+            .any(|alias| essence.eq_ignore_ascii_case(alias))
+}
+
+/// Splits a content line at the first colon outside a quoted parameter value.
+fn split_property(line: &str) -> Option<(&str, &str)> {
+    let mut quoted = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ':' if !quoted => return Some((&line[..index], &line[index + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Lower-case TYPE values, including vCard 3.0's bare `TEL;CELL:` form.
+fn types<'a>(parameters: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut kinds = Vec::new();
+    for parameter in parameters {
+        let values = match parameter.split_once('=') {
+            Some((key, values)) if key.eq_ignore_ascii_case("TYPE") => values,
+            Some(_) => continue,
+            None => parameter,
+        };
+        kinds.extend(
+            values
+                .split(',')
+                .map(|kind| kind.trim_matches('"').to_ascii_lowercase())
+                .filter(|kind| !kind.is_empty()),
+        );
+    }
+    kinds
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ',' | ';' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Splits a structured value at unescaped `separator`s and unescapes each part.
+fn split_unescaped(value: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (index, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == separator {
+            parts.push(unescape(&value[start..index]));
+            start = index + 1;
+        }
+    }
+    parts.push(unescape(&value[start..]));
+    parts
+}
+
+/// Folds `line` into physical lines of at most 75 bytes, never splitting a
+/// UTF-8 character.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE * 3);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
@@ -0,0 +1,312 @@
+use super::{NdefRecord, TNF_MIME_MEDIA};
+use crate::error::Error;
+use std::fmt;
+
+pub const MIME_WSC: &str = "application/vnd.wfa.wsc";
+
+/// WSC attribute types (Wi-Fi Simple Configuration 2.0, section 12).
+const AUTH_TYPE: u16 = 0x1003;
+const CREDENTIAL: u16 = 0x100E;
+const ENCRYPTION_TYPE: u16 = 0x100F;
+const MAC_ADDRESS: u16 = 0x1020;
+const NETWORK_INDEX: u16 = 0x1026;
+const NETWORK_KEY: u16 = 0x1027;
+const SSID: u16 = 0x1045;
+const VENDOR_EXTENSION: u16 = 0x1049;
+const VERSION: u16 = 0x104A;
+
+/// Wi-Fi Alliance vendor ID and its Version2 subelement.
+const WFA_VENDOR_ID: [u8; 3] = [0x00, 0x37, 0x2A];
+const VERSION2: u8 = 0x00;
+/// Version attribute value required for compatibility with WSC 1.0 readers.
+const LEGACY_VERSION: u8 = 0x10;
+
+/// Authentication Type attribute values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthType {
+    Open,
+    WpaPersonal,
+    Shared,
+    WpaEnterprise,
+    Wpa2Enterprise,
+    Wpa2Personal,
+    /// WPA and WPA2 Personal mixed mode.
+    WpaWpa2Personal,
+}
+
+impl AuthType {
+    pub fn code(self) -> u16 {
+        match self {
+            AuthType::Open => 0x0001,
+            AuthType::WpaPersonal => 0x0002,
+            AuthType::Shared => 0x0004,
+            AuthType::WpaEnterprise => 0x0008,
+            AuthType::Wpa2Enterprise => 0x0010,
+            AuthType::Wpa2Personal => 0x0020,
+            AuthType::WpaWpa2Personal => 0x0022,
+        }
+    }
+
+    pub fn from_code(code: u16) -> Option<AuthType> {
+        match code {
+            0x0001 => Some(AuthType::Open),
+            0x0002 => Some(AuthType::WpaPersonal),
+            0x0004 => Some(AuthType::Shared),
+            0x0008 => Some(AuthType::WpaEnterprise),
+            0x0010 => Some(AuthType::Wpa2Enterprise),
+            0x0020 => Some(AuthType::Wpa2Personal),
+            0x0022 => Some(AuthType::WpaWpa2Personal),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AuthType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AuthType::Open => "open",
+            AuthType::WpaPersonal => "WPA-Personal",
+            AuthType::Shared => "shared",
+            AuthType::WpaEnterprise => "WPA-Enterprise",
+            AuthType::Wpa2Enterprise => "WPA2-Enterprise",
+            AuthType::Wpa2Personal => "WPA2-Personal",
+            AuthType::WpaWpa2Personal => "WPA/WPA2-Personal",
+        })
+    }
+}
+
+/// Encryption Type attribute values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionType {
+    None,
+    Wep,
+    Tkip,
+    Aes,
+    /// AES and TKIP mixed mode.
+    AesTkip,
+}
+
+impl EncryptionType {
+    pub fn code(self) -> u16 {
+        match self {
+            EncryptionType::None => 0x0001,
+            EncryptionType::Wep => 0x0002,
+            EncryptionType::Tkip => 0x0004,
+            EncryptionType::Aes => 0x0008,
+            EncryptionType::AesTkip => 0x000C,
+        }
+    }
+
+    pub fn from_code(code: u16) -> Option<EncryptionType> {
+        match code {
+            0x0001 => Some(EncryptionType::None),
+            0x0002 => Some(EncryptionType::Wep),
+            0x0004 => Some(EncryptionType::Tkip),
+            0x0008 => Some(EncryptionType::Aes),
+            0x000C => Some(EncryptionType::AesTkip),
+            _ => None,
+        }
+    }
+}
+
+/// One network from a WSC configuration token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WifiCredential {
+    pub ssid: String,
+    /// Passphrase, or the PSK as 64 hex digits. Empty for open networks.
+    pub network_key: String,
+    pub auth_type: AuthType,
+    pub encryption_type: EncryptionType,
+    /// Access point MAC address; broadcast when any AP of the network will do.
+    pub mac_address: [u8; 6],
+    /// Version2 from the WFA vendor extension, e.g. 0x20 for WSC 2.0.
+    pub version2: Option<u8>,
+}
+
+impl WifiCredential {
+    /// WPA2-Personal network with AES, or an open one when `network_key` is
+    /// empty. The SSID must be 1-32 bytes, and the key a passphrase of 8-63
+    /// ASCII characters or a PSK of 64 hex digits.
+    pub fn new(ssid: &str, network_key: &str) -> Result<WifiCredential, Error> {
+        if ssid.is_empty() || ssid.len() > 32 {
+            return Err(Error::Invalid(format!(
+                "SSID is {} bytes long, it must be 1-32",
+                ssid.len()
+            )));
+        }
+        let (auth_type, encryption_type) = if network_key.is_empty() {
+            (AuthType::Open, EncryptionType::None)
+        } else {
+            (AuthType::Wpa2Personal, EncryptionType::Aes)
+        };
+        let passphrase = (8..=63).contains(&network_key.len())
+            && network_key.bytes().all(|byte| (0x20..0x7F).contains(&byte));
+        let psk =
+            network_key.len() == 64 && network_key.bytes().all(|byte| byte.is_ascii_hexdigit());
+        if !network_key.is_empty() && !passphrase && !psk {
+            return Err(Error::Invalid(
+                "WPA2 key must be a passphrase of 8-63 ASCII characters or 64 hex digits".into(),
+            ));
+        }
+        Ok(WifiCredential {
+            ssid: ssid.to_string(),
+            network_key: network_key.to_string(),
+            auth_type,
+            encryption_type,
+            mac_address: [0xFF; 6],
+            version2: Some(0x20),
+        })
+    }
+
+    /// Encodes the Version, Credential and vendor extension attributes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut credential = Vec::new();
+        push_attribute(&mut credential, NETWORK_INDEX, &[0x01]);
+        push_attribute(&mut credential, SSID, self.ssid.as_bytes());
+        push_attribute(
+            &mut credential,
+            AUTH_TYPE,
+            &self.auth_type.code().to_be_bytes(),
+        );
+        push_attribute(
+            &mut credential,
+            ENCRYPTION_TYPE,
+            &self.encryption_type.code().to_be_bytes(),
+        );
+        push_attribute(&mut credential, NETWORK_KEY, self.network_key.as_bytes());
+        push_attribute(&mut credential, MAC_ADDRESS, &self.mac_address);
+
+        let mut data = Vec::new();
+        push_attribute(&mut data, VERSION, &[LEGACY_VERSION]);
+        push_attribute(&mut data, CREDENTIAL, &credential);
+        if let Some(version2) = self.version2 {
+            let mut extension = WFA_VENDOR_ID.to_vec();
+            extension.extend_from_slice(&[VERSION2, 0x01, version2]);
+            push_attribute(&mut data, VENDOR_EXTENSION, &extension);
+        }
+        data
+    }
+
+    /// Decodes the first Credential attribute of a WSC configuration token.
+    pub fn decode(data: &[u8]) -> Result<WifiCredential, Error> {
+        let attributes = parse_attributes(data)?;
+        let credential = find(&attributes, CREDENTIAL)
+            .ok_or_else(|| Error::Ndef("WSC data has no Credential".into()))?;
+        let fields = parse_attributes(credential)?;
+
+        let ssid =
+            find(&fields, SSID).ok_or_else(|| Error::Ndef("Credential has no SSID".into()))?;
+        let auth_type = u16_attribute(&fields, AUTH_TYPE, "Authentication Type")?;
+        let encryption_type = u16_attribute(&fields, ENCRYPTION_TYPE, "Encryption Type")?;
+        let mac_address = match find(&fields, MAC_ADDRESS) {
+            Some(mac) => mac
+                .try_into()
+                .map_err(|_| Error::Ndef("MAC Address must be 6 bytes".into()))?,
+            None => [0xFF; 6],
+        };
+        // Some writers put the vendor extension inside the Credential.
+        let version2 = [&attributes, &fields]
+            .into_iter()
+            .flat_map(|attributes| attributes.iter())
+            .filter(|(kind, _)| *kind == VENDOR_EXTENSION)
+            .find_map(|(_, value)| version2(value));
+
+        Ok(WifiCredential {
+            ssid: String::from_utf8_lossy(ssid).into_owned(),
+            network_key: String::from_utf8_lossy(find(&fields, NETWORK_KEY).unwrap_or_default())
+                .into_owned(),
+            auth_type: AuthType::from_code(auth_type).ok_or_else(|| {
+                Error::Ndef(format!("Unknown Authentication Type {:#06X}", auth_type))
+            })?,
+            encryption_type: EncryptionType::from_code(encryption_type).ok_or_else(|| {
+                Error::Ndef(format!("Unknown Encryption Type {:#06X}", encryption_type))
+            })?,
+            mac_address,
+            version2,
+        })
+    }
+
+    pub fn to_record(&self) -> NdefRecord {
+        NdefRecord::new(TNF_MIME_MEDIA, MIME_WSC.as_bytes(), self.encode())
+    }
+
+    pub fn from_record(record: &NdefRecord) -> Result<WifiCredential, Error> {
+        if !record
+            .mime_type()
+            .is_some_and(|mime_type| mime_type.eq_ignore_ascii_case(MIME_WSC))
+        {
+            return Err(Error::Ndef(format!(
+                "Not a WSC record: {}",
+                record.summary()
+            )));
+        }
+        WifiCredential::decode(&record.payload)
+    }
+}
+
+/// Attribute type and value.
+type Attribute<'a> = (u16, &'a [u8]);
+
+fn push_attribute(data: &mut Vec<u8>, kind: u16, value: &[u8]) {
+    data.extend_from_slice(&kind.to_be_bytes());
+    data.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    data.extend_from_slice(value);
+}
+
+/// Splits `data` into `(type, value)` attributes.
+fn parse_attributes(data: &[u8]) -> Result<Vec<Attribute<'_>>, Error> {
+    let mut attributes = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let header = data.get(offset..offset + 4).ok_or_else(|| {
+            Error::Ndef(format!(
+                "Truncated WSC attribute header at offset {}",
+                offset
+            ))
+        })?;
+        let kind = u16::from_be_bytes([header[0], header[1]]);
+        let length = u16::from_be_bytes([header[2], header[3]]) as usize;
+        let value = data.get(offset + 4..offset + 4 + length).ok_or_else(|| {
+            Error::Ndef(format!(
+                "WSC attribute {:#06X} at offset {} overruns the data",
+                kind, offset
+            ))
+        })?;
+        attributes.push((kind, value));
+        offset += 4 + length;
+    }
+    Ok(attributes)
+}
+
+fn find<'a>(attributes: &[Attribute<'a>], kind: u16) -> Option<&'a [u8]> {
+    attributes
+        .iter()
+        .find(|(other, _)| *other == kind)
+        .map(|(_, value)| *value)
+}
+
+fn u16_attribute(attributes: &[Attribute], kind: u16, name: &str) -> Result<u16, Error> {
+    match find(attributes, kind) {
+        Some(&[high, low]) => Ok(u16::from_be_bytes([high, low])),
+        Some(value) => Err(Error::Ndef(format!(
+            "{} must be 2 bytes, not {}",
+            name,
+            value.len()
+        ))),
+        None => Err(Error::Ndef(format!("Credential has no {}", name))),
+    }
+}
+
+/// Version2 from a WFA vendor extension: vendor ID, then ID-length-value
+/// subelements.
+fn version2(extension: &[u8]) -> Option<u8> {
+    let mut subelements = extension.strip_prefix(&WFA_VENDOR_ID[..])?;
+    while let [id, length, rest @ ..] = subelements {
+        let value = rest.get(..*length as usize)?;
+        if *id == VERSION2 {
+            return value.first().copied();
+        }
+        subelements = &rest[*length as usize..];
+    }
+    None
+}
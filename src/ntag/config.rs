@@ -0,0 +1,285 @@
+use super::{config_pages, read_page, write_page, Mirror, MirrorMode, Protection};
+use crate::chip::Chip;
+use crate::error::Error;
+use crate::transport::Transport;
+use log::{info, warn};
+use std::fmt;
+
+/// STRG_MOD_EN bit of the MIRROR byte (CFG0 byte 0).
+pub(super) const STRG_MOD_EN: u8 = 0x04;
+/// Bits of the ACCESS byte (CFG1 byte 0).
+pub(super) const PROT: u8 = 0x80;
+pub(super) const CFGLCK: u8 = 0x40;
+pub(super) const NFC_CNT_EN: u8 = 0x10;
+pub(super) const NFC_CNT_PWD_PROT: u8 = 0x08;
+pub(super) const AUTHLIM: u8 = 0x07;
+
+/// One configuration byte that differs; `index` 0-3 is CFG0, 4-7 is CFG1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigChange {
+    pub index: usize,
+    pub before: u8,
+    pub after: u8,
+}
+
+/// Decoded CFG0 and CFG1 pages of an NTAG21x. RFUI bits are kept as read so
+/// writing the configuration back never changes them.
+#[derive(Debug, Clone, Copy)]
+pub struct NtagConfig {
+    pub mirror: Mirror,
+    pub strong_modulation: bool,
+    /// First page protected by the password.
+    pub auth0: u8,
+    pub protection: Protection,
+    /// Permanently locks CFG0 and CFG1 against writes.
+    pub config_locked: bool,
+    pub counter_enabled: bool,
+    /// READ_CNT needs the password.
+    pub counter_password_protected: bool,
+    /// Failed PWD_AUTH attempts allowed (2^AUTHLIM), 0 for unlimited.
+    pub authlim: u8,
+    raw: [u8; 8],
+}
+
+impl NtagConfig {
+    /// Decodes CFG0 followed by CFG1.
+    pub fn decode(raw: [u8; 8]) -> NtagConfig {
+        let access = raw[4];
+        NtagConfig {
+            mirror: Mirror {
+                mode: MirrorMode::from_bits(raw[0] >> 6),
+                byte: (raw[0] >> 4) & 0b11,
+                page: raw[2],
+            },
+            strong_modulation: raw[0] & STRG_MOD_EN != 0,
+            auth0: raw[3],
+            protection: if access & PROT != 0 {
+                Protection::ReadWrite
+            } else {
+                Protection::Write
+            },
+            config_locked: access & CFGLCK != 0,
+            counter_enabled: access & NFC_CNT_EN != 0,
+            counter_password_protected: access & NFC_CNT_PWD_PROT != 0,
+            authlim: access & AUTHLIM,
+            raw,
+        }
+    }
+
+    /// Encodes the fields back into CFG0 and CFG1.
+    pub fn encode(&self) -> [u8; 8] {
+        let mut raw = self.raw;
+        raw[0] = (raw[0] & !(0xF0 | STRG_MOD_EN))
+            | (self.mirror.mode.bits() << 6)
+            | ((self.mirror.byte & 0b11) << 4)
+            | flag(self.strong_modulation, STRG_MOD_EN);
+        raw[2] = self.mirror.page;
+        raw[3] = self.auth0;
+        raw[4] = (raw[4] & !(PROT | CFGLCK | NFC_CNT_EN | NFC_CNT_PWD_PROT | AUTHLIM))
+            | flag(self.protection == Protection::ReadWrite, PROT)
+            | flag(self.config_locked, CFGLCK)
+            | flag(self.counter_enabled, NFC_CNT_EN)
+            | flag(self.counter_password_protected, NFC_CNT_PWD_PROT)
+            | (self.authlim & AUTHLIM);
+        raw
+    }
+
+    /// Checks the fields against each other and the chip's memory layout.
+    pub fn validate(&self, chip: Chip) -> Result<(), Error> {
+        if !chip.is_ntag() {
+            return Err(Error::Invalid(format!(
+                "{} has no NTAG configuration pages",
+                chip
+            )));
+        }
+        if self.authlim > AUTHLIM {
+            return Err(Error::Invalid(format!(
+                "AUTHLIM {} is out of range 0-7",
+                self.authlim
+            )));
+        }
+        if self.mirror.byte > 3 {
+            return Err(Error::Invalid(format!(
+                "MIRROR_BYTE {} is out of range 0-3",
+                self.mirror.byte
+            )));
+        }
+        self.mirror.validate(chip)?;
+        if self.mirror.mode.uses_counter() && !self.counter_enabled {
+            return Err(Error::Invalid(
+                "Mirroring the counter needs NFC_CNT_EN".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Bytes that differ from `original`.
+    pub fn changes(&self, original: &NtagConfig) -> Vec<ConfigChange> {
+        let (before, after) = (original.encode(), self.encode());
+        (0..8)
+            .filter(|&index| before[index] != after[index])
+            .map(|index| ConfigChange {
+                index,
+                before: before[index],
+                after: after[index],
+            })
+            .collect()
+    }
+
+    /// Sets one field from its command-line name, e.g. `auth0=0x10` or `cfglck=on`.
+    pub fn set(&mut self, field: &str, value: &str) -> Result<(), Error> {
+        match field {
+            "mirror" => {
+                self.mirror.mode = match value {
+                    "off" => MirrorMode::Off,
+                    "uid" => MirrorMode::Uid,
+                    "cnt" => MirrorMode::Counter,
+                    "uid-cnt" => MirrorMode::UidCounter,
+                    _ => return Err(Error::Invalid(format!("Unknown mirror mode: {}", value))),
+                }
+            }
+            "mirror-page" => self.mirror.page = parse_byte(value)?,
+            "mirror-byte" => self.mirror.byte = parse_byte(value)?,
+            "strg-mod" => self.strong_modulation = parse_switch(value)?,
+            "auth0" => self.auth0 = parse_byte(value)?,
+            "prot" => {
+                self.protection = match value {
+                    "w" => Protection::Write,
+                    "rw" => Protection::ReadWrite,
+                    _ => {
+                        return Err(Error::Invalid(format!(
+                            "PROT must be w or rw, not {}",
+                            value
+                        )))
+                    }
+                }
+            }
+            "cfglck" => self.config_locked = parse_switch(value)?,
+            "cnt" => self.counter_enabled = parse_switch(value)?,
+            "cnt-pwd-prot" => self.counter_password_protected = parse_switch(value)?,
+            "authlim" => self.authlim = parse_byte(value)?,
+            _ => {
+                return Err(Error::Invalid(format!(
+                    "Unknown configuration field: {}",
+                    field
+                )))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Two configurations are equal when they encode to the same bytes.
+impl PartialEq for NtagConfig {
+    fn eq(&self, other: &Self) -> bool {
+        self.encode() == other.encode()
+    }
+}
+
+impl Eq for NtagConfig {}
+
+impl fmt::Display for NtagConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let on_off = |value: bool| if value { "on" } else { "off" };
+        writeln!(
+            f,
+            "Mirror:                {:?} at page {} byte {}",
+            self.mirror.mode, self.mirror.page, self.mirror.byte
+        )?;
+        writeln!(
+            f,
+            "Strong modulation:     {}",
+            on_off(self.strong_modulation)
+        )?;
+        writeln!(f, "AUTH0:                 {:#04X}", self.auth0)?;
+        writeln!(f, "Protection:            {:?}", self.protection)?;
+        writeln!(f, "Config lock (CFGLCK):  {}", on_off(self.config_locked))?;
+        writeln!(f, "NFC counter:           {}", on_off(self.counter_enabled))?;
+        writeln!(
+            f,
+            "Counter password:      {}",
+            on_off(self.counter_password_protected)
+        )?;
+        writeln!(f, "AUTHLIM:               {}", self.authlim)
+    }
+}
+
+/// Reads and decodes CFG0 and CFG1 at the chip's configuration address.
+pub fn read_config<T: Transport + ?Sized>(tx: &T, chip: Chip) -> Result<NtagConfig, Error> {
+    let cfg0 = config_pages(chip)?.start;
+    let mut raw = [0; 8];
+    raw[..4].copy_from_slice(&read_page(tx, cfg0)?);
+    raw[4..].copy_from_slice(&read_page(tx, cfg0 + 1)?);
+    Ok(NtagConfig::decode(raw))
+}
+
+/// Validates `updated` and writes only the configuration pages whose bytes
+/// differ from `original`. Returns the changed bytes.
+pub fn write_config<T: Transport + ?Sized>(
+    tx: &T,
+    chip: Chip,
+    original: &NtagConfig,
+    updated: &NtagConfig,
+) -> Result<Vec<ConfigChange>, Error> {
+    updated.validate(chip)?;
+    let changes = updated.changes(original);
+    if changes.is_empty() {
+        info!("Configuration unchanged.");
+        return Ok(changes);
+    }
+    if original.config_locked {
+        // CFGLCK locks CFG0 and CFG1 for good.
+        return Err(Error::LockedPage(config_pages(chip)?.start));
+    }
+    if updated.config_locked {
+        warn!("Setting CFGLCK permanently locks CFG0 and CFG1; this cannot be undone.");
+    }
+
+    let cfg0 = config_pages(chip)?.start;
+    let raw = updated.encode();
+    for page in 0..2 {
+        let bytes = page * 4..page * 4 + 4;
+        if changes.iter().any(|change| bytes.contains(&change.index)) {
+            let mut data = [0; 4];
+            data.copy_from_slice(&raw[bytes]);
+            write_page(tx, cfg0 + page, &data)?;
+        }
+    }
+    for change in &changes {
+        info!(
+            "CFG{} byte {}: {:02X} -> {:02X}",
+            change.index / 4,
+            change.index % 4,
+            change.before,
+            change.after
+        );
+    }
+    Ok(changes)
+}
+
+fn flag(set: bool, bit: u8) -> u8 {
+    if set {
+        bit
+    } else {
+        0
+    }
+}
+
+fn parse_switch(value: &str) -> Result<bool, Error> {
+    match value {
+        "on" | "1" | "true" => Ok(true),
+        "off" | "0" | "false" => Ok(false),
+        _ => Err(Error::Invalid(format!("Expected on or off, not {}", value))),
+    }
+}
+
+fn parse_byte(value: &str) -> Result<u8, Error> {
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|err| Error::Invalid(format!("Invalid value {}: {}", value, err)))
+}
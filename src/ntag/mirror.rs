@@ -0,0 +1,217 @@
+use super::config::NFC_CNT_EN;
+use super::{config_pages, read_page, transceive, write_page, READ_CNT};
+use crate::chip::Chip;
+use crate::error::Error;
+use crate::ndef::{encode_ndef_message, encode_uri_reserving, NdefRecord, TNF_WELL_KNOWN};
+use crate::tlv::{encode_tlv, NDEF_TLV, TERMINATOR_TLV};
+use crate::transport::Transport;
+use log::info;
+
+/// Address of the NFC counter for READ_CNT.
+const NFC_COUNTER: u8 = 0x02;
+/// Marker replaced by the mirror placeholder in URI templates.
+pub const MIRROR_MARKER: &str = "{mirror}";
+
+/// What the chip mirrors into the NDEF data as ASCII hex (MIRROR_CONF).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorMode {
+    Off,
+    /// 7-byte UID, 14 characters.
+    Uid,
+    /// 24-bit NFC counter, 6 characters.
+    Counter,
+    /// UID, an `x` separator and the counter, 21 characters.
+    UidCounter,
+}
+
+impl MirrorMode {
+    /// Number of bytes the mirror overwrites.
+    pub fn len(self) -> usize {
+        match self {
+            MirrorMode::Off => 0,
+            MirrorMode::Uid => 14,
+            MirrorMode::Counter => 6,
+            MirrorMode::UidCounter => 21,
+        }
+    }
+
+    pub fn is_empty(self) -> bool {
+        self == MirrorMode::Off
+    }
+
+    pub(super) fn bits(self) -> u8 {
+        match self {
+            MirrorMode::Off => 0b00,
+            MirrorMode::Uid => 0b01,
+            MirrorMode::Counter => 0b10,
+            MirrorMode::UidCounter => 0b11,
+        }
+    }
+
+    pub(super) fn from_bits(bits: u8) -> MirrorMode {
+        match bits & 0b11 {
+            0b01 => MirrorMode::Uid,
+            0b10 => MirrorMode::Counter,
+            0b11 => MirrorMode::UidCounter,
+            _ => MirrorMode::Off,
+        }
+    }
+
+    pub fn uses_counter(self) -> bool {
+        matches!(self, MirrorMode::Counter | MirrorMode::UidCounter)
+    }
+}
+
+/// Where and what the chip mirrors: MIRROR_CONF, MIRROR_PAGE and MIRROR_BYTE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mirror {
+    pub mode: MirrorMode,
+    pub page: u8,
+    /// Byte within `page` where the mirror starts, 0-3.
+    pub byte: u8,
+}
+
+impl Mirror {
+    /// Mirror starting at the absolute byte address `address`.
+    pub fn at(mode: MirrorMode, address: usize) -> Mirror {
+        Mirror {
+            mode,
+            page: (address / 4) as u8,
+            byte: (address % 4) as u8,
+        }
+    }
+
+    pub fn address(&self) -> usize {
+        self.page as usize * 4 + self.byte as usize
+    }
+
+    /// Checks that the mirrored text lies entirely inside user memory.
+    pub fn validate(&self, chip: Chip) -> Result<(), Error> {
+        if self.mode == MirrorMode::Off {
+            return Ok(());
+        }
+        let user = chip.user_pages();
+        let end = self.address() + self.mode.len();
+        if self.byte > 3 || (self.page as usize) < user.start || end > user.end * 4 {
+            return Err(Error::Invalid(format!(
+                "{:?} mirror at page {} byte {} does not fit in user pages {}-{}",
+                self.mode,
+                self.page,
+                self.byte,
+                user.start,
+                user.end - 1
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Reads the 24-bit NFC counter with READ_CNT (0x39).
+pub fn read_counter<T: Transport + ?Sized>(tx: &T) -> Result<u32, Error> {
+    let reply = transceive(tx, &[READ_CNT, NFC_COUNTER])?;
+    match reply[..] {
+        [low, middle, high, ..] => Ok(u32::from_le_bytes([low, middle, high, 0])),
+        _ => Err(Error::Tag(format!("READ_CNT returned {:02X?}", reply))),
+    }
+}
+
+/// Turns the NFC counter (NFC_CNT_EN) on or off. The counter only counts
+/// the first READ or FAST_READ after the tag enters the field.
+pub fn set_counter_enabled<T: Transport + ?Sized>(
+    tx: &T,
+    chip: Chip,
+    enabled: bool,
+) -> Result<(), Error> {
+    let cfg1 = config_pages(chip)?.start + 1;
+    let mut data = read_page(tx, cfg1)?;
+    if enabled {
+        data[0] |= NFC_CNT_EN;
+    } else {
+        data[0] &= !NFC_CNT_EN;
+    }
+    write_page(tx, cfg1, &data)?;
+    info!(
+        "NFC counter {}.",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    Ok(())
+}
+
+/// Reads MIRROR_CONF, MIRROR_BYTE and MIRROR_PAGE from CFG0.
+pub fn read_mirror<T: Transport + ?Sized>(tx: &T, chip: Chip) -> Result<Mirror, Error> {
+    let cfg0 = read_page(tx, config_pages(chip)?.start)?;
+    Ok(Mirror {
+        mode: MirrorMode::from_bits(cfg0[0] >> 6),
+        byte: (cfg0[0] >> 4) & 0b11,
+        page: cfg0[2],
+    })
+}
+
+/// Writes the mirror configuration, enabling the NFC counter first when the
+/// counter is mirrored.
+pub fn set_mirror<T: Transport + ?Sized>(tx: &T, chip: Chip, mirror: &Mirror) -> Result<(), Error> {
+    mirror.validate(chip)?;
+    if mirror.mode.uses_counter() {
+        set_counter_enabled(tx, chip, true)?;
+    }
+
+    let cfg0 = config_pages(chip)?.start;
+    let mut data = read_page(tx, cfg0)?;
+    data[0] = (data[0] & 0x0F) | (mirror.mode.bits() << 6) | (mirror.byte << 4);
+    data[2] = mirror.page;
+    write_page(tx, cfg0, &data)?;
+    info!(
+        "Mirror set to {:?} at page {} byte {}.",
+        mirror.mode, mirror.page, mirror.byte
+    );
+    Ok(())
+}
+
+/// Encodes `template` as a URI record in an NDEF TLV starting at page 4,
+/// with [`MIRROR_MARKER`] replaced by placeholder bytes for `mode`. Returns
+/// the bytes to write from page 4 and the matching mirror configuration.
+pub fn mirrored_uri(
+    chip: Chip,
+    template: &str,
+    mode: MirrorMode,
+) -> Result<(Vec<u8>, Mirror), Error> {
+    let (payload, placeholder) = encode_uri_reserving(template, MIRROR_MARKER, mode.len())
+        .ok_or_else(|| Error::Invalid(format!("URI template has no {} marker", MIRROR_MARKER)))?;
+    // Header, type length, payload length (1 byte when short, else 4), type "U".
+    let record_header = if payload.len() < 256 { 4 } else { 7 };
+    let message = encode_ndef_message(&[NdefRecord::new(TNF_WELL_KNOWN, b"U", payload)])?;
+
+    let tlv_header = encode_tlv(NDEF_TLV, &message).len() - message.len();
+    let address = chip.user_pages().start * 4 + tlv_header + record_header + placeholder;
+
+    let mut data = encode_tlv(NDEF_TLV, &message);
+    data.push(TERMINATOR_TLV);
+    if data.len() > chip.user_pages().len() * 4 {
+        return Err(Error::CapacityExceeded {
+            needed: data.len(),
+            capacity: chip.user_pages().len() * 4,
+            chip,
+        });
+    }
+
+    let mirror = Mirror::at(mode, address);
+    mirror.validate(chip)?;
+    Ok((data, mirror))
+}
+
+/// Writes a URI with a mirrored UID and/or counter and configures the mirror.
+pub fn write_mirrored_uri<T: Transport + ?Sized>(
+    tx: &T,
+    chip: Chip,
+    template: &str,
+    mode: MirrorMode,
+) -> Result<Mirror, Error> {
+    let (data, mirror) = mirrored_uri(chip, template, mode)?;
+    for (index, chunk) in data.chunks(4).enumerate() {
+        let mut page = [0; 4];
+        page[..chunk.len()].copy_from_slice(chunk);
+        write_page(tx, chip.user_pages().start + index, &page)?;
+    }
+    set_mirror(tx, chip, &mirror)?;
+    Ok(mirror)
+}
@@ -0,0 +1,246 @@
+mod config;
+mod mirror;
+mod originality;
+mod password;
+
+pub use config::{read_config, write_config, ConfigChange, NtagConfig};
+pub use mirror::{
+    mirrored_uri, read_counter, read_mirror, set_counter_enabled, set_mirror, write_mirrored_uri,
+    Mirror, MirrorMode, MIRROR_MARKER,
+};
+pub use originality::{
+    verify_originality, verify_signature, Originality, OriginalityReport, NTAG21X_PUBLIC_KEY,
+};
+
+pub use password::{
+    authenticate, protect, pwd_auth, read_protection, remove_protection, set_auth0, set_password,
+    set_protection, Password, PasswordTransport, Protection,
+};
+
+use crate::apdu::{is_successful_response, read_binary, update_binary};
+use crate::chip::Chip;
+use crate::error::Error;
+use crate::tlv::{encode_tlv, NDEF_TLV, TERMINATOR_TLV};
+use crate::transport::Transport;
+use log::{debug, warn};
+use std::ops::Range;
+
+/// Native NTAG21x command codes.
+pub const GET_VERSION: u8 = 0x60;
+pub const READ: u8 = 0x30;
+pub const FAST_READ: u8 = 0x3A;
+pub const WRITE: u8 = 0xA2;
+pub const READ_CNT: u8 = 0x39;
+pub const PWD_AUTH: u8 = 0x1B;
+pub const READ_SIG: u8 = 0x3C;
+
+/// Sends a native tag command through the reader's pass-through and returns
+/// the tag's reply.
+pub fn transceive<T: Transport + ?Sized>(tx: &T, command: &[u8]) -> Result<Vec<u8>, Error> {
+    tx.pass_through().exchange(&tx, command)
+}
+
+/// Pages asked for per FAST_READ; 128 bytes keeps the reply inside one
+/// reader frame.
+pub const FAST_READ_PAGES: usize = 32;
+
+/// Ways of reading Type 2 tag memory, fastest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadMethod {
+    /// Native FAST_READ (0x3A) of an arbitrary page range, NTAG21x only.
+    FastRead,
+    /// Native READ (0x30), 4 pages per command.
+    Read,
+    /// PC/SC READ BINARY, one page per command.
+    ReadBinary,
+}
+
+impl ReadMethod {
+    pub fn pages_per_command(self) -> usize {
+        match self {
+            ReadMethod::FastRead => FAST_READ_PAGES,
+            ReadMethod::Read => 4,
+            ReadMethod::ReadBinary => 1,
+        }
+    }
+}
+
+/// Reads 4 pages from `page` with native READ. The tag wraps around to
+/// page 0 past the end of its memory.
+pub fn read_four_pages<T: Transport + ?Sized>(tx: &T, page: usize) -> Result<[u8; 16], Error> {
+    let reply = transceive(tx, &[READ, page as u8])?;
+    reply
+        .get(..16)
+        .and_then(|data| data.try_into().ok())
+        .ok_or_else(|| {
+            Error::Tag(format!(
+                "READ of page {} returned {} bytes",
+                page,
+                reply.len()
+            ))
+        })
+}
+
+/// Reads `pages` with one native FAST_READ.
+pub fn fast_read<T: Transport + ?Sized>(tx: &T, pages: Range<usize>) -> Result<Vec<u8>, Error> {
+    if pages.is_empty() {
+        return Ok(Vec::new());
+    }
+    let reply = transceive(tx, &[FAST_READ, pages.start as u8, (pages.end - 1) as u8])?;
+    if reply.len() < pages.len() * 4 {
+        return Err(Error::Tag(format!(
+            "FAST_READ of pages {}-{} returned {} bytes",
+            pages.start,
+            pages.end - 1,
+            reply.len()
+        )));
+    }
+    Ok(reply[..pages.len() * 4].to_vec())
+}
+
+/// Reads `pages` of a Type 2 tag with as few round trips as possible:
+/// FAST_READ on NTAG, then READ, then READ BINARY when the reader has no
+/// pass-through. A chunk that fails once a method works is read again page
+/// by page, so unreadable pages come back as `None` individually.
+pub fn read_pages<T: Transport + ?Sized>(
+    tx: &T,
+    chip: Chip,
+    pages: Range<usize>,
+) -> Result<Vec<Option<[u8; 4]>>, Error> {
+    let mut methods: &[ReadMethod] = if chip.is_ntag() {
+        &[
+            ReadMethod::FastRead,
+            ReadMethod::Read,
+            ReadMethod::ReadBinary,
+        ]
+    } else {
+        &[ReadMethod::Read, ReadMethod::ReadBinary]
+    };
+    let mut method_works = false;
+    let mut result = Vec::with_capacity(pages.len());
+    let mut page = pages.start;
+
+    while page < pages.end {
+        let method = methods[0];
+        let count = method.pages_per_command().min(pages.end - page);
+        let chunk = match method {
+            ReadMethod::FastRead => fast_read(tx, page..page + count),
+            ReadMethod::Read => read_four_pages(tx, page).map(|data| data.to_vec()),
+            ReadMethod::ReadBinary => {
+                result.push(read_binary_page(tx, page)?);
+                page += 1;
+                continue;
+            }
+        };
+
+        match chunk {
+            Ok(data) => {
+                method_works = true;
+                result.extend(data.chunks_exact(4).take(count).map(|chunk| {
+                    let mut page = [0; 4];
+                    page.copy_from_slice(chunk);
+                    Some(page)
+                }));
+            }
+            Err(err) if !method_works => {
+                debug!("{:?} not available ({}), falling back.", method, err);
+                methods = &methods[1..];
+                continue;
+            }
+            Err(_) => {
+                for page in page..page + count {
+                    result.push(read_binary_page(tx, page)?);
+                }
+            }
+        }
+        page += count;
+    }
+    Ok(result)
+}
+
+/// Reads one page with READ BINARY, `None` when the tag refuses it.
+fn read_binary_page<T: Transport + ?Sized>(tx: &T, page: usize) -> Result<Option<[u8; 4]>, Error> {
+    let mut response_buf = [0; 256];
+    let response = tx.transmit(&read_binary(page as u8, 0x04), &mut response_buf)?;
+
+    if is_successful_response(response) && response.len() >= 6 {
+        let mut data = [0; 4];
+        data.copy_from_slice(&response[..4]);
+        Ok(Some(data))
+    } else {
+        warn!("Failed to read page {}: {:02X?}", page, response);
+        Ok(None)
+    }
+}
+
+/// Reads one 4-byte page with READ BINARY.
+pub fn read_page<T: Transport + ?Sized>(tx: &T, page: usize) -> Result<[u8; 4], Error> {
+    let mut response_buf = [0; 256];
+    let response = tx.transmit(&read_binary(page as u8, 0x04), &mut response_buf)?;
+
+    if !is_successful_response(response) || response.len() < 6 {
+        return Err(Error::status(
+            format!("Failed to read page {}", page),
+            response,
+        ));
+    }
+    let mut data = [0; 4];
+    data.copy_from_slice(&response[..4]);
+    Ok(data)
+}
+
+/// Writes one 4-byte page with UPDATE BINARY.
+pub fn write_page<T: Transport + ?Sized>(tx: &T, page: usize, data: &[u8; 4]) -> Result<(), Error> {
+    let mut response_buf = [0; 256];
+    let response = tx.transmit(&update_binary(page as u8, data), &mut response_buf)?;
+
+    if !is_successful_response(response) {
+        return Err(Error::status(
+            format!("Failed to write page {}", page),
+            response,
+        ));
+    }
+    Ok(())
+}
+
+/// Writes `message` as an NDEF TLV followed by a Terminator TLV from the
+/// first user page of a Type 2 tag.
+pub fn write_ndef_message<T: Transport + ?Sized>(
+    tx: &T,
+    chip: Chip,
+    message: &[u8],
+) -> Result<(), Error> {
+    if !chip.is_type2() {
+        return Err(Error::Tag(format!(
+            "Writing NDEF to {} is not supported",
+            chip
+        )));
+    }
+    if message.len() > chip.ndef_capacity() {
+        return Err(Error::CapacityExceeded {
+            needed: message.len(),
+            capacity: chip.ndef_capacity(),
+            chip,
+        });
+    }
+
+    let mut data = encode_tlv(NDEF_TLV, message);
+    data.push(TERMINATOR_TLV);
+    for (index, chunk) in data.chunks(4).enumerate() {
+        let mut page = [0; 4];
+        page[..chunk.len()].copy_from_slice(chunk);
+        write_page(tx, chip.user_pages().start + index, &page)?;
+    }
+    Ok(())
+}
+
+/// CFG0, CFG1, PWD and PACK pages of an NTAG21x chip.
+pub(crate) fn config_pages(chip: Chip) -> Result<Range<usize>, Error> {
+    match chip.config_pages() {
+        Some(pages) if chip.is_ntag() => Ok(pages),
+        _ => Err(Error::Tag(format!(
+            "{} has no NTAG configuration pages",
+            chip
+        ))),
+    }
+}
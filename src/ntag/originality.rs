@@ -0,0 +1,335 @@
+use super::{read_page, transceive, READ_SIG};
+use crate::chip::Chip;
+use crate::error::Error;
+use crate::transport::Transport;
+use std::fmt;
+
+/// NXP's public key for NTAG21x originality signatures (uncompressed point).
+pub const NTAG21X_PUBLIC_KEY: [u8; 33] = [
+    0x04, 0x49, 0x4E, 0x1A, 0x38, 0x6D, 0x3D, 0x3C, 0xFE, 0x3D, 0xC1, 0x0E, 0x5D, 0xE6, 0x8A, 0x49,
+    0x9B, 0x1C, 0x20, 0x2D, 0xB5, 0xB1, 0x32, 0x39, 0x3E, 0x89, 0xED, 0x19, 0xFE, 0x5B, 0xE8, 0xBC,
+    0x61,
+];
+
+/// secp128r1 domain parameters (SEC 2).
+const P: u128 = 0xFFFFFFFD_FFFFFFFF_FFFFFFFF_FFFFFFFF;
+const A: u128 = 0xFFFFFFFD_FFFFFFFF_FFFFFFFF_FFFFFFFC;
+const B: u128 = 0xE87579C1_1079F43D_D824993C_2CEE5ED3;
+const N: u128 = 0xFFFFFFFE_00000000_75A30D1B_9038A115;
+const G: Point = Point::Affine(
+    0x161FF752_8B899B2D_0C28607C_A52C5B86,
+    0xCF5AC839_5BAFEB13_C02DA292_DDED7A83,
+);
+
+/// Outcome of an originality check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Originality {
+    /// The signature over the UID verifies against NXP's key.
+    Genuine,
+    /// The tag has no valid NXP signature for its UID.
+    Counterfeit,
+    /// No published key is known for this chip, so nothing was checked.
+    UnknownChip,
+}
+
+impl fmt::Display for Originality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Originality::Genuine => "genuine NXP tag",
+            Originality::Counterfeit => "counterfeit: signature does not verify",
+            Originality::UnknownChip => "unknown chip: no originality key",
+        })
+    }
+}
+
+/// What [`verify_originality`] read from the tag and concluded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginalityReport {
+    pub chip: Chip,
+    pub uid: Vec<u8>,
+    /// `r || s`, absent when the chip was not checked.
+    pub signature: Option<[u8; 32]>,
+    pub result: Originality,
+}
+
+/// Reads the 7-byte UID and the READ_SIG (0x3C) signature and verifies the
+/// signature against NXP's NTAG21x key.
+pub fn verify_originality<T: Transport + ?Sized>(
+    tx: &T,
+    chip: Chip,
+) -> Result<OriginalityReport, Error> {
+    let page0 = read_page(tx, 0)?;
+    let page1 = read_page(tx, 1)?;
+    let mut uid = page0[..3].to_vec();
+    uid.extend_from_slice(&page1);
+
+    if !chip.is_ntag() {
+        return Ok(OriginalityReport {
+            chip,
+            uid,
+            signature: None,
+            result: Originality::UnknownChip,
+        });
+    }
+
+    let reply = transceive(tx, &[READ_SIG, 0x00])?;
+    let signature: [u8; 32] = reply
+        .get(..32)
+        .and_then(|signature| signature.try_into().ok())
+        .ok_or_else(|| Error::Tag(format!("READ_SIG returned {} bytes", reply.len())))?;
+
+    let result = if verify_signature(&NTAG21X_PUBLIC_KEY, &uid, &signature) {
+        Originality::Genuine
+    } else {
+        Originality::Counterfeit
+    };
+    Ok(OriginalityReport {
+        chip,
+        uid,
+        signature: Some(signature),
+        result,
+    })
+}
+
+/// Verifies a raw secp128r1 ECDSA signature `r || s` over `message`. NXP
+/// signs the UID itself, so `message` is used as the integer to sign without
+/// hashing.
+pub fn verify_signature(public_key: &[u8; 33], message: &[u8], signature: &[u8; 32]) -> bool {
+    let Some(public_key) = decode_point(public_key) else {
+        return false;
+    };
+    let r = u128::from_be_bytes(signature[..16].try_into().expect("16 bytes"));
+    let s = u128::from_be_bytes(signature[16..].try_into().expect("16 bytes"));
+    if r == 0 || r >= N || s == 0 || s >= N {
+        return false;
+    }
+
+    let e = message_to_integer(message);
+    let w = inv_mod(s, N);
+    let u1 = mul_mod(e, w, N);
+    let u2 = mul_mod(r, w, N);
+    match double_scalar_mul(u1, G, u2, public_key) {
+        Point::Affine(x, _) => x % N == r,
+        Point::Infinity => false,
+    }
+}
+
+/// Leftmost 128 bits of `message` as a big-endian integer, reduced mod n.
+fn message_to_integer(message: &[u8]) -> u128 {
+    let e = message
+        .iter()
+        .take(16)
+        .fold(0u128, |e, &byte| (e << 8) | byte as u128);
+    e % N
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Point {
+    Infinity,
+    Affine(u128, u128),
+}
+
+/// Decodes an uncompressed `04 || x || y` point and checks it lies on the curve.
+fn decode_point(bytes: &[u8; 33]) -> Option<Point> {
+    if bytes[0] != 0x04 {
+        return None;
+    }
+    let x = u128::from_be_bytes(bytes[1..17].try_into().ok()?);
+    let y = u128::from_be_bytes(bytes[17..].try_into().ok()?);
+    let point = Point::Affine(x, y);
+    (x < P && y < P && is_on_curve(point)).then_some(point)
+}
+
+fn is_on_curve(point: Point) -> bool {
+    match point {
+        Point::Infinity => true,
+        Point::Affine(x, y) => {
+            let rhs = add_mod(mul_mod(add_mod(mul_mod(x, x, P), A, P), x, P), B, P);
+            mul_mod(y, y, P) == rhs
+        }
+    }
+}
+
+fn point_add(p: Point, q: Point) -> Point {
+    let (Point::Affine(x1, y1), Point::Affine(x2, y2)) = (p, q) else {
+        return if p == Point::Infinity { q } else { p };
+    };
+    let lambda = if x1 == x2 {
+        if add_mod(y1, y2, P) == 0 {
+            return Point::Infinity;
+        }
+        // Doubling: (3x^2 + a) / 2y
+        let numerator = add_mod(mul_mod(3, mul_mod(x1, x1, P), P), A, P);
+        mul_mod(numerator, inv_mod(add_mod(y1, y1, P), P), P)
+    } else {
+        mul_mod(sub_mod(y2, y1, P), inv_mod(sub_mod(x2, x1, P), P), P)
+    };
+    let x3 = sub_mod(sub_mod(mul_mod(lambda, lambda, P), x1, P), x2, P);
+    let y3 = sub_mod(mul_mod(lambda, sub_mod(x1, x3, P), P), y1, P);
+    Point::Affine(x3, y3)
+}
+
+/// `a*p + b*q` with one shared double-and-add pass (Shamir's trick).
+fn double_scalar_mul(a: u128, p: Point, b: u128, q: Point) -> Point {
+    let pq = point_add(p, q);
+    let mut result = Point::Infinity;
+    for bit in (0..128).rev() {
+        result = point_add(result, result);
+        result = match ((a >> bit) & 1, (b >> bit) & 1) {
+            (1, 1) => point_add(result, pq),
+            (1, 0) => point_add(result, p),
+            (0, 1) => point_add(result, q),
+            _ => result,
+        };
+    }
+    result
+}
+
+fn add_mod(a: u128, b: u128, m: u128) -> u128 {
+    let (sum, carry) = a.overflowing_add(b);
+    if carry || sum >= m {
+        sum.wrapping_sub(m)
+    } else {
+        sum
+    }
+}
+
+fn sub_mod(a: u128, b: u128, m: u128) -> u128 {
+    if a >= b {
+        a - b
+    } else {
+        a.wrapping_sub(b).wrapping_add(m)
+    }
+}
+
+/// `a * b mod m` by double-and-add, since the product needs 256 bits.
+fn mul_mod(a: u128, b: u128, m: u128) -> u128 {
+    let a = a % m;
+    let mut result = 0;
+    for bit in (0..128).rev() {
+        result = add_mod(result, result, m);
+        if (b >> bit) & 1 == 1 {
+            result = add_mod(result, a, m);
+        }
+    }
+    result
+}
+
+/// Inverse modulo a prime `m`, by Fermat's little theorem.
+fn inv_mod(a: u128, m: u128) -> u128 {
+    let mut result = 1;
+    let mut base = a % m;
+    let mut exponent = m - 2;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exponent >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex;
+    use crate::sim::SimulatedTag;
+
+    /// Key pairs and signatures generated independently with
+    /// `d = 0x0123456789ABCDEF0011223344556677` and `d = 0xDEADBEEF`.
+    const VECTORS: [(&str, &str, &str); 2] = [
+        (
+            "04112233445566",
+            "041B9D07A4A7B5ECE086032A4AFE231336A22D290CBBAF36B8468E9D4A55FC6948",
+            "FFAEA8EEA4A67CC48E7CDC21EA828744BBAFB758D908AF32156A6A7BE4E70C1E",
+        ),
+        (
+            "04A1B2C3D4E5F6",
+            "0401C14DC16F7374F6B36E59E865D97EA1AC2AEF88C2742C5C110A9A999AE59549",
+            "A9C711A56A82A916C497E4CF6E4F14FD4BFF5144BFD98FAF6674690A784E88C9",
+        ),
+    ];
+
+    fn vector(index: usize) -> (Vec<u8>, [u8; 33], [u8; 32]) {
+        let (uid, key, signature) = VECTORS[index];
+        (
+            hex::decode(uid).unwrap(),
+            hex::decode(key).unwrap().try_into().unwrap(),
+            hex::decode(signature).unwrap().try_into().unwrap(),
+        )
+    }
+
+    #[test]
+    fn domain_parameters_are_consistent() {
+        assert!(is_on_curve(G));
+        assert!(decode_point(&NTAG21X_PUBLIC_KEY).is_some());
+        assert_eq!(double_scalar_mul(N, G, 0, Point::Infinity), Point::Infinity);
+    }
+
+    #[test]
+    fn nxp_key_lies_in_the_prime_order_group() {
+        let key = decode_point(&NTAG21X_PUBLIC_KEY).unwrap();
+        assert_eq!(
+            double_scalar_mul(N, key, 0, Point::Infinity),
+            Point::Infinity
+        );
+        assert_ne!(
+            double_scalar_mul(N - 1, key, 0, Point::Infinity),
+            Point::Infinity
+        );
+
+        // One flipped bit in either coordinate takes the key off the curve.
+        for index in [1, 16, 17, 32] {
+            let mut key = NTAG21X_PUBLIC_KEY;
+            key[index] ^= 0x01;
+            assert!(decode_point(&key).is_none(), "byte {}", index);
+        }
+    }
+
+    #[test]
+    fn valid_signatures_verify() {
+        for index in 0..VECTORS.len() {
+            let (uid, key, signature) = vector(index);
+            assert!(verify_signature(&key, &uid, &signature), "vector {}", index);
+        }
+    }
+
+    #[test]
+    fn tampered_signatures_fail() {
+        let (uid, key, signature) = vector(0);
+
+        let mut other_uid = uid.clone();
+        other_uid[6] ^= 0x01;
+        assert!(!verify_signature(&key, &other_uid, &signature));
+
+        let mut bad_signature = signature;
+        bad_signature[31] ^= 0x01;
+        assert!(!verify_signature(&key, &uid, &bad_signature));
+
+        let (_, other_key, _) = vector(1);
+        assert!(!verify_signature(&other_key, &uid, &signature));
+        assert!(!verify_signature(&NTAG21X_PUBLIC_KEY, &uid, &signature));
+    }
+
+    #[test]
+    fn degenerate_signatures_fail() {
+        let (uid, _, _) = vector(0);
+        assert!(!verify_signature(&NTAG21X_PUBLIC_KEY, &uid, &[0; 32]));
+        assert!(!verify_signature(&NTAG21X_PUBLIC_KEY, &uid, &[0xFF; 32]));
+    }
+
+    #[test]
+    fn report_classifies_the_tag() {
+        let tag = SimulatedTag::new(Chip::Ntag213);
+        let report = verify_originality(&tag, Chip::Ntag213).unwrap();
+        assert_eq!(report.uid, hex::decode("04112233445566").unwrap());
+        assert_eq!(report.signature, Some([0; 32]));
+        assert_eq!(report.result, Originality::Counterfeit);
+
+        let tag = SimulatedTag::new(Chip::MifareUltralight);
+        let report = verify_originality(&tag, Chip::MifareUltralight).unwrap();
+        assert_eq!(report.signature, None);
+        assert_eq!(report.result, Originality::UnknownChip);
+    }
+}
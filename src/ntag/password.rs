@@ -0,0 +1,241 @@
+use super::config::PROT;
+use super::{config_pages, read_page, transceive, write_page, FAST_READ, PWD_AUTH, READ, WRITE};
+use crate::chip::Chip;
+use crate::error::Error;
+use crate::hex;
+use crate::passthrough::{native_command, PassThrough};
+use crate::transport::{ControlCode, Transport};
+use log::{error, info};
+use pcsc::Error as PcscError;
+use std::cell::Cell;
+use std::str::FromStr;
+
+/// NTAG21x password and the PACK the tag answers a successful PWD_AUTH with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Password {
+    pub pwd: [u8; 4],
+    pub pack: [u8; 2],
+}
+
+impl FromStr for Password {
+    type Err = Error;
+
+    /// Parses `PWD` or `PWD:PACK` in hex, e.g. `11223344:AABB`. A missing
+    /// PACK defaults to `0000`, the factory value.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pwd, pack) = s.split_once(':').unwrap_or((s, "0000"));
+        let pwd: [u8; 4] = hex::decode(pwd)?
+            .try_into()
+            .map_err(|_| Error::Invalid("PWD must be 4 bytes".into()))?;
+        let pack: [u8; 2] = hex::decode(pack)?
+            .try_into()
+            .map_err(|_| Error::Invalid("PACK must be 2 bytes".into()))?;
+        Ok(Password { pwd, pack })
+    }
+}
+
+/// Which accesses AUTH0 protects, selected by the PROT bit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protection {
+    Write,
+    ReadWrite,
+}
+
+/// Sends PWD_AUTH (0x1B) and returns the PACK the tag answered with.
+pub fn pwd_auth<T: Transport + ?Sized>(tx: &T, pwd: &[u8; 4]) -> Result<[u8; 2], Error> {
+    let mut command = vec![PWD_AUTH];
+    command.extend_from_slice(pwd);
+
+    // A tag NAKs a wrong password; reader and card errors pass through.
+    let reply = transceive(tx, &command).map_err(|err| match err {
+        Error::Tag(reason) => Error::AuthFailed(reason),
+        err => err,
+    })?;
+    match reply[..] {
+        [pack0, pack1, ..] => Ok([pack0, pack1]),
+        _ => Err(Error::Tag(format!(
+            "PWD_AUTH returned no PACK: {:02X?}",
+            reply
+        ))),
+    }
+}
+
+/// Authenticates with `password` and checks the tag's PACK, which guards
+/// against a counterfeit tag that accepts any password.
+pub fn authenticate<T: Transport + ?Sized>(tx: &T, password: &Password) -> Result<(), Error> {
+    let pack = pwd_auth(tx, &password.pwd)?;
+    if pack != password.pack {
+        return Err(Error::AuthFailed(format!(
+            "PACK mismatch: expected {:02X?}, tag answered {:02X?}",
+            password.pack, pack
+        )));
+    }
+    Ok(())
+}
+
+/// Writes PWD and PACK. Neither can be read back afterwards.
+pub fn set_password<T: Transport + ?Sized>(
+    tx: &T,
+    chip: Chip,
+    password: &Password,
+) -> Result<(), Error> {
+    let pages = config_pages(chip)?;
+    write_page(tx, pages.start + 2, &password.pwd)?;
+    write_page(
+        tx,
+        pages.start + 3,
+        &[password.pack[0], password.pack[1], 0x00, 0x00],
+    )?;
+    info!("Password and PACK written.");
+    Ok(())
+}
+
+/// Sets AUTH0, the first page that needs the password. Values past the last
+/// page (0xFF) turn protection off.
+pub fn set_auth0<T: Transport + ?Sized>(tx: &T, chip: Chip, auth0: u8) -> Result<(), Error> {
+    let cfg0 = config_pages(chip)?.start;
+    let mut data = read_page(tx, cfg0)?;
+    data[3] = auth0;
+    write_page(tx, cfg0, &data)?;
+    info!("AUTH0 set to {:#04X}.", auth0);
+    Ok(())
+}
+
+/// Chooses write-only or read+write protection through the PROT bit.
+pub fn set_protection<T: Transport + ?Sized>(
+    tx: &T,
+    chip: Chip,
+    protection: Protection,
+) -> Result<(), Error> {
+    let cfg1 = config_pages(chip)?.start + 1;
+    let mut data = read_page(tx, cfg1)?;
+    match protection {
+        Protection::Write => data[0] &= !PROT,
+        Protection::ReadWrite => data[0] |= PROT,
+    }
+    write_page(tx, cfg1, &data)?;
+    info!("Protection set to {:?}.", protection);
+    Ok(())
+}
+
+/// Returns AUTH0 and the protection mode currently configured on the tag.
+pub fn read_protection<T: Transport + ?Sized>(
+    tx: &T,
+    chip: Chip,
+) -> Result<(u8, Protection), Error> {
+    let pages = config_pages(chip)?;
+    let cfg0 = read_page(tx, pages.start)?;
+    let cfg1 = read_page(tx, pages.start + 1)?;
+    let protection = if cfg1[0] & PROT != 0 {
+        Protection::ReadWrite
+    } else {
+        Protection::Write
+    };
+    Ok((cfg0[3], protection))
+}
+
+/// Password-protects every page from `auth0` on. PWD, PACK and PROT are
+/// written before AUTH0 so the tag never locks us out half-way.
+pub fn protect<T: Transport + ?Sized>(
+    tx: &T,
+    chip: Chip,
+    password: &Password,
+    auth0: u8,
+    protection: Protection,
+) -> Result<(), Error> {
+    set_password(tx, chip, password)?;
+    set_protection(tx, chip, protection)?;
+    set_auth0(tx, chip, auth0)?;
+    authenticate(tx, password)
+}
+
+/// Turns password protection off; the tag must already be authenticated.
+pub fn remove_protection<T: Transport + ?Sized>(tx: &T, chip: Chip) -> Result<(), Error> {
+    set_auth0(tx, chip, 0xFF)?;
+    set_protection(tx, chip, Protection::Write)
+}
+
+/// Transport that runs PWD_AUTH before the first read or write it carries.
+///
+/// The tag stays authenticated until it leaves the field, so one wrapper
+/// should be used per card session.
+pub struct PasswordTransport<T> {
+    inner: T,
+    password: Password,
+    authenticated: Cell<bool>,
+}
+
+impl<T: Transport> PasswordTransport<T> {
+    pub fn new(inner: T, password: Password) -> PasswordTransport<T> {
+        PasswordTransport {
+            inner,
+            password,
+            authenticated: Cell::new(false),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Transport> Transport for PasswordTransport<T> {
+    fn transmit<'buf>(
+        &self,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], PcscError> {
+        self.authenticate_before(send_buffer)?;
+        self.inner.transmit(send_buffer, receive_buffer)
+    }
+
+    fn control<'buf>(
+        &self,
+        control_code: ControlCode,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], PcscError> {
+        self.authenticate_before(send_buffer)?;
+        self.inner
+            .control(control_code, send_buffer, receive_buffer)
+    }
+
+    fn pass_through(&self) -> &dyn PassThrough {
+        self.inner.pass_through()
+    }
+}
+
+impl<T: Transport> PasswordTransport<T> {
+    /// A refused PWD_AUTH or a PACK mismatch is reported as
+    /// `CardNotAuthenticated`; a removed card or a reader failure keeps its
+    /// own kind so callers can tell it from a wrong password.
+    fn authenticate_before(&self, request: &[u8]) -> Result<(), PcscError> {
+        if !self.authenticated.get() && is_memory_access(request) {
+            if let Err(err) = authenticate(&self.inner, &self.password) {
+                error!("Automatic authentication failed: {}", err);
+                return Err(match err {
+                    Error::AuthFailed(_) => PcscError::CardNotAuthenticated,
+                    Error::Pcsc(err) => err,
+                    Error::NoReader => PcscError::ReaderUnavailable,
+                    Error::NoCard => PcscError::NoSmartcard,
+                    Error::CardRemoved => PcscError::RemovedCard,
+                    Error::SharingViolation => PcscError::SharingViolation,
+                    _ => PcscError::CommError,
+                });
+            }
+            self.authenticated.set(true);
+        }
+        Ok(())
+    }
+}
+
+/// `true` for READ/UPDATE BINARY and native READ, FAST_READ and WRITE.
+fn is_memory_access(request: &[u8]) -> bool {
+    match request {
+        [0xFF, 0xB0 | 0xD6, ..] => true,
+        _ => matches!(
+            native_command(request),
+            Some([READ | FAST_READ | WRITE, ..])
+        ),
+    }
+}
@@ -0,0 +1,301 @@
+use crate::apdu::{in_communicate_thru, is_successful_response, response_data};
+use crate::error::Error;
+use crate::transport::{ControlCode, Transport};
+use pcsc::Error as PcscError;
+use std::cell::Cell;
+
+/// SCardControl code of the CCID escape command.
+pub const IOCTL_CCID_ESCAPE: ControlCode = pcsc::ctl_code(3500);
+
+/// PN53x InCommunicateThru command and answer codes.
+const PN53X_IN_COMMUNICATE_THRU: [u8; 2] = [0xD4, 0x42];
+const PN53X_ANSWER: [u8; 2] = [0xD5, 0x43];
+
+/// PC/SC Part 3 Manage Session and Transparent Exchange data objects.
+const START_SESSION: [u8; 2] = [0x81, 0x00];
+const END_SESSION: [u8; 2] = [0x82, 0x00];
+const TRANSCEIVE: u8 = 0x95;
+const ICC_RESPONSE: u8 = 0x97;
+const GENERIC_ERROR_STATUS: u8 = 0xC0;
+
+/// A reader-specific way of sending native tag commands (GET_VERSION,
+/// PWD_AUTH, FAST_READ, ...) that have no PC/SC storage card APDU.
+pub trait PassThrough {
+    /// Short name of the mechanism, used in messages.
+    fn name(&self) -> &'static str;
+
+    /// Sends `command` to the tag and returns the tag's reply.
+    fn exchange(&self, tx: &dyn Transport, command: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+/// ACR122U and other PN532 readers: `FF 00 00 00 Lc D4 42 ...` pseudo-APDU.
+pub struct Pn532;
+
+impl PassThrough for Pn532 {
+    fn name(&self) -> &'static str {
+        "PN532 InCommunicateThru"
+    }
+
+    fn exchange(&self, tx: &dyn Transport, command: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut response_buf = [0; 300];
+        let response = tx.transmit(&in_communicate_thru(command), &mut response_buf)?;
+
+        if !is_successful_response(response) {
+            return Err(Error::status(
+                "Reader rejected pass-through command",
+                response,
+            ));
+        }
+        pn53x_reply(command, response_data(response))
+    }
+}
+
+/// PN533 readers (SCL3711 and similar): InCommunicateThru sent as a CCID
+/// escape through SCardControl.
+pub struct CcidEscape;
+
+impl PassThrough for CcidEscape {
+    fn name(&self) -> &'static str {
+        "CCID escape"
+    }
+
+    fn exchange(&self, tx: &dyn Transport, command: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut request = PN53X_IN_COMMUNICATE_THRU.to_vec();
+        request.extend_from_slice(command);
+
+        let mut response_buf = [0; 300];
+        let response = tx.control(IOCTL_CCID_ESCAPE, &request, &mut response_buf)?;
+        pn53x_reply(command, response)
+    }
+}
+
+/// PC/SC Part 3 transparent exchange (`FF C2 00 01`), opening the session
+/// with Manage Session on first use.
+#[derive(Default)]
+pub struct TransparentExchange {
+    session: Cell<bool>,
+}
+
+impl TransparentExchange {
+    pub fn new() -> TransparentExchange {
+        TransparentExchange::default()
+    }
+
+    /// Closes the transparent session, handing the RF field back to the reader.
+    pub fn end_session(&self, tx: &dyn Transport) -> Result<(), Error> {
+        if self.session.replace(false) {
+            manage_session(tx, 0x00, &END_SESSION)?;
+        }
+        Ok(())
+    }
+}
+
+impl PassThrough for TransparentExchange {
+    fn name(&self) -> &'static str {
+        "PC/SC transparent exchange"
+    }
+
+    fn exchange(&self, tx: &dyn Transport, command: &[u8]) -> Result<Vec<u8>, Error> {
+        if !self.session.get() {
+            manage_session(tx, 0x00, &START_SESSION)?;
+            self.session.set(true);
+        }
+
+        let mut object = vec![TRANSCEIVE];
+        push_ber_length(&mut object, command.len());
+        object.extend_from_slice(command);
+
+        let objects = manage_session(tx, 0x01, &object)?;
+        data_object(&objects, ICC_RESPONSE)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| {
+                Error::Reader(format!(
+                    "Reader returned no tag response to command {}",
+                    command_code(command)
+                ))
+            })
+    }
+}
+
+/// Reader families with a known pass-through mechanism.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReaderFamily {
+    Acr122,
+    Pn533,
+    Part3,
+}
+
+impl ReaderFamily {
+    /// Picks the family from the name `list_readers` reports. Readers that are
+    /// not recognised are assumed to follow PC/SC Part 3.
+    pub fn from_reader_name(name: &str) -> ReaderFamily {
+        let name = name.to_lowercase();
+        if name.contains("acr122") {
+            ReaderFamily::Acr122
+        } else if ["pn533", "pn531", "pr533", "scl3711"]
+            .iter()
+            .any(|model| name.contains(model))
+        {
+            ReaderFamily::Pn533
+        } else {
+            ReaderFamily::Part3
+        }
+    }
+
+    pub fn pass_through(self) -> Box<dyn PassThrough> {
+        match self {
+            ReaderFamily::Acr122 => Box::new(Pn532),
+            ReaderFamily::Pn533 => Box::new(CcidEscape),
+            ReaderFamily::Part3 => Box::new(TransparentExchange::new()),
+        }
+    }
+}
+
+/// Transport for a named reader, carrying the pass-through that suits it.
+pub struct ReaderTransport<T> {
+    inner: T,
+    reader: String,
+    pass_through: Box<dyn PassThrough>,
+}
+
+impl<T: Transport> ReaderTransport<T> {
+    pub fn new(inner: T, reader: &str) -> ReaderTransport<T> {
+        let pass_through = ReaderFamily::from_reader_name(reader).pass_through();
+        ReaderTransport::with_pass_through(inner, reader, pass_through)
+    }
+
+    pub fn with_pass_through(
+        inner: T,
+        reader: &str,
+        pass_through: Box<dyn PassThrough>,
+    ) -> ReaderTransport<T> {
+        ReaderTransport {
+            inner,
+            reader: reader.to_string(),
+            pass_through,
+        }
+    }
+
+    pub fn reader(&self) -> &str {
+        &self.reader
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Transport> Transport for ReaderTransport<T> {
+    fn transmit<'buf>(
+        &self,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], PcscError> {
+        self.inner.transmit(send_buffer, receive_buffer)
+    }
+
+    fn control<'buf>(
+        &self,
+        control_code: ControlCode,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], PcscError> {
+        self.inner
+            .control(control_code, send_buffer, receive_buffer)
+    }
+
+    fn pass_through(&self) -> &dyn PassThrough {
+        self.pass_through.as_ref()
+    }
+}
+
+/// Returns the native tag command wrapped in an APDU or escape payload built
+/// by one of the pass-through implementations.
+pub fn native_command(data: &[u8]) -> Option<&[u8]> {
+    match data {
+        [0xFF, 0x00, 0x00, 0x00, _, 0xD4, 0x42, command @ ..] => Some(command),
+        [0xD4, 0x42, command @ ..] => Some(command),
+        [0xFF, 0xC2, 0x00, 0x01, _, objects @ ..] => data_object(objects, TRANSCEIVE),
+        _ => None,
+    }
+}
+
+/// Sends a Manage Session (`p2` 0) or Transparent Exchange (`p2` 1) APDU and
+/// returns the response data objects once the generic status is clean. A
+/// failed status on a Transparent Exchange is the tag's doing, like a
+/// nonzero PN53x status.
+fn manage_session(tx: &dyn Transport, p2: u8, objects: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut apdu = vec![0xFF, 0xC2, 0x00, p2, objects.len() as u8];
+    apdu.extend_from_slice(objects);
+
+    let mut response_buf = [0; 300];
+    let response = tx.transmit(&apdu, &mut response_buf)?;
+    if !is_successful_response(response) {
+        return Err(Error::status(
+            "Reader rejected transparent session command",
+            response,
+        ));
+    }
+
+    let objects = response_data(response);
+    match data_object(objects, GENERIC_ERROR_STATUS) {
+        Some([_, 0x90, 0x00]) | None => Ok(objects.to_vec()),
+        Some([.., sw1, sw2]) if p2 == 0x01 => Err(Error::Tag(format!(
+            "Tag rejected transparent exchange: status {:02X} {:02X}",
+            sw1, sw2
+        ))),
+        Some(status) => Err(Error::status("Transparent session command failed", status)),
+    }
+}
+
+/// Finds the value of the first BER-TLV data object with `tag`.
+fn data_object(mut objects: &[u8], tag: u8) -> Option<&[u8]> {
+    while let [object_tag, rest @ ..] = objects {
+        let (length, rest) = match rest {
+            [0x81, length, rest @ ..] => (*length as usize, rest),
+            [0x82, high, low, rest @ ..] => (u16::from_be_bytes([*high, *low]) as usize, rest),
+            [length, rest @ ..] if *length < 0x80 => (*length as usize, rest),
+            _ => return None,
+        };
+        let value = rest.get(..length)?;
+        if *object_tag == tag {
+            return Some(value);
+        }
+        objects = &rest[length..];
+    }
+    None
+}
+
+pub(crate) fn push_ber_length(out: &mut Vec<u8>, length: usize) {
+    match length {
+        0..=0x7F => out.push(length as u8),
+        0x80..=0xFF => out.extend_from_slice(&[0x81, length as u8]),
+        _ => {
+            out.push(0x82);
+            out.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+    }
+}
+
+/// The command byte for messages, without panicking on an empty command.
+fn command_code(command: &[u8]) -> String {
+    command
+        .first()
+        .map_or("(empty)".to_string(), |code| format!("{:02X}", code))
+}
+
+/// Unpacks a PN53x InCommunicateThru answer: D5 43, status, then the tag's reply.
+fn pn53x_reply(command: &[u8], answer: &[u8]) -> Result<Vec<u8>, Error> {
+    match answer {
+        [0xD5, 0x43, 0x00, reply @ ..] => Ok(reply.to_vec()),
+        [0xD5, 0x43, status, ..] => Err(Error::Tag(format!(
+            "Tag rejected command {}: status {:02X}",
+            command_code(command),
+            status
+        ))),
+        other => Err(Error::Reader(format!(
+            "Unexpected pass-through response {:02X?}, expected {:02X?}",
+            other, PN53X_ANSWER
+        ))),
+    }
+}
@@ -0,0 +1,215 @@
+use crate::error::Error;
+use crate::transport::Transport;
+use pcsc::{Card, Context, Disposition, Protocols, Scope, ShareMode};
+use std::collections::BTreeMap;
+use std::ffi::CString;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Opens card connections for a [`SessionManager`].
+///
+/// Implemented over PC/SC by [`PcscConnector`]; tests plug in their own
+/// readers.
+pub trait Connector: Send + Sync {
+    type Card: Transport + Send;
+
+    /// Names of the attached readers, empty when there are none.
+    fn list_readers(&self) -> Result<Vec<String>, pcsc::Error>;
+
+    /// Connects to the card on `reader` and returns it with its ATR.
+    fn connect(&self, reader: &str) -> Result<(Self::Card, Vec<u8>), pcsc::Error>;
+
+    /// Picks up a connection again after the card was reset, returning the
+    /// ATR.
+    fn reconnect(&self, card: &mut Self::Card) -> Result<Vec<u8>, pcsc::Error>;
+
+    /// ATR of the card, or the error an exchange would run into:
+    /// `Error::ResetCard` or `Error::RemovedCard` for a stale connection.
+    fn status(&self, card: &Self::Card) -> Result<Vec<u8>, pcsc::Error>;
+}
+
+/// Readers attached through PC/SC, all sharing one context.
+pub struct PcscConnector {
+    ctx: Context,
+}
+
+impl PcscConnector {
+    pub fn establish() -> Result<PcscConnector, pcsc::Error> {
+        Ok(PcscConnector {
+            ctx: Context::establish(Scope::User)?,
+        })
+    }
+}
+
+impl Connector for PcscConnector {
+    type Card = Card;
+
+    fn list_readers(&self) -> Result<Vec<String>, pcsc::Error> {
+        match self.ctx.list_readers_owned() {
+            Ok(names) => Ok(names
+                .iter()
+                .map(|name| name.to_string_lossy().into_owned())
+                .collect()),
+            Err(pcsc::Error::NoReadersAvailable) => Ok(Vec::new()),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn connect(&self, reader: &str) -> Result<(Card, Vec<u8>), pcsc::Error> {
+        let name = CString::new(reader).map_err(|_| pcsc::Error::UnknownReader)?;
+        let card = self.ctx.connect(&name, ShareMode::Shared, Protocols::ANY)?;
+        let atr = self.status(&card)?;
+        Ok((card, atr))
+    }
+
+    fn reconnect(&self, card: &mut Card) -> Result<Vec<u8>, pcsc::Error> {
+        card.reconnect(ShareMode::Shared, Protocols::ANY, Disposition::LeaveCard)?;
+        self.status(card)
+    }
+
+    fn status(&self, card: &Card) -> Result<Vec<u8>, pcsc::Error> {
+        Ok(card.status2_owned()?.atr().to_vec())
+    }
+}
+
+/// Connection to the card on one reader, if there is one.
+struct Session<T> {
+    card: Option<(T, Vec<u8>)>,
+}
+
+type SharedSession<T> = Arc<Mutex<Session<T>>>;
+
+/// Shares one connector between threads working on different readers.
+///
+/// Each reader has its own lock, so operations on different readers run in
+/// parallel while operations on the same reader take turns. Connections are
+/// kept between operations and re-established when the card was reset or
+/// swapped in the meantime.
+pub struct SessionManager<C: Connector> {
+    connector: C,
+    sessions: Mutex<BTreeMap<String, SharedSession<C::Card>>>,
+}
+
+impl SessionManager<PcscConnector> {
+    /// A manager over every PC/SC reader.
+    pub fn establish() -> Result<SessionManager<PcscConnector>, pcsc::Error> {
+        let manager = SessionManager::new(PcscConnector::establish()?);
+        manager.refresh()?;
+        Ok(manager)
+    }
+}
+
+impl<C: Connector> SessionManager<C> {
+    pub fn new(connector: C) -> SessionManager<C> {
+        SessionManager {
+            connector,
+            sessions: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Lists the readers again, tracking new ones and forgetting the ones
+    /// that were unplugged. Returns the reader names.
+    pub fn refresh(&self) -> Result<Vec<String>, pcsc::Error> {
+        let names = self.connector.list_readers()?;
+        let mut sessions = self.lock_sessions();
+        sessions.retain(|name, _| names.contains(name));
+        for name in &names {
+            sessions
+                .entry(name.clone())
+                .or_insert_with(|| Arc::new(Mutex::new(Session { card: None })));
+        }
+        Ok(names)
+    }
+
+    /// Readers tracked since the last [`refresh`](SessionManager::refresh).
+    pub fn readers(&self) -> Vec<String> {
+        self.lock_sessions().keys().cloned().collect()
+    }
+
+    /// Runs `operation` with the card on `reader` and its ATR, after any
+    /// operation already running on that reader has finished.
+    ///
+    /// When the card reports `pcsc::Error::ResetCard` the connection is
+    /// picked up again and `operation` runs once more. After
+    /// [`Error::CardRemoved`] the error is returned and the next call
+    /// connects to whatever card is on the reader then.
+    pub fn with_card<R>(
+        &self,
+        reader: &str,
+        mut operation: impl FnMut(&C::Card, &[u8]) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        let session = match self.session(reader) {
+            Some(session) => session,
+            None => {
+                self.refresh()?;
+                self.session(reader).ok_or(pcsc::Error::UnknownReader)?
+            }
+        };
+        let mut session = session.lock().unwrap_or_else(|poisoned| {
+            // An operation panicked halfway; start over with a new connection.
+            session.clear_poison();
+            let mut session = poisoned.into_inner();
+            session.card = None;
+            session
+        });
+
+        let mut retried = false;
+        loop {
+            let (card, atr) = self.connected(&mut session, reader)?;
+            let result = operation(card, atr);
+            match &result {
+                Err(Error::Pcsc(pcsc::Error::ResetCard)) if !retried => {
+                    retried = true;
+                    let (card, atr) = session.card.as_mut().ok_or(pcsc::Error::ResetCard)?;
+                    *atr = self.connector.reconnect(card)?;
+                }
+                Err(Error::CardRemoved | Error::Pcsc(pcsc::Error::ResetCard)) => {
+                    session.card = None;
+                    return result;
+                }
+                _ => return result,
+            }
+        }
+    }
+
+    /// Drops the connection to the card on `reader`, if any, once the
+    /// operation running on it has finished.
+    pub fn disconnect(&self, reader: &str) {
+        if let Some(session) = self.session(reader) {
+            if let Ok(mut session) = session.lock() {
+                session.card = None;
+            }
+        }
+    }
+
+    fn session(&self, reader: &str) -> Option<SharedSession<C::Card>> {
+        self.lock_sessions().get(reader).cloned()
+    }
+
+    fn lock_sessions(&self) -> MutexGuard<'_, BTreeMap<String, SharedSession<C::Card>>> {
+        // The map is only ever changed in one step, so a panic elsewhere
+        // cannot leave it half updated.
+        self.sessions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The session's connection, checked and if need be re-established.
+    fn connected<'a>(
+        &self,
+        session: &'a mut Session<C::Card>,
+        reader: &str,
+    ) -> Result<(&'a C::Card, &'a [u8]), pcsc::Error> {
+        if let Some((card, atr)) = &mut session.card {
+            match self.connector.status(card) {
+                Ok(current) => *atr = current,
+                Err(pcsc::Error::ResetCard) => *atr = self.connector.reconnect(card)?,
+                Err(_) => session.card = None,
+            }
+        }
+        if session.card.is_none() {
+            session.card = Some(self.connector.connect(reader)?);
+        }
+        let (card, atr) = session.card.as_ref().ok_or(pcsc::Error::NoSmartcard)?;
+        Ok((card, atr))
+    }
+}
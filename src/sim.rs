@@ -0,0 +1,381 @@
+use crate::chip::Chip;
+use crate::hex;
+use crate::ntag::{FAST_READ, GET_VERSION, PWD_AUTH, READ, READ_CNT, READ_SIG, WRITE};
+use crate::passthrough::{native_command, push_ber_length, IOCTL_CCID_ESCAPE};
+use crate::transport::{ControlCode, Transport};
+use pcsc::Error;
+use std::cell::{Cell, RefCell};
+
+/// PC/SC Part 3 ATR of a contactless storage card; bytes 13-14 hold the card name.
+const ATR_TEMPLATE: [u8; 20] = [
+    0x3B, 0x8F, 0x80, 0x01, 0x80, 0x4F, 0x0C, 0xA0, 0x00, 0x00, 0x03, 0x06, 0x03, 0x00, 0x03, 0x00,
+    0x00, 0x00, 0x00, 0x68,
+];
+
+/// An in-memory Type 2 tag behind an ACR122U-style reader.
+///
+/// Answers READ/UPDATE BINARY, GET UID and, when `pass_through` is enabled,
+/// native READ, FAST_READ, WRITE, GET_VERSION, READ_CNT, READ_SIG and
+/// PWD_AUTH wrapped in any of the [`PassThrough`](crate::passthrough::PassThrough)
+/// mechanisms. NTAG password protection (AUTH0, PROT), the NFC counter and
+/// UID/counter mirroring are emulated.
+/// Every APDU is counted so tests can check how many round trips an
+/// operation costs.
+pub struct SimulatedTag {
+    pub chip: Chip,
+    pub pass_through: bool,
+    /// Originality signature returned by READ_SIG; all zeros by default.
+    pub signature: [u8; 32],
+    memory: RefCell<Vec<u8>>,
+    authenticated: Cell<bool>,
+    /// NFC counter and whether it was already bumped in this field session.
+    counter: Cell<u32>,
+    counted: Cell<bool>,
+    transmits: Cell<usize>,
+}
+
+impl SimulatedTag {
+    /// A freshly formatted tag: UID, empty NDEF message and factory config.
+    pub fn new(chip: Chip) -> SimulatedTag {
+        let mut memory = vec![0; chip.memory_size()];
+        let uid = [0x04, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+        memory[..3].copy_from_slice(&uid[..3]);
+        memory[3] = 0x88 ^ uid[0] ^ uid[1] ^ uid[2];
+        memory[4..8].copy_from_slice(&uid[3..]);
+        memory[8] = uid[3] ^ uid[4] ^ uid[5] ^ uid[6];
+        memory[9] = 0x48;
+
+        let data_size = match chip {
+            Chip::MifareUltralight => 0x06,
+            Chip::Ntag215 => 0x3E,
+            Chip::Ntag216 => 0x6D,
+            _ => 0x12,
+        };
+        memory[12..16].copy_from_slice(&[0xE1, 0x10, data_size, 0x00]);
+        memory[16..19].copy_from_slice(&[0x03, 0x00, 0xFE]);
+
+        if let (true, Some(config)) = (chip.is_ntag(), chip.config_pages()) {
+            let cfg0 = config.start * 4;
+            memory[cfg0..cfg0 + 8].copy_from_slice(&[0x04, 0x00, 0x00, 0xFF, 0x00, 0x05, 0, 0]);
+            memory[cfg0 + 8..cfg0 + 12].copy_from_slice(&[0xFF; 4]);
+        }
+        SimulatedTag::with_memory(chip, memory)
+    }
+
+    /// A tag holding `memory`, which must be exactly the chip's memory size.
+    pub fn with_memory(chip: Chip, memory: Vec<u8>) -> SimulatedTag {
+        assert_eq!(memory.len(), chip.memory_size(), "memory size of {}", chip);
+        SimulatedTag {
+            chip,
+            pass_through: true,
+            signature: [0; 32],
+            memory: RefCell::new(memory),
+            authenticated: Cell::new(false),
+            counter: Cell::new(0),
+            counted: Cell::new(false),
+            transmits: Cell::new(0),
+        }
+    }
+
+    /// ATR the reader would report for this tag.
+    pub fn atr(&self) -> Vec<u8> {
+        let mut atr = ATR_TEMPLATE.to_vec();
+        if self.chip == Chip::MifareUltralightC {
+            atr[14] = 0x3A;
+        }
+        atr[19] = atr[1..19].iter().fold(0, |check, byte| check ^ byte);
+        atr
+    }
+
+    pub fn memory(&self) -> Vec<u8> {
+        self.memory.borrow().clone()
+    }
+
+    /// Number of APDUs transmitted so far.
+    pub fn transmit_count(&self) -> usize {
+        self.transmits.get()
+    }
+
+    pub fn reset_count(&self) {
+        self.transmits.set(0);
+    }
+
+    /// Simulates the tag leaving the field, which drops authentication and
+    /// lets the next read bump the NFC counter again.
+    pub fn remove(&self) {
+        self.authenticated.set(false);
+        self.counted.set(false);
+    }
+
+    pub fn counter(&self) -> u32 {
+        self.counter.get()
+    }
+
+    /// CFG0 and CFG1 of an NTAG, `None` for other chips.
+    fn config(&self) -> Option<([u8; 4], [u8; 4])> {
+        let config = self.chip.config_pages().filter(|_| self.chip.is_ntag())?;
+        let memory = self.memory.borrow();
+        let cfg0 = config.start * 4;
+        let mut pages = ([0; 4], [0; 4]);
+        pages.0.copy_from_slice(&memory[cfg0..cfg0 + 4]);
+        pages.1.copy_from_slice(&memory[cfg0 + 4..cfg0 + 8]);
+        Some(pages)
+    }
+
+    /// Bumps the NFC counter on the first read in a field session when
+    /// NFC_CNT_EN is set.
+    fn count_read(&self) {
+        let enabled = self.config().is_some_and(|(_, cfg1)| cfg1[0] & 0x10 != 0);
+        if enabled && !self.counted.replace(true) {
+            self.counter.set((self.counter.get() + 1) & 0xFF_FFFF);
+        }
+    }
+
+    /// Start address and ASCII text of the UID/counter mirror, if one is on.
+    fn mirror(&self) -> Option<(usize, Vec<u8>)> {
+        let (cfg0, _) = self.config()?;
+        let address = cfg0[2] as usize * 4 + ((cfg0[0] >> 4) & 0b11) as usize;
+        if cfg0[2] < 4 {
+            return None;
+        }
+        let memory = self.memory.borrow();
+        let mut uid = memory[..3].to_vec();
+        uid.extend_from_slice(&memory[4..8]);
+        let counter = hex::encode(&self.counter.get().to_be_bytes()[1..]);
+        let text = match cfg0[0] >> 6 {
+            0b01 => hex::encode(&uid),
+            0b10 => counter,
+            0b11 => format!("{}x{}", hex::encode(&uid), counter),
+            _ => return None,
+        };
+        Some((address, text.into_bytes()))
+    }
+
+    /// First protected page and whether reads are protected too.
+    fn protection(&self) -> (usize, bool) {
+        match self.chip.config_pages() {
+            Some(config) if self.chip.is_ntag() => {
+                let memory = self.memory.borrow();
+                let auth0 = memory[config.start * 4 + 3] as usize;
+                let prot = memory[(config.start + 1) * 4] & 0x80 != 0;
+                (auth0, prot)
+            }
+            _ => (usize::MAX, false),
+        }
+    }
+
+    fn can_read(&self, page: usize) -> bool {
+        let (auth0, prot) = self.protection();
+        page < self.chip.block_count() && (page < auth0 || !prot || self.authenticated.get())
+    }
+
+    fn can_write(&self, page: usize) -> bool {
+        let (auth0, _) = self.protection();
+        let config_locked = match (self.config(), self.chip.config_pages()) {
+            (Some((_, cfg1)), Some(config)) => {
+                cfg1[0] & 0x40 != 0 && (page == config.start || page == config.start + 1)
+            }
+            _ => false,
+        };
+        !config_locked
+            && page >= 2
+            && page < self.chip.block_count()
+            && (page < auth0 || self.authenticated.get())
+    }
+
+    /// Reads `count` pages from `page`, wrapping around like native READ does.
+    /// PWD and PACK always read back as zeros.
+    fn read_pages(&self, page: usize, count: usize) -> Option<Vec<u8>> {
+        let memory = self.memory.borrow();
+        let pages = self.chip.block_count();
+        let secret = match self.chip.config_pages() {
+            Some(config) if self.chip.is_ntag() => config.start + 2..config.end,
+            _ => 0..0,
+        };
+        let mut data = Vec::with_capacity(count * 4);
+        for page in (page..page + count).map(|page| page % pages) {
+            if !self.can_read(page) {
+                return None;
+            }
+            if secret.contains(&page) {
+                data.extend_from_slice(&[0; 4]);
+            } else {
+                data.extend_from_slice(&memory[page * 4..page * 4 + 4]);
+            }
+        }
+        drop(memory);
+
+        self.count_read();
+        if let Some((address, text)) = self.mirror() {
+            for (index, page) in (page..page + count).map(|page| page % pages).enumerate() {
+                for byte in 0..4 {
+                    let offset = (page * 4 + byte).wrapping_sub(address);
+                    if let Some(&character) = text.get(offset) {
+                        data[index * 4 + byte] = character;
+                    }
+                }
+            }
+        }
+        Some(data)
+    }
+
+    /// Writes one page, OR-ing the OTP and lock bytes like the real chip.
+    fn write_page(&self, page: usize, data: &[u8]) -> bool {
+        if data.len() != 4 || !self.can_write(page) {
+            return false;
+        }
+        let mut memory = self.memory.borrow_mut();
+        let offset = page * 4;
+        match page {
+            2 => {
+                memory[offset + 2] |= data[2];
+                memory[offset + 3] |= data[3];
+            }
+            3 => (0..4).for_each(|index| memory[offset + index] |= data[index]),
+            _ => memory[offset..offset + 4].copy_from_slice(data),
+        }
+        true
+    }
+
+    /// Answers a native command; `None` is a NAK.
+    fn native(&self, command: &[u8]) -> Option<Vec<u8>> {
+        match *command {
+            [READ, page] if (page as usize) < self.chip.block_count() => {
+                self.read_pages(page as usize, 4)
+            }
+            [FAST_READ, start, end] if start <= end && self.chip.is_ntag() => {
+                let (start, end) = (start as usize, end as usize);
+                if end >= self.chip.block_count() {
+                    return None;
+                }
+                self.read_pages(start, end - start + 1)
+            }
+            [WRITE, page, ref data @ ..] => self.write_page(page as usize, data).then(Vec::new),
+            [READ_CNT, 0x02] if self.chip.is_ntag() => {
+                Some(self.counter.get().to_le_bytes()[..3].to_vec())
+            }
+            [READ_SIG, 0x00] if self.chip.is_ntag() => Some(self.signature.to_vec()),
+            [GET_VERSION] if self.chip.is_ntag() => {
+                let size = match self.chip {
+                    Chip::Ntag213 => 0x0F,
+                    Chip::Ntag215 => 0x11,
+                    _ => 0x13,
+                };
+                Some(vec![0x00, 0x04, 0x04, 0x02, 0x01, 0x00, size, 0x03])
+            }
+            [PWD_AUTH, ref pwd @ ..] if self.chip.is_ntag() && pwd.len() == 4 => {
+                let config = self.chip.config_pages()?;
+                let memory = self.memory.borrow();
+                let stored = &memory[(config.start + 2) * 4..(config.start + 2) * 4 + 4];
+                if stored != pwd {
+                    return None;
+                }
+                self.authenticated.set(true);
+                let pack = (config.start + 3) * 4;
+                Some(memory[pack..pack + 2].to_vec())
+            }
+            _ => None,
+        }
+    }
+
+    /// PN53x InCommunicateThru answer: D5 43, status, then the tag's reply.
+    fn pn53x_answer(&self, command: &[u8]) -> Vec<u8> {
+        match self.native(command) {
+            Some(reply) => {
+                let mut answer = vec![0xD5, 0x43, 0x00];
+                answer.extend(reply);
+                answer
+            }
+            None => vec![0xD5, 0x43, 0x01],
+        }
+    }
+
+    fn respond(&self, apdu: &[u8]) -> Vec<u8> {
+        let ok = |mut data: Vec<u8>| {
+            data.extend_from_slice(&[0x90, 0x00]);
+            data
+        };
+        match apdu {
+            [0xFF, 0xCA, 0x00, 0x00, ..] => {
+                let memory = self.memory.borrow();
+                let mut uid = memory[..3].to_vec();
+                uid.extend_from_slice(&memory[4..8]);
+                ok(uid)
+            }
+            [0xFF, 0xB0, 0x00, page, len @ 1..=16] => {
+                let (page, len) = (*page as usize, *len as usize);
+                let count = len.div_ceil(4);
+                match self.read_pages(page, count) {
+                    Some(data) if page + count <= self.chip.block_count() => {
+                        ok(data[..len].to_vec())
+                    }
+                    _ => vec![0x63, 0x00],
+                }
+            }
+            [0xFF, 0xD6, 0x00, page, 0x04, data @ ..] => {
+                match self.write_page(*page as usize, data) {
+                    true => ok(Vec::new()),
+                    false => vec![0x63, 0x00],
+                }
+            }
+            [0xFF, 0x00, 0x00, 0x00, _, 0xD4, 0x42, command @ ..] if self.pass_through => {
+                ok(self.pn53x_answer(command))
+            }
+            [0xFF, 0xC2, 0x00, 0x00, _, 0x81 | 0x82, 0x00] if self.pass_through => {
+                ok(vec![0xC0, 0x03, 0x00, 0x90, 0x00])
+            }
+            [0xFF, 0xC2, 0x00, 0x01, _, ..] if self.pass_through => {
+                match native_command(apdu).and_then(|command| self.native(command)) {
+                    Some(reply) => {
+                        let mut response = vec![0xC0, 0x03, 0x00, 0x90, 0x00, 0x97];
+                        push_ber_length(&mut response, reply.len());
+                        response.extend(reply);
+                        ok(response)
+                    }
+                    // No answer from the tag: timeout in the generic error status.
+                    None => ok(vec![0xC0, 0x03, 0x01, 0x64, 0x01]),
+                }
+            }
+            _ => vec![0x6A, 0x81],
+        }
+    }
+}
+
+impl Transport for SimulatedTag {
+    fn transmit<'buf>(
+        &self,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error> {
+        self.transmits.set(self.transmits.get() + 1);
+        let response = self.respond(send_buffer);
+        if response.len() > receive_buffer.len() {
+            return Err(Error::InsufficientBuffer);
+        }
+        receive_buffer[..response.len()].copy_from_slice(&response);
+        Ok(&receive_buffer[..response.len()])
+    }
+
+    /// Answers InCommunicateThru sent as a CCID escape, as PN533 readers do.
+    fn control<'buf>(
+        &self,
+        control_code: ControlCode,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error> {
+        self.transmits.set(self.transmits.get() + 1);
+        let response = match send_buffer {
+            [0xD4, 0x42, command @ ..]
+                if self.pass_through && control_code == IOCTL_CCID_ESCAPE =>
+            {
+                self.pn53x_answer(command)
+            }
+            _ => return Err(Error::UnsupportedFeature),
+        };
+        if response.len() > receive_buffer.len() {
+            return Err(Error::InsufficientBuffer);
+        }
+        receive_buffer[..response.len()].copy_from_slice(&response);
+        Ok(&receive_buffer[..response.len()])
+    }
+}
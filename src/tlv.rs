@@ -0,0 +1,139 @@
+/// TLV tags used in the data area of NFC Forum Type 2 tags (and MIFARE Classic
+/// NDEF sectors).
+pub const NULL_TLV: u8 = 0x00;
+pub const LOCK_CONTROL_TLV: u8 = 0x01;
+pub const MEMORY_CONTROL_TLV: u8 = 0x02;
+pub const NDEF_TLV: u8 = 0x03;
+pub const PROPRIETARY_TLV: u8 = 0xFD;
+pub const TERMINATOR_TLV: u8 = 0xFE;
+
+/// One TLV block found in the data area.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tlv {
+    pub tag: u8,
+    /// Offset of the tag byte, relative to the start of the scanned data.
+    pub offset: usize,
+    /// Offset of the first value byte.
+    pub value_offset: usize,
+    /// Length of the value as declared by the TLV; may exceed the scanned data.
+    pub length: usize,
+}
+
+impl Tlv {
+    pub fn name(&self) -> &'static str {
+        match self.tag {
+            NULL_TLV => "NULL TLV",
+            LOCK_CONTROL_TLV => "Lock Control TLV",
+            MEMORY_CONTROL_TLV => "Memory Control TLV",
+            NDEF_TLV => "NDEF TLV",
+            PROPRIETARY_TLV => "Proprietary TLV",
+            TERMINATOR_TLV => "Terminator TLV",
+            _ => "Unknown TLV",
+        }
+    }
+
+    /// Offset just past the value.
+    pub fn end(&self) -> usize {
+        self.value_offset + self.length
+    }
+
+    /// The value bytes that are actually present in `data`.
+    pub fn value<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        let start = self.value_offset.min(data.len());
+        let end = self.end().min(data.len());
+        &data[start..end]
+    }
+}
+
+/// Walks the TLV blocks in `data`, stopping after a Terminator TLV or at the
+/// first TLV whose header is cut off.
+pub fn parse_tlvs(data: &[u8]) -> Vec<Tlv> {
+    let mut tlvs = Vec::new();
+    let mut offset = 0;
+
+    while offset < data.len() {
+        let tag = data[offset];
+        if tag == NULL_TLV || tag == TERMINATOR_TLV {
+            tlvs.push(Tlv {
+                tag,
+                offset,
+                value_offset: offset + 1,
+                length: 0,
+            });
+            if tag == TERMINATOR_TLV {
+                break;
+            }
+            offset += 1;
+            continue;
+        }
+
+        let (length, header_length) = match data.get(offset + 1) {
+            Some(0xFF) => match data.get(offset + 2..offset + 4) {
+                Some(bytes) => (u16::from_be_bytes([bytes[0], bytes[1]]) as usize, 4),
+                None => break,
+            },
+            Some(&length) => (length as usize, 2),
+            None => break,
+        };
+
+        let tlv = Tlv {
+            tag,
+            offset,
+            value_offset: offset + header_length,
+            length,
+        };
+        offset = tlv.end();
+        tlvs.push(tlv);
+    }
+
+    tlvs
+}
+
+/// Finds the first NDEF TLV in `data`.
+pub fn find_ndef_tlv(data: &[u8]) -> Option<Tlv> {
+    parse_tlvs(data).into_iter().find(|tlv| tlv.tag == NDEF_TLV)
+}
+
+/// Encodes `value` as a TLV with the short or long length form as needed.
+pub fn encode_tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut tlv = vec![tag];
+    if value.len() < 0xFF {
+        tlv.push(value.len() as u8);
+    } else {
+        tlv.push(0xFF);
+        tlv.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    }
+    tlv.extend_from_slice(value);
+    tlv
+}
+
+/// Position of a memory area described by a Lock Control or Memory Control TLV.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControlArea {
+    /// Byte address of the area in tag memory.
+    pub byte_address: usize,
+    /// Size of the area: bits for Lock Control TLVs, bytes for Memory Control TLVs.
+    pub size: usize,
+    /// Number of bytes locked by each lock bit (Lock Control TLVs only).
+    pub bytes_locked_per_bit: usize,
+}
+
+/// Decodes the three-byte value of a Lock Control or Memory Control TLV.
+pub fn parse_control_tlv(value: &[u8]) -> Option<ControlArea> {
+    if value.len() < 3 {
+        return None;
+    }
+    let page_address = (value[0] >> 4) as usize;
+    let byte_offset = (value[0] & 0x0F) as usize;
+    let bytes_per_page = 1 << (value[2] & 0x0F);
+    let size = if value[1] == 0 {
+        256
+    } else {
+        value[1] as usize
+    };
+    Some(ControlArea {
+        byte_address: page_address * bytes_per_page + byte_offset,
+        size,
+        bytes_locked_per_bit: 1 << (value[2] >> 4),
+    })
+}
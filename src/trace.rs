@@ -0,0 +1,246 @@
+use crate::chip::Chip;
+use crate::ntag::{PWD_AUTH, WRITE};
+use crate::passthrough::{native_command, PassThrough};
+use crate::transport::{ControlCode, Transport};
+use log::trace;
+use pcsc::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// One command sent to a reader and what came back, with secrets masked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exchange {
+    pub time: SystemTime,
+    pub reader: String,
+    /// Set for SCardControl commands, `None` for APDUs.
+    pub control_code: Option<ControlCode>,
+    /// Spaced hex, `**` for redacted bytes.
+    pub command: String,
+    /// The response in the same form as `command`, or the PC/SC error.
+    pub response: Result<String, String>,
+    pub duration: Duration,
+}
+
+impl fmt::Display for Exchange {
+    /// Two lines, e.g.
+    ///
+    /// ```text
+    /// 1760791234.125 [ACS ACR122U 00 00] > FF CA 00 00 00
+    /// 1760791234.125 [ACS ACR122U 00 00] < 04 11 22 33 44 55 66 90 00 (1.8 ms)
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let since_epoch = self.time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let prefix = format!(
+            "{}.{:03} [{}]",
+            since_epoch.as_secs(),
+            since_epoch.subsec_millis(),
+            self.reader
+        );
+        match self.control_code {
+            Some(code) => writeln!(f, "{} > control {:#X}: {}", prefix, code, self.command)?,
+            None => writeln!(f, "{} > {}", prefix, self.command)?,
+        }
+        let millis = self.duration.as_secs_f64() * 1000.0;
+        match &self.response {
+            Ok(response) => write!(f, "{} < {} ({:.1} ms)", prefix, response, millis),
+            Err(err) => write!(f, "{} < error: {} ({:.1} ms)", prefix, err, millis),
+        }
+    }
+}
+
+/// Collects the exchanges of every [`TracingTransport`] made from it, for
+/// attaching to bug reports.
+#[derive(Debug, Default)]
+pub struct Tracer {
+    exchanges: Mutex<Vec<Exchange>>,
+}
+
+impl Tracer {
+    pub fn new() -> Arc<Tracer> {
+        Arc::new(Tracer::default())
+    }
+
+    /// Wraps `inner` so its exchanges with `reader` are recorded here.
+    pub fn wrap<T: Transport>(self: &Arc<Self>, inner: T, reader: &str) -> TracingTransport<T> {
+        TracingTransport {
+            inner,
+            reader: reader.to_string(),
+            tracer: Arc::clone(self),
+        }
+    }
+
+    pub fn exchanges(&self) -> Vec<Exchange> {
+        self.lock().clone()
+    }
+
+    /// Writes every exchange so far, oldest first.
+    pub fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        for exchange in self.lock().iter() {
+            writeln!(out, "{}", exchange)?;
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_to(&mut out)?;
+        out.flush()
+    }
+
+    fn record(&self, exchange: Exchange) {
+        trace!(target: "apdu", "{}", exchange);
+        self.lock().push(exchange);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Exchange>> {
+        // A panic elsewhere cannot leave the list half-pushed.
+        self.exchanges
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A [`Transport`] that records everything sent through it in a [`Tracer`].
+///
+/// Put it directly around the card, under any pass-through or password
+/// wrapper, so the trace shows the APDUs the reader actually received.
+pub struct TracingTransport<T> {
+    inner: T,
+    reader: String,
+    tracer: Arc<Tracer>,
+}
+
+impl<T> TracingTransport<T> {
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn record(
+        &self,
+        control_code: Option<ControlCode>,
+        command: &[u8],
+        response: &Result<&[u8], Error>,
+        time: SystemTime,
+        started: Instant,
+    ) {
+        let duration = started.elapsed();
+        let response = match response {
+            Ok(response) if has_secret_response(command) => Ok(redacted_response(response)),
+            Ok(response) => Ok(spaced_hex(response)),
+            Err(err) => Err(err.to_string()),
+        };
+        self.tracer.record(Exchange {
+            time,
+            reader: self.reader.clone(),
+            control_code,
+            command: redact_command(command),
+            response,
+            duration,
+        });
+    }
+}
+
+impl<T: Transport> Transport for TracingTransport<T> {
+    fn transmit<'buf>(
+        &self,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error> {
+        let (time, started) = (SystemTime::now(), Instant::now());
+        let response = self.inner.transmit(send_buffer, receive_buffer);
+        self.record(None, send_buffer, &response, time, started);
+        response
+    }
+
+    fn control<'buf>(
+        &self,
+        control_code: ControlCode,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error> {
+        let (time, started) = (SystemTime::now(), Instant::now());
+        let response = self
+            .inner
+            .control(control_code, send_buffer, receive_buffer);
+        self.record(Some(control_code), send_buffer, &response, time, started);
+        response
+    }
+
+    fn pass_through(&self) -> &dyn PassThrough {
+        self.inner.pass_through()
+    }
+}
+
+/// `command` as spaced hex with `**` in place of the key of LOAD KEY, the
+/// password of PWD_AUTH and data written to an NTAG PWD or PACK page, both
+/// as UPDATE BINARY and as a native WRITE wrapped in a pass-through.
+///
+/// The chip is not known here, so writes to the PWD and PACK page numbers of
+/// every NTAG are masked, even on tags where those pages hold user data.
+pub fn redact_command(command: &[u8]) -> String {
+    let mut secret = vec![false; command.len()];
+    let mut mask = |range: std::ops::Range<usize>| {
+        for index in range {
+            if let Some(byte) = secret.get_mut(index) {
+                *byte = true;
+            }
+        }
+    };
+    match command {
+        [0xFF, 0x82, _, _, 0x06, ..] => mask(5..11),
+        [0xFF, 0xD6, 0x00, page, ..] if is_password_page(*page) => mask(5..command.len()),
+        _ => {}
+    }
+    if let Some(native) = native_command(command) {
+        let offset = native.as_ptr() as usize - command.as_ptr() as usize;
+        match native {
+            [PWD_AUTH, ..] => mask(offset + 1..offset + 5),
+            [WRITE, page, ..] if is_password_page(*page) => mask(offset + 2..offset + 6),
+            _ => {}
+        }
+    }
+
+    command
+        .iter()
+        .zip(secret)
+        .map(|(byte, secret)| match secret {
+            true => "**".to_string(),
+            false => format!("{:02X}", byte),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether the answer to `command` carries a PACK.
+fn has_secret_response(command: &[u8]) -> bool {
+    matches!(native_command(command), Some([PWD_AUTH, ..]))
+}
+
+/// Masks everything but the status word.
+fn redacted_response(response: &[u8]) -> String {
+    let data = response.len().saturating_sub(2);
+    let mut parts = vec!["**".to_string(); data];
+    parts.extend(response[data..].iter().map(|byte| format!("{:02X}", byte)));
+    parts.join(" ")
+}
+
+fn spaced_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether `page` is the PWD or PACK page of some NTAG.
+fn is_password_page(page: u8) -> bool {
+    Chip::ALL
+        .into_iter()
+        .filter(|chip| chip.is_ntag())
+        .filter_map(Chip::config_pages)
+        .any(|pages| (pages.start + 2..pages.end).contains(&(page as usize)))
+}
@@ -0,0 +1,120 @@
+use crate::passthrough::{PassThrough, Pn532};
+use pcsc::{Card, Error, Transaction};
+
+/// SCardControl control code, a `DWORD` in the PC/SC API.
+#[cfg(not(target_os = "macos"))]
+pub type ControlCode = std::os::raw::c_ulong;
+#[cfg(target_os = "macos")]
+pub type ControlCode = u32;
+
+/// Anything that can carry an APDU to a card and hand back its response.
+///
+/// Implemented for [`Card`] and [`Transaction`] so the library functions work
+/// the same inside or outside of a transaction.
+pub trait Transport {
+    fn transmit<'buf>(
+        &self,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error>;
+
+    /// Sends a reader control command (SCardControl). Unsupported by default.
+    fn control<'buf>(
+        &self,
+        _control_code: ControlCode,
+        _send_buffer: &[u8],
+        _receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error> {
+        Err(Error::UnsupportedFeature)
+    }
+
+    /// How native tag commands reach the tag through this reader. Defaults to
+    /// the ACR122U/PN532 InCommunicateThru pseudo-APDU.
+    fn pass_through(&self) -> &dyn PassThrough {
+        &Pn532
+    }
+}
+
+impl Transport for Card {
+    fn transmit<'buf>(
+        &self,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error> {
+        Card::transmit(self, send_buffer, receive_buffer)
+    }
+
+    fn control<'buf>(
+        &self,
+        control_code: ControlCode,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error> {
+        Card::control(self, control_code, send_buffer, receive_buffer)
+    }
+}
+
+impl Transport for Transaction<'_> {
+    fn transmit<'buf>(
+        &self,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error> {
+        Card::transmit(self, send_buffer, receive_buffer)
+    }
+
+    fn control<'buf>(
+        &self,
+        control_code: ControlCode,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error> {
+        Card::control(self, control_code, send_buffer, receive_buffer)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for &T {
+    fn transmit<'buf>(
+        &self,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error> {
+        (**self).transmit(send_buffer, receive_buffer)
+    }
+
+    fn control<'buf>(
+        &self,
+        control_code: ControlCode,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error> {
+        (**self).control(control_code, send_buffer, receive_buffer)
+    }
+
+    fn pass_through(&self) -> &dyn PassThrough {
+        (**self).pass_through()
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn transmit<'buf>(
+        &self,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error> {
+        (**self).transmit(send_buffer, receive_buffer)
+    }
+
+    fn control<'buf>(
+        &self,
+        control_code: ControlCode,
+        send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error> {
+        (**self).control(control_code, send_buffer, receive_buffer)
+    }
+
+    fn pass_through(&self) -> &dyn PassThrough {
+        (**self).pass_through()
+    }
+}
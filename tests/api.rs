@@ -0,0 +1,302 @@
+#![cfg(feature = "http-api")]
+
+use rust_nfc_card_reader::api::{ApiServer, Readers, SimulatedReaders};
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::ndef::{encode_ndef_message, NdefRecord};
+use rust_nfc_card_reader::ntag::write_ndef_message;
+use rust_nfc_card_reader::sim::SimulatedTag;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+const READER: &str = "ACS ACR122U 00 00";
+const READER_PATH: &str = "ACS%20ACR122U%2000%2000";
+
+fn start(readers: &Arc<SimulatedReaders>) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let mut server = ApiServer::new(Arc::clone(readers) as Arc<dyn Readers>);
+    server.poll_interval = Duration::from_millis(20);
+    std::thread::spawn(move || server.serve(listener).unwrap());
+    address
+}
+
+fn tag_with(records: &[NdefRecord]) -> SimulatedTag {
+    let tag = SimulatedTag::new(Chip::Ntag215);
+    write_ndef_message(&tag, tag.chip, &encode_ndef_message(records).unwrap()).unwrap();
+    tag
+}
+
+/// Sends one request and returns the status and JSON body.
+fn request(address: SocketAddr, method: &str, path: &str, body: &str) -> (u16, Value) {
+    let headers = match method {
+        "POST" => "Host: localhost\r\nContent-Type: application/json\r\n",
+        _ => "Host: localhost\r\n",
+    };
+    request_with(address, method, path, headers, body)
+}
+
+fn request_with(
+    address: SocketAddr,
+    method: &str,
+    path: &str,
+    headers: &str,
+    body: &str,
+) -> (u16, Value) {
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\n{}Content-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        headers,
+        body.len(),
+        body
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+#[test]
+fn lists_readers_and_reads_the_card() {
+    let readers = Arc::new(SimulatedReaders::new(&[READER, "Empty"]));
+    readers
+        .insert(READER, tag_with(&[NdefRecord::uri("https://example.com")]))
+        .unwrap();
+    let address = start(&readers);
+
+    let (status, list) = request(address, "GET", "/readers", "");
+    assert_eq!(status, 200);
+    assert_eq!(list[0]["name"], READER);
+    assert_eq!(list[0]["card_present"], true);
+    assert_eq!(list[1]["card_present"], false);
+    assert_eq!(list[1]["atr"], "");
+
+    let (status, card) = request(
+        address,
+        "GET",
+        &format!("/readers/{}/card", READER_PATH),
+        "",
+    );
+    assert_eq!(status, 200);
+    assert_eq!(card["uid"], "04112233445566");
+    assert_eq!(card["chip"], "ntag215");
+    assert_eq!(card["atr"], list[0]["atr"]);
+    assert_eq!(
+        card["ndef"]["records"][0]["summary"],
+        "URI https://example.com"
+    );
+}
+
+#[test]
+fn writes_ndef_from_records_or_raw_message() {
+    let readers = Arc::new(SimulatedReaders::new(&[READER]));
+    readers
+        .insert(READER, SimulatedTag::new(Chip::Ntag213))
+        .unwrap();
+    let address = start(&readers);
+    let ndef_path = format!("/readers/{}/ndef", READER_PATH);
+    let card_path = format!("/readers/{}/card", READER_PATH);
+
+    let body = json!({"records": [
+        {"uri": "https://example.com/kiosk"},
+        {"text": "Hallo", "language": "de"},
+        {"json": {"id": 7}},
+    ]});
+    let (status, written) = request(address, "POST", &ndef_path, &body.to_string());
+    assert_eq!(status, 200, "{}", written);
+    assert_eq!(written["chip"], "ntag213");
+
+    let (_, card) = request(address, "GET", &card_path, "");
+    let records = card["ndef"]["records"].as_array().unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[1]["summary"], "Text [de] Hallo");
+    assert_eq!(records[2]["summary"], "JSON {\"id\":7}");
+
+    let message = rust_nfc_card_reader::hex::encode(
+        &encode_ndef_message(&[NdefRecord::uri("tel:+123")]).unwrap(),
+    );
+    let body = json!({ "message": message });
+    assert_eq!(
+        request(address, "POST", &ndef_path, &body.to_string()).0,
+        200
+    );
+    let (_, card) = request(address, "GET", &card_path, "");
+    assert_eq!(card["ndef"]["message"], message);
+
+    // Invalid messages never reach the tag.
+    let (status, _) = request(address, "POST", &ndef_path, r#"{"message": "D1"}"#);
+    assert_eq!(status, 400);
+    let (status, _) = request(address, "POST", &ndef_path, r#"{"records": [{"beep": 1}]}"#);
+    assert_eq!(status, 400);
+    let (_, card) = request(address, "GET", &card_path, "");
+    assert_eq!(card["ndef"]["message"], message);
+}
+
+#[test]
+fn forwards_raw_apdus() {
+    let readers = Arc::new(SimulatedReaders::new(&[READER]));
+    readers
+        .insert(READER, SimulatedTag::new(Chip::Ntag213))
+        .unwrap();
+    let address = start(&readers);
+
+    let (status, response) = request(
+        address,
+        "POST",
+        &format!("/readers/{}/apdu", READER_PATH),
+        r#"{"apdu": "FFCA000000"}"#,
+    );
+    assert_eq!(status, 200);
+    assert_eq!(response["data"], "04112233445566");
+    assert_eq!(response["sw"], "9000");
+}
+
+#[test]
+fn errors_map_to_http_statuses() {
+    let readers = Arc::new(SimulatedReaders::new(&[READER]));
+    let address = start(&readers);
+
+    let (status, body) = request(address, "GET", "/readers/Nope/card", "");
+    assert_eq!(status, 404);
+    assert!(body["error"].is_string());
+    assert_eq!(
+        request(
+            address,
+            "GET",
+            &format!("/readers/{}/card", READER_PATH),
+            ""
+        )
+        .0,
+        409
+    );
+    assert_eq!(request(address, "GET", "/nothing", "").0, 404);
+    assert_eq!(request(address, "DELETE", "/readers", "").0, 405);
+    assert_eq!(
+        request(
+            address,
+            "POST",
+            &format!("/readers/{}/apdu", READER_PATH),
+            "{"
+        )
+        .0,
+        400
+    );
+}
+
+#[test]
+fn streams_insert_and_remove_events() {
+    let readers = Arc::new(SimulatedReaders::new(&[READER]));
+    let address = start(&readers);
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    write!(stream, "GET /events HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    let mut events = BufReader::new(stream);
+    let mut line = String::new();
+    events.read_line(&mut line).unwrap();
+    assert_eq!(line, "HTTP/1.1 200 OK\r\n");
+    while line != "\r\n" {
+        line.clear();
+        events.read_line(&mut line).unwrap();
+    }
+
+    let mut next_event = || {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            events.read_line(&mut line).unwrap();
+            if line == "\n" {
+                return lines;
+            }
+            lines.push(line.trim_end().to_string());
+        }
+    };
+
+    let tag = SimulatedTag::new(Chip::Ntag213);
+    let atr = rust_nfc_card_reader::hex::encode(&tag.atr());
+    readers.insert(READER, tag).unwrap();
+    let inserted = next_event();
+    assert_eq!(inserted[0], "event: inserted");
+    let data: Value = serde_json::from_str(inserted[1].strip_prefix("data: ").unwrap()).unwrap();
+    assert_eq!(data["reader"], READER);
+    assert_eq!(data["atr"], atr);
+
+    readers.remove(READER).unwrap();
+    assert_eq!(next_event()[0], "event: removed");
+}
+
+#[test]
+fn refuses_other_hosts_origins_and_form_posts() {
+    let readers = Arc::new(SimulatedReaders::new(&[READER]));
+    readers
+        .insert(READER, SimulatedTag::new(Chip::Ntag213))
+        .unwrap();
+    let address = start(&readers);
+    let apdu_path = format!("/readers/{}/apdu", READER_PATH);
+    let body = r#"{"apdu": "FFCA000000"}"#;
+    let status = |headers: &str| request_with(address, "POST", &apdu_path, headers, body).0;
+
+    let json = "Content-Type: application/json\r\n";
+    assert_eq!(status(&format!("Host: {}\r\n{}", address, json)), 200);
+    assert_eq!(
+        status(&format!("Host: localhost:{}\r\n{}", address.port(), json)),
+        200
+    );
+    // DNS rebinding: an attacker's name resolving to 127.0.0.1.
+    assert_eq!(status(&format!("Host: evil.example\r\n{}", json)), 403);
+    assert_eq!(
+        status(&format!(
+            "Host: localhost:{}\r\n{}",
+            address.port() + 1,
+            json
+        )),
+        403
+    );
+    assert_eq!(status(json), 400);
+    // A simple cross-origin POST needs no preflight.
+    assert_eq!(
+        status("Host: localhost\r\nContent-Type: text/plain\r\n"),
+        415
+    );
+    assert_eq!(status("Host: localhost\r\n"), 415);
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}",
+        apdu_path,
+        body.len(),
+        body
+    )
+    .unwrap();
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).unwrap();
+    assert_eq!(line, "HTTP/1.1 415 Unsupported Media Type\r\n");
+    assert_eq!(
+        status(&format!(
+            "Host: localhost\r\nOrigin: http://evil.example\r\n{}",
+            json
+        )),
+        403
+    );
+    let (status, _) = request_with(
+        address,
+        "GET",
+        "/readers",
+        "Host: localhost\r\nOrigin: http://evil.example\r\n",
+        "",
+    );
+    assert_eq!(status, 403);
+
+    let listener = TcpListener::bind("0.0.0.0:0").unwrap();
+    let server = ApiServer::new(readers as Arc<dyn Readers>);
+    assert!(server.serve(listener).is_err());
+}
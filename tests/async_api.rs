@@ -0,0 +1,85 @@
+#![cfg(feature = "async")]
+
+use futures_core::Stream;
+use rust_nfc_card_reader::async_api::{AsyncTransport, ReaderEvents};
+use rust_nfc_card_reader::chip::{detect_chip, Chip};
+use rust_nfc_card_reader::monitor::ReaderEvent;
+use rust_nfc_card_reader::ndef::{encode_ndef_message, NdefRecord};
+use rust_nfc_card_reader::ntag::write_ndef_message;
+use rust_nfc_card_reader::sim::SimulatedTag;
+use std::future::poll_fn;
+use std::pin::Pin;
+
+const GET_UID: [u8; 5] = [0xFF, 0xCA, 0x00, 0x00, 0x00];
+
+#[tokio::test]
+async fn transmits_on_the_worker_thread() {
+    let tag = AsyncTransport::spawn(SimulatedTag::new(Chip::Ntag215));
+    let response = tag.transmit(&GET_UID).await.unwrap();
+    assert_eq!(
+        response,
+        [0x04, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x90, 0x00]
+    );
+}
+
+#[tokio::test]
+async fn runs_library_functions_and_keeps_the_tag() {
+    let tag = AsyncTransport::spawn(SimulatedTag::new(Chip::Ntag213));
+    let chip = tag
+        .run(|tag| {
+            let chip = detect_chip(tag, &tag.atr()).map_err(|err| err.to_string())?;
+            let message = encode_ndef_message(&[NdefRecord::uri("https://example.com")]).unwrap();
+            write_ndef_message(tag, chip, &message).map_err(|err| err.to_string())?;
+            Ok::<_, String>(chip)
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(chip, Chip::Ntag213);
+
+    // The NDEF TLV starts on page 4.
+    let memory = tag.run(|tag| tag.memory()).await.unwrap();
+    assert_eq!(memory[16], 0x03);
+}
+
+#[tokio::test]
+async fn clones_share_one_tag_without_interleaving() {
+    let tag = AsyncTransport::spawn(SimulatedTag::new(Chip::Ntag215));
+    let tasks: Vec<_> = (0..8)
+        .map(|_| {
+            let tag = tag.clone();
+            tokio::spawn(async move { tag.transmit(&GET_UID).await })
+        })
+        .collect();
+    for task in tasks {
+        assert_eq!(task.await.unwrap().unwrap().len(), 9);
+    }
+    assert_eq!(tag.run(|tag| tag.transmit_count()).await.unwrap(), 8);
+}
+
+#[tokio::test]
+async fn reports_a_stopped_worker() {
+    let tag = AsyncTransport::spawn(SimulatedTag::new(Chip::Ntag215));
+    let result = tag.run(|_| -> () { panic!("worker gone") }).await;
+    assert_eq!(result, Err(pcsc::Error::ServiceStopped));
+    assert_eq!(
+        tag.transmit(&GET_UID).await,
+        Err(pcsc::Error::ServiceStopped)
+    );
+}
+
+#[tokio::test]
+async fn reader_events_are_a_stream() {
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut events = ReaderEvents::from_receiver(receiver);
+    sender
+        .send(Ok(ReaderEvent::Attached("Front".to_string())))
+        .unwrap();
+    sender.send(Err(pcsc::Error::Cancelled)).unwrap();
+    drop(sender);
+
+    let first = poll_fn(|cx| Pin::new(&mut events).poll_next(cx)).await;
+    assert_eq!(first, Some(Ok(ReaderEvent::Attached("Front".to_string()))));
+    assert_eq!(events.next().await, Some(Err(pcsc::Error::Cancelled)));
+    assert_eq!(events.next().await, None);
+}
@@ -0,0 +1,222 @@
+use rust_nfc_card_reader::batch::{parse_csv, parse_json_lines, Batch, Options, Status};
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::daemon::Tap;
+use rust_nfc_card_reader::ndef::NdefRecord;
+use rust_nfc_card_reader::ntag::{read_protection, Password, Protection};
+use rust_nfc_card_reader::sim::SimulatedTag;
+use serde_json::Value;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("nfc-batch-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// A blank tag whose UID ends in `last`.
+fn tag(chip: Chip, last: u8) -> SimulatedTag {
+    let mut memory = SimulatedTag::new(chip).memory();
+    memory[7] = last;
+    memory[8] = memory[4] ^ memory[5] ^ memory[6] ^ last;
+    SimulatedTag::with_memory(chip, memory)
+}
+
+fn uris(tag: &SimulatedTag) -> Vec<String> {
+    Tap::read(tag, &tag.atr(), "Bench").unwrap().uris()
+}
+
+fn report_lines(path: &PathBuf) -> Vec<Value> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+const JOBS: &str = "id,uri\n\
+    dev-1,https://example.com/d/1\n\
+    dev-2,https://example.com/d/2\n\
+    dev-3,https://example.com/d/3\n";
+
+#[test]
+fn parses_csv_and_json_lines() {
+    let jobs = parse_csv(
+        "ID,Text,Language,URI\r\n\
+         a,\"12345-device-id, \"\"rev 2\"\"\",de,\n\
+         ,,,https://example.com\n\
+         \n",
+    )
+    .unwrap();
+    assert_eq!(jobs.len(), 2);
+    assert_eq!(
+        jobs[0].records,
+        [NdefRecord::text("de", "12345-device-id, \"rev 2\"").unwrap()]
+    );
+    // Rows without an id are keyed by their line.
+    assert_eq!(jobs[1].id, "3");
+    assert_eq!(jobs[1].summary(), "URI https://example.com");
+
+    let jobs = parse_json_lines(
+        "{\"id\": \"x\", \"mime\": \"application/vnd.example\", \"payload\": \"42\"}\n\n\
+         {\"url\": \"https://example.com/y\"}\n",
+    )
+    .unwrap();
+    assert_eq!(
+        jobs[0].records,
+        [NdefRecord::mime("application/vnd.example", b"42".to_vec()).unwrap()]
+    );
+    assert_eq!(jobs[1].id, "3");
+
+    let errors = [
+        parse_csv("id,uri\na,https://a\na,https://b\n").unwrap_err(),
+        parse_csv("id,uri,text\na,https://a,hello\n").unwrap_err(),
+        parse_csv("id,uri\na\n").unwrap_err(),
+        parse_csv("id,uri\n\"a,https://a\n").unwrap_err(),
+        parse_json_lines("{\"id\": \"a\"}\n").unwrap_err(),
+    ];
+    let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        messages,
+        [
+            "Job id \"a\" appears more than once",
+            "Line 2: Only one of uri, text and mime may be given",
+            "Line 2: 1 fields, the header has 2",
+            "Line 2: unterminated quoted field",
+            "Line 1: No uri, text or mime payload",
+        ]
+    );
+}
+
+#[test]
+fn provisions_each_tag_once_and_reports_it() {
+    let report = temp_path("once.jsonl");
+    let mut batch = Batch::open(parse_csv(JOBS).unwrap(), &report, Options::default()).unwrap();
+
+    let first = tag(Chip::Ntag213, 0x01);
+    let entry = batch
+        .provision(&first, &first.atr(), "Bench")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (entry.id.as_str(), entry.status),
+        ("dev-1", Status::Provisioned)
+    );
+    assert_eq!(uris(&first), ["https://example.com/d/1"]);
+
+    // The same tag again is left alone and does not use up a job.
+    let entry = batch
+        .provision(&first, &first.atr(), "Bench")
+        .unwrap()
+        .unwrap();
+    assert_eq!(entry.status, Status::AlreadyProvisioned);
+    assert_eq!(entry.error.as_deref(), Some("Tag already has job dev-1"));
+    assert_eq!(batch.next_job().unwrap().id, "dev-2");
+
+    let second = tag(Chip::Ntag215, 0x02);
+    batch
+        .provision(&second, &second.atr(), "Bench")
+        .unwrap()
+        .unwrap();
+    assert_eq!(uris(&second), ["https://example.com/d/2"]);
+    assert_eq!(batch.remaining(), 1);
+
+    let lines = report_lines(&report);
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["uid"], "04112233445501");
+    assert_eq!(lines[0]["status"], "provisioned");
+    assert_eq!(lines[0]["payload"], "URI https://example.com/d/1");
+    assert_eq!(lines[1]["status"], "already_provisioned");
+    assert_eq!(lines[2]["id"], "dev-2");
+}
+
+#[test]
+fn failed_jobs_go_to_the_next_tag() {
+    let report = temp_path("failed.jsonl");
+    let jobs = parse_csv(&format!(
+        "id,uri\nlong,https://example.com/{}\n",
+        "x".repeat(60)
+    ))
+    .unwrap();
+    let mut batch = Batch::open(jobs, &report, Options::default()).unwrap();
+
+    let small = tag(Chip::MifareUltralight, 0x01);
+    let entry = batch
+        .provision(&small, &small.atr(), "Bench")
+        .unwrap()
+        .unwrap();
+    assert_eq!(entry.status, Status::Failed);
+    assert!(entry.error.unwrap().contains("does not fit"));
+
+    let large = tag(Chip::Ntag215, 0x02);
+    let entry = batch
+        .provision(&large, &large.atr(), "Bench")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (entry.id.as_str(), entry.status),
+        ("long", Status::Provisioned)
+    );
+    assert!(batch
+        .provision(&large, &large.atr(), "Bench")
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn resumes_from_the_report() {
+    let report = temp_path("resume.jsonl");
+    let first = tag(Chip::Ntag213, 0x01);
+    {
+        let mut batch = Batch::open(parse_csv(JOBS).unwrap(), &report, Options::default()).unwrap();
+        batch.provision(&first, &first.atr(), "Bench").unwrap();
+    }
+    // A run killed while writing its report leaves half a line.
+    let mut text = std::fs::read_to_string(&report).unwrap();
+    text += "{\"id\": \"dev-2\", \"ui";
+    std::fs::write(&report, text).unwrap();
+
+    let mut batch = Batch::open(parse_csv(JOBS).unwrap(), &report, Options::default()).unwrap();
+    assert_eq!(batch.remaining(), 2);
+    assert_eq!(batch.next_job().unwrap().id, "dev-2");
+    let entry = batch
+        .provision(&first, &first.atr(), "Bench")
+        .unwrap()
+        .unwrap();
+    assert_eq!(entry.status, Status::AlreadyProvisioned);
+
+    let second = tag(Chip::Ntag213, 0x02);
+    batch
+        .provision(&second, &second.atr(), "Bench")
+        .unwrap()
+        .unwrap();
+    let lines = std::fs::read_to_string(&report).unwrap();
+    let last: Value = serde_json::from_str(lines.lines().last().unwrap()).unwrap();
+    assert_eq!(
+        (&last["id"], &last["uid"]),
+        (&"dev-2".into(), &"04112233445502".into())
+    );
+}
+
+#[test]
+fn protects_and_locks_after_verifying() {
+    let report = temp_path("protect.jsonl");
+    let password: Password = "11223344:AABB".parse().unwrap();
+    let options = Options {
+        protect: Some((password, 4)),
+        lock: true,
+    };
+    let mut batch = Batch::open(parse_csv(JOBS).unwrap(), &report, options).unwrap();
+
+    let tag = tag(Chip::Ntag213, 0x01);
+    let entry = batch.provision(&tag, &tag.atr(), "Bench").unwrap().unwrap();
+    assert_eq!(entry.status, Status::Provisioned, "{:?}", entry.error);
+    assert_eq!(
+        read_protection(&tag, Chip::Ntag213).unwrap(),
+        (4, Protection::Write)
+    );
+    let memory = tag.memory();
+    // CC write access and static lock bytes.
+    assert_eq!(memory[15], 0x0F);
+    assert_eq!(memory[10..12], [0xFF, 0xFF]);
+    assert_eq!(uris(&tag), ["https://example.com/d/1"]);
+}
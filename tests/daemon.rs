@@ -0,0 +1,224 @@
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::daemon::{Action, Daemon, Rule, Tap, DEFAULT_DEBOUNCE};
+use rust_nfc_card_reader::ndef::{encode_ndef_message, NdefRecord};
+use rust_nfc_card_reader::ntag::write_ndef_message;
+use rust_nfc_card_reader::sim::SimulatedTag;
+use serde_json::json;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const UID: &str = "04112233445566";
+
+/// Reads a simulated NTAG215 holding `records`, as the daemon would.
+fn tap(records: &[NdefRecord]) -> Tap {
+    let tag = SimulatedTag::new(Chip::Ntag215);
+    write_ndef_message(&tag, tag.chip, &encode_ndef_message(records).unwrap()).unwrap();
+    Tap::read(&tag, &tag.atr(), "Reader 0").unwrap()
+}
+
+fn rule(config: serde_json::Value) -> Rule {
+    Rule::from_json(&config).unwrap()
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("nfc-daemon-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn tap_exposes_uid_uris_and_mime_types() {
+    let tap = tap(&[
+        NdefRecord::uri("https://example.com/door"),
+        NdefRecord::json(&json!({"room": 4})),
+    ]);
+
+    assert_eq!(rust_nfc_card_reader::hex::encode(&tap.uid), UID);
+    assert_eq!(tap.uris(), ["https://example.com/door"]);
+    assert_eq!(tap.mime_types(), ["application/json"]);
+    assert!(tap
+        .env("door")
+        .contains(&("NFC_URI", "https://example.com/door".to_string())));
+}
+
+#[test]
+fn rules_and_their_conditions() {
+    let tap = tap(&[NdefRecord::uri("https://example.com/door")]);
+    let actions = json!([{"log": "/dev/null"}]);
+
+    assert!(rule(json!({"name": "any", "actions": actions})).matches(&tap));
+    assert!(rule(json!({"uids": ["04:11:22:33:44:55:66"], "actions": actions})).matches(&tap));
+    assert!(!rule(json!({"uids": ["04AABBCCDDEEFF"], "actions": actions})).matches(&tap));
+    assert!(rule(json!({"uri_prefix": "https://example.com/", "actions": actions})).matches(&tap));
+    assert!(!rule(json!({"uri_prefix": "tel:", "actions": actions})).matches(&tap));
+    assert!(!rule(json!({"mime_type": "application/json", "actions": actions})).matches(&tap));
+    // Every condition that is set must hold.
+    assert!(!rule(json!({
+        "uids": [UID],
+        "uri_prefix": "tel:",
+        "actions": actions,
+    }))
+    .matches(&tap));
+}
+
+#[test]
+fn configuration_is_validated() {
+    let daemon = Daemon::from_config(
+        r#"{"debounce_ms": 500, "rules": [{"name": "door", "actions": [
+            {"command": ["true"]}, {"log": "taps.log"}, {"post": "http://127.0.0.1:8080/tap"}]}]}"#,
+    )
+    .unwrap();
+    assert_eq!(daemon.debounce, Duration::from_millis(500));
+    assert_eq!(
+        daemon.rules[0].actions,
+        [
+            Action::Command(vec!["true".to_string()]),
+            Action::Log("taps.log".to_string()),
+            Action::Post("http://127.0.0.1:8080/tap".to_string()),
+        ]
+    );
+    assert_eq!(
+        Daemon::from_config(r#"{"rules": []}"#).unwrap().debounce,
+        DEFAULT_DEBOUNCE
+    );
+
+    assert!(Daemon::from_config("{}").is_err());
+    assert!(Daemon::from_config(r#"{"rules": [{"name": "x"}]}"#).is_err());
+    assert!(Daemon::from_config(r#"{"rules": [{"actions": [{"command": []}]}]}"#).is_err());
+    assert!(Daemon::from_config(r#"{"rules": [{"actions": [{"beep": true}]}]}"#).is_err());
+    assert!(Daemon::from_config(r#"{"rules": [{"actions": [{"post": "https://x"}]}]}"#).is_err());
+    assert!(Daemon::from_config(r#"{"rules": [{"uids": ["XYZ"], "actions": []}]}"#).is_err());
+}
+
+#[test]
+fn repeated_taps_are_debounced() {
+    let tap = tap(&[NdefRecord::uri("https://example.com/door")]);
+    let log = temp_path("debounce.log");
+    let mut daemon = Daemon::new(
+        vec![rule(json!({"name": "door", "actions": [{"log": log}]}))],
+        Duration::from_secs(2),
+    );
+
+    let start = Instant::now();
+    assert_eq!(daemon.handle(&tap, start), ["door"]);
+    assert!(daemon
+        .handle(&tap, start + Duration::from_secs(1))
+        .is_empty());
+    // The ignored tap restarted the window.
+    assert!(daemon
+        .handle(&tap, start + Duration::from_millis(2500))
+        .is_empty());
+    assert_eq!(
+        daemon.handle(&tap, start + Duration::from_secs(5)),
+        ["door"]
+    );
+
+    let mut other_reader = tap.clone();
+    other_reader.reader = "Reader 1".to_string();
+    assert_eq!(
+        daemon.handle(&other_reader, start + Duration::from_secs(5)),
+        ["door"]
+    );
+
+    daemon.wait();
+    let lines = std::fs::read_to_string(&log).unwrap();
+    assert_eq!(lines.lines().count(), 3);
+    assert!(lines.lines().next().unwrap().ends_with(&format!(
+        "door \"Reader 0\" {} https://example.com/door",
+        UID
+    )));
+    std::fs::remove_file(log).unwrap();
+}
+
+#[test]
+fn command_action_gets_the_tap_in_its_environment() {
+    let tap = tap(&[NdefRecord::json(&json!({"room": 4}))]);
+    let output = temp_path("command.out");
+    let mut daemon = Daemon::new(
+        vec![rule(json!({
+            "name": "json",
+            "mime_type": "application/json",
+            "actions": [{"command": [
+                "sh", "-c", "echo \"$NFC_RULE $NFC_UID $NFC_MIME_TYPE\" > \"$1\"", "sh", output,
+            ]}],
+        }))],
+        DEFAULT_DEBOUNCE,
+    );
+
+    assert_eq!(daemon.handle(&tap, Instant::now()), ["json"]);
+    daemon.wait();
+    assert_eq!(
+        std::fs::read_to_string(&output).unwrap(),
+        format!("json {} application/json\n", UID)
+    );
+    std::fs::remove_file(output).unwrap();
+}
+
+#[test]
+fn slow_actions_do_not_hold_up_later_taps() {
+    let tap = tap(&[NdefRecord::uri("https://example.com/door")]);
+    let log = temp_path("slow.log");
+    let mut daemon = Daemon::new(
+        vec![rule(json!({
+            "name": "door",
+            "actions": [{"command": ["sleep", "1"]}, {"log": log}],
+        }))],
+        DEFAULT_DEBOUNCE,
+    );
+
+    let start = Instant::now();
+    assert_eq!(daemon.handle(&tap, start), ["door"]);
+    let mut other_reader = tap.clone();
+    other_reader.reader = "Reader 1".to_string();
+    assert_eq!(daemon.handle(&other_reader, start), ["door"]);
+    assert!(start.elapsed() < Duration::from_millis(500));
+
+    // Each tap's actions still run in order: the log waits for the command.
+    daemon.wait();
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert_eq!(std::fs::read_to_string(&log).unwrap().lines().count(), 2);
+    std::fs::remove_file(log).unwrap();
+}
+
+#[test]
+fn post_action_sends_the_tap_as_json() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/tap", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            if header == "\r\n" {
+                break;
+            }
+            if let Some(value) = header.to_ascii_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        reader
+            .get_mut()
+            .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+            .unwrap();
+        (request_line, body)
+    });
+
+    let tap = tap(&[NdefRecord::uri("https://example.com/door")]);
+    let action = Action::from_json(&json!({ "post": url })).unwrap();
+    action.run(&tap, "door").unwrap();
+
+    let (request_line, body) = server.join().unwrap();
+    assert_eq!(request_line, "POST /tap HTTP/1.1\r\n");
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["rule"], "door");
+    assert_eq!(body["uid"], UID);
+    assert_eq!(body["uris"][0], "https://example.com/door");
+}
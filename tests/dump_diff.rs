@@ -0,0 +1,211 @@
+use rust_nfc_card_reader::chip::{Chip, ULC_COUNTER_PAGE};
+use rust_nfc_card_reader::dump::{diff_dumps, from_bin, Change, Dump};
+use rust_nfc_card_reader::ndef::{encode_ndef_message, NdefRecord};
+use rust_nfc_card_reader::tlv::{encode_tlv, NDEF_TLV, TERMINATOR_TLV};
+use serde_json::Value;
+
+/// An NTAG213 dump holding `records` in an NDEF TLV at page 4.
+fn dump_with(records: &[NdefRecord]) -> Dump {
+    let chip = Chip::Ntag213;
+    let mut memory = vec![0; chip.memory_size()];
+    memory[..16].copy_from_slice(&[
+        0x04, 0x11, 0x22, 0xBF, 0x33, 0x44, 0x55, 0x66, 0x44, 0x48, 0x00, 0x00, 0xE1, 0x10, 0x12,
+        0x00,
+    ]);
+    let mut tlvs = encode_tlv(NDEF_TLV, &encode_ndef_message(records).unwrap());
+    tlvs.push(TERMINATOR_TLV);
+    memory[16..16 + tlvs.len()].copy_from_slice(&tlvs);
+    from_bin(&memory, Some(chip)).unwrap()
+}
+
+/// A readable Classic 1K dump with transport access bits.
+fn classic_dump() -> Dump {
+    let chip = Chip::MifareClassic1K;
+    let mut dump = Dump::empty(chip);
+    for block in &mut dump.blocks {
+        block.readable = true;
+        if chip.is_sector_trailer(block.index) {
+            block.data = [&[0xFF; 6][..], &[0xFF, 0x07, 0x80, 0x69], &[0xFF; 6]].concat();
+        }
+    }
+    dump
+}
+
+fn value_block(value: i32, address: u8) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&value.to_le_bytes());
+    data.extend_from_slice(&(!value).to_le_bytes());
+    data.extend_from_slice(&value.to_le_bytes());
+    data.extend_from_slice(&[address, !address, address, !address]);
+    data
+}
+
+#[test]
+fn type2_ranges_records_and_locks() {
+    let before = dump_with(&[
+        NdefRecord::uri("https://example.com/a"),
+        NdefRecord::text("en", "hi").unwrap(),
+    ]);
+    let mut after = dump_with(&[NdefRecord::uri("https://example.com/b")]);
+    // Static lock bits for pages 4 and 5.
+    after.blocks[2].data[2] |= 0x30;
+
+    let diff = diff_dumps(&before, &after).unwrap();
+    let blocks: Vec<usize> = diff.blocks.iter().map(|block| block.index).collect();
+    assert_eq!(blocks, [2, 4, 8, 9, 10, 11]);
+    assert_eq!(diff.blocks[1].ranges, vec![1..3]);
+    assert_eq!(diff.blocks[3].fields, ["NDEF TLV", "Terminator TLV"]);
+    assert_eq!(
+        diff.changes,
+        [
+            Change::NdefRecordChanged {
+                index: 0,
+                before: "URI https://example.com/a".into(),
+                after: "URI https://example.com/b".into(),
+            },
+            Change::NdefRecordRemoved {
+                index: 1,
+                record: "Text [en] hi".into(),
+            },
+            Change::PagesLocked { pages: vec![4, 5] },
+        ]
+    );
+
+    let text = diff.to_text();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "NTAG213 dumps: 6 page(s) changed");
+    assert_eq!(
+        lines[1],
+        "Page 2: 44480000 -> 44483000  (bytes 2; Static lock bytes)"
+    );
+    assert_eq!(
+        lines[2],
+        "Page 4: 031B9101 -> 0312D101  (bytes 1-2; NDEF TLV)"
+    );
+    assert_eq!(lines[7], "Decoded changes:");
+    assert_eq!(lines[10], "  Pages newly locked: [4, 5]");
+
+    // Adding a record back is reported too.
+    let diff = diff_dumps(&after, &before).unwrap();
+    assert!(diff.changes.contains(&Change::NdefRecordAdded {
+        index: 1,
+        record: "Text [en] hi".into(),
+    }));
+
+    assert!(diff_dumps(&before, &before).unwrap().is_empty());
+    assert!(diff_dumps(&before, &classic_dump()).is_err());
+}
+
+#[test]
+fn readability_changes_are_listed() {
+    let before = dump_with(&[]);
+    let mut after = before.clone();
+    after.blocks[20].readable = false;
+
+    let diff = diff_dumps(&before, &after).unwrap();
+    assert_eq!(diff.blocks.len(), 1);
+    assert!(diff.blocks[0].ranges.is_empty());
+    assert_eq!(
+        diff.to_text().lines().nth(1),
+        Some("Page 20: 00000000 -> ????????  (readability changed)")
+    );
+}
+
+#[test]
+fn ultralight_c_counter() {
+    let mut before = Dump::empty(Chip::MifareUltralightC);
+    for block in &mut before.blocks {
+        block.readable = true;
+    }
+    let mut after = before.clone();
+    after.blocks[ULC_COUNTER_PAGE].data[..2].copy_from_slice(&300u16.to_le_bytes());
+
+    let diff = diff_dumps(&before, &after).unwrap();
+    assert_eq!(
+        diff.changes,
+        [Change::CounterChanged {
+            block: ULC_COUNTER_PAGE,
+            before: 0,
+            after: 300,
+        }]
+    );
+    assert_eq!(
+        diff.changes[0].to_string(),
+        "Counter in block 41 went from 0 to 300 (+300)"
+    );
+}
+
+#[test]
+fn classic_value_blocks_and_access_bits() {
+    let mut before = classic_dump();
+    before.blocks[4].data = value_block(100, 4);
+    let mut after = before.clone();
+    after.blocks[4].data = value_block(95, 4);
+    // Sector 1 data group 0 becomes read-only: FF 07 80 -> EF 07 81.
+    after.blocks[7].data[6..9].copy_from_slice(&[0xEF, 0x07, 0x81]);
+    // Key B changes but the access bits of sector 2 do not.
+    after.blocks[11].data[10] = 0x00;
+
+    let diff = diff_dumps(&before, &after).unwrap();
+    assert_eq!(
+        diff.changes,
+        [
+            Change::CounterChanged {
+                block: 4,
+                before: 100,
+                after: 95,
+            },
+            Change::AccessBitsChanged {
+                sector: 1,
+                before: Some([0b000, 0b000, 0b000, 0b001]),
+                after: Some([0b010, 0b000, 0b000, 0b001]),
+            },
+        ]
+    );
+    assert!(diff.changes[0].to_string().ends_with("(-5)"));
+    assert!(diff.changes[1]
+        .to_string()
+        .starts_with("Sector 1 access bits changed; group 0: "));
+
+    after.blocks[7].data[6] = 0x00;
+    let diff = diff_dumps(&before, &after).unwrap();
+    assert_eq!(
+        diff.changes[1].to_string(),
+        "Sector 1 access bits changed (invalid access bits on one side)"
+    );
+}
+
+#[test]
+fn json_output_shape() {
+    let before = dump_with(&[NdefRecord::uri("https://example.com/a")]);
+    let mut after = dump_with(&[NdefRecord::uri("https://example.com/b")]);
+    after.blocks[30].readable = false;
+
+    let json: Value =
+        serde_json::from_str(&diff_dumps(&before, &after).unwrap().to_json()).unwrap();
+    assert_eq!(json["chip"], "ntag213");
+    assert_eq!(
+        json["blocks"][0],
+        serde_json::json!({
+            "index": 8,
+            "before": "6F6D2F61",
+            "after": "6F6D2F62",
+            "readable_before": true,
+            "readable_after": true,
+            "ranges": [[3, 4]],
+            "fields": ["NDEF TLV"],
+        })
+    );
+    assert_eq!(json["blocks"][1]["readable_after"], false);
+    assert_eq!(json["blocks"][1]["ranges"], serde_json::json!([]));
+    assert_eq!(
+        json["changes"],
+        serde_json::json!([{
+            "kind": "ndef_record_changed",
+            "description": "NDEF record 0 changed: URI https://example.com/a -> URI https://example.com/b",
+            "index": 0,
+            "before": "URI https://example.com/a",
+            "after": "URI https://example.com/b",
+        }])
+    );
+}
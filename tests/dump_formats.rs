@@ -0,0 +1,189 @@
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::dump::{
+    from_bin, from_eml, from_json, load_dump, save_dump, to_bin, to_eml, to_json, to_listing, Dump,
+};
+use serde_json::Value;
+
+/// An NTAG213 dump whose pages from 0x10 on could not be read, as when they
+/// are password protected.
+fn protected_dump() -> Dump {
+    let chip = Chip::Ntag213;
+    let mut memory = vec![0; chip.memory_size()];
+    memory[..16].copy_from_slice(&[
+        0x04, 0x11, 0x22, 0xBF, 0x33, 0x44, 0x55, 0x66, 0x44, 0x48, 0x00, 0x00, 0xE1, 0x10, 0x12,
+        0x00,
+    ]);
+    // An empty NDEF message, then data past the Terminator TLV.
+    memory[16..20].copy_from_slice(&[0x03, 0x00, 0xFE, 0x00]);
+    memory[20..24].copy_from_slice(b"Hi!\0");
+
+    let mut dump = Dump::empty(chip);
+    dump.uid = vec![0x04, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66];
+    for block in &mut dump.blocks {
+        block.readable = block.index < 0x10;
+        if block.readable {
+            block.data = memory[block.index * 4..block.index * 4 + 4].to_vec();
+        }
+    }
+    dump
+}
+
+fn classic_dump() -> Dump {
+    let chip = Chip::MifareClassic1K;
+    let mut dump = Dump::empty(chip);
+    for block in &mut dump.blocks {
+        block.readable = block.index < 8;
+        if block.readable {
+            block.data = vec![block.index as u8; 16];
+        }
+    }
+    dump.blocks[3].data = [&[0xA0; 6][..], &[0xFF, 0x07, 0x80, 0x69], &[0xB0; 6]].concat();
+    dump.blocks[7].data = [&[0xFF; 6][..], &[0xFF, 0x07, 0x80, 0x69], &[0xFF; 6]].concat();
+    dump.uid = vec![0, 0, 0, 0];
+    dump.sector_keys[0] = Some([0xA0; 6]);
+    dump.sector_keys[1] = Some([0xFF; 6]);
+    dump
+}
+
+#[test]
+fn json_follows_the_proxmark3_layout() {
+    let json: Value = serde_json::from_str(&to_json(&protected_dump())).unwrap();
+    assert_eq!(json["FileType"], "mfu");
+    assert_eq!(json["Card"]["UID"], "04112233445566");
+    assert_eq!(json["Card"]["Chip"], "ntag213");
+    assert_eq!(json["blocks"]["3"], "E1101200");
+    assert_eq!(json["blocks"]["16"], "00000000");
+    assert_eq!(json["blocks"].as_object().unwrap().len(), 45);
+    assert_eq!(json["Unreadable"][0], 16);
+    assert!(json.get("SectorKeys").is_none());
+
+    let json: Value = serde_json::from_str(&to_json(&classic_dump())).unwrap();
+    assert_eq!(json["FileType"], "mfcard");
+    assert_eq!(json["Card"]["Chip"], "classic-1k");
+    assert_eq!(json["SectorKeys"]["0"]["KeyA"], "A0A0A0A0A0A0");
+    assert_eq!(json["SectorKeys"]["0"]["KeyB"], "B0B0B0B0B0B0");
+    assert_eq!(json["SectorKeys"]["0"]["AccessConditions"], "FF078069");
+    assert_eq!(json["SectorKeys"]["2"]["KeyA"], "");
+    assert_eq!(json["Unreadable"][0], 8);
+}
+
+#[test]
+fn eml_and_bin_hold_every_block() {
+    let dump = protected_dump();
+    let bin = to_bin(&dump);
+    assert_eq!(bin.len(), 180);
+    assert_eq!(bin[20..24], *b"Hi!\0");
+
+    let eml = to_eml(&dump);
+    let lines: Vec<&str> = eml.lines().collect();
+    assert_eq!(lines.len(), 45);
+    assert_eq!(lines[0], "041122BF");
+    assert_eq!(lines[5], "48692100");
+    // Unreadable pages are written as zeros.
+    assert_eq!(lines[16], "00000000");
+}
+
+#[test]
+fn json_round_trips() {
+    for dump in [protected_dump(), classic_dump()] {
+        let json = to_json(&dump);
+        assert_eq!(from_json(&json, None).unwrap(), dump, "{}", json);
+    }
+}
+
+#[test]
+fn eml_and_bin_round_trip_the_memory() {
+    let dump = protected_dump();
+    let eml = from_eml(&to_eml(&dump), None).unwrap();
+    assert_eq!(eml.chip, Chip::Ntag213);
+    assert_eq!(eml.uid, dump.uid);
+    assert_eq!(eml.bytes(), dump.bytes());
+    assert_eq!(from_bin(&to_bin(&dump), None).unwrap(), eml);
+
+    // A chip given on the command line must match the image size.
+    let err = from_eml(&to_eml(&dump), Some(Chip::Ntag215)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "NTAG215 has 540 bytes of memory, the image has 180"
+    );
+    assert!(from_eml("0011\nXY\n", None).is_err());
+}
+
+#[test]
+fn json_blocks_must_be_contiguous_and_whole() {
+    let json: Value = serde_json::from_str(&to_json(&protected_dump())).unwrap();
+    let with_blocks = |edit: &dyn Fn(&mut serde_json::Map<String, Value>)| {
+        let mut json = json.clone();
+        edit(json["blocks"].as_object_mut().unwrap());
+        from_json(&json.to_string(), None).unwrap_err().to_string()
+    };
+
+    assert_eq!(
+        with_blocks(&|blocks| {
+            blocks.remove("7");
+        }),
+        "Block 7 is missing"
+    );
+    assert_eq!(
+        with_blocks(&|blocks| {
+            blocks.insert("07".into(), "00000000".into());
+        }),
+        "Block 7 appears more than once"
+    );
+    assert_eq!(
+        with_blocks(&|blocks| {
+            blocks.insert("5".into(), "000000".into());
+        }),
+        "Block 5 has 3 bytes, expected 4"
+    );
+    assert_eq!(
+        with_blocks(&|blocks| {
+            blocks.insert("45".into(), "00000000".into());
+        }),
+        "NTAG213 has 180 bytes of memory, the image has 184"
+    );
+}
+
+#[test]
+fn listing_shows_fields_and_unreadable_pages() {
+    let listing = to_listing(&protected_dump());
+    let lines: Vec<&str> = listing.lines().collect();
+    assert_eq!(lines[0], "Chip: NTAG213 (180 bytes)");
+    assert_eq!(lines[1], "UID:  04 11 22 33 44 55 66");
+    assert_eq!(lines[4], " Page  Data         ASCII  Fields");
+    assert_eq!(lines[5], "    0  04 11 22 BF  ..\".  UID, BCC");
+    assert_eq!(lines[8], "    3  E1 10 12 00  ....  CC");
+    assert_eq!(
+        lines[9],
+        "    4  03 00 FE 00  ....  NDEF TLV, Terminator TLV"
+    );
+    assert_eq!(lines[10], "    5  48 69 21 00  Hi!.");
+    assert_eq!(lines[21], "   16  ?? ?? ?? ??");
+    assert_eq!(lines[49], "   44  ?? ?? ?? ??        PACK");
+    assert_eq!(lines.len(), 50);
+
+    let listing = to_listing(&classic_dump());
+    assert!(listing.contains("-- Sector 1 --\n    4  04 04 04"));
+}
+
+#[test]
+fn save_dump_writes_every_format() {
+    let base = std::env::temp_dir().join(format!("nfc-dump-{}", std::process::id()));
+    let dump = protected_dump();
+    let files = save_dump(&dump, &base).unwrap();
+
+    let extensions: Vec<_> = files
+        .iter()
+        .map(|path| path.extension().unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(extensions, ["bin", "eml", "json", "txt"]);
+    assert_eq!(std::fs::read(&files[0]).unwrap(), to_bin(&dump));
+    assert_eq!(load_dump(&files[2], None).unwrap(), dump);
+    assert_eq!(
+        std::fs::read_to_string(&files[3]).unwrap(),
+        to_listing(&dump)
+    );
+    for file in files {
+        std::fs::remove_file(file).unwrap();
+    }
+}
@@ -0,0 +1,89 @@
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::hex;
+use rust_nfc_card_reader::ndef::parse_ndef_message;
+use rust_nfc_card_reader::ntag::{
+    authenticate, protect, read_config, read_page, write_config, write_ndef_message, write_page,
+    Password, PasswordTransport, Protection,
+};
+use rust_nfc_card_reader::sim::SimulatedTag;
+use rust_nfc_card_reader::Error;
+
+#[test]
+fn tag_failures_are_told_apart() {
+    let chip = Chip::Ntag213;
+    let tag = SimulatedTag::new(chip);
+    let password: Password = "11223344:AABB".parse().unwrap();
+    protect(&tag, chip, &password, 4, Protection::Write).unwrap();
+    tag.remove();
+
+    let err = write_page(&tag, 4, &[1, 2, 3, 4]).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            Error::Status {
+                sw: [0x63, 0x00],
+                ..
+            }
+        ),
+        "{}",
+        err
+    );
+    let wrong = "99999999:AABB".parse().unwrap();
+    let err = authenticate(&tag, &wrong).unwrap_err();
+    assert!(matches!(err, Error::AuthFailed(_)), "{}", err);
+    authenticate(&tag, &password).unwrap();
+    tag.remove();
+    let err = read_page(&PasswordTransport::new(&tag, wrong), 4).unwrap_err();
+    assert!(matches!(err, Error::AuthFailed(_)), "{}", err);
+
+    let chip = Chip::MifareUltralight;
+    let err = write_ndef_message(&SimulatedTag::new(chip), chip, &[0; 64]).unwrap_err();
+    assert!(
+        matches!(err, Error::CapacityExceeded { needed: 64, capacity, .. } if capacity == chip.ndef_capacity()),
+        "{}",
+        err
+    );
+
+    let chip = Chip::Ntag213;
+    let tag = SimulatedTag::new(chip);
+    let original = read_config(&tag, chip).unwrap();
+    let mut locked = original;
+    locked.set("cfglck", "on").unwrap();
+    write_config(&tag, chip, &original, &locked).unwrap();
+    let mut updated = locked;
+    updated.set("auth0", "0x10").unwrap();
+    let err = write_config(&tag, chip, &locked, &updated).unwrap_err();
+    assert!(matches!(err, Error::LockedPage(0x29)), "{}", err);
+}
+
+#[test]
+fn input_and_ndef_errors_keep_their_messages() {
+    let err = parse_ndef_message(&[0xD1, 0x01]).unwrap_err();
+    assert!(matches!(err, Error::Ndef(_)), "{}", err);
+
+    let err = hex::decode("ABC").unwrap_err();
+    assert!(matches!(err, Error::Invalid(_)), "{}", err);
+    assert_eq!(err.to_string(), "Odd number of hex digits in \"ABC\"");
+
+    let err = "1122".parse::<Password>().unwrap_err();
+    assert_eq!(err.to_string(), "PWD must be 4 bytes");
+}
+
+#[test]
+fn pcsc_errors_map_to_reader_and_card_kinds() {
+    let kinds = [
+        (pcsc::Error::NoReadersAvailable, "NoReader"),
+        (pcsc::Error::UnknownReader, "NoReader"),
+        (pcsc::Error::NoSmartcard, "NoCard"),
+        (pcsc::Error::RemovedCard, "CardRemoved"),
+        (pcsc::Error::SharingViolation, "SharingViolation"),
+        (pcsc::Error::ResetCard, "Pcsc(ResetCard)"),
+    ];
+    for (err, kind) in kinds {
+        assert_eq!(format!("{:?}", Error::from(err)), kind);
+    }
+    let err = Error::from(pcsc::Error::CardNotAuthenticated);
+    assert!(matches!(err, Error::AuthFailed(_)), "{}", err);
+    let err = Error::from(pcsc::Error::ResetCard);
+    assert!(std::error::Error::source(&err).is_some());
+}
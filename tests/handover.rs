@@ -0,0 +1,169 @@
+use rust_nfc_card_reader::hex;
+use rust_nfc_card_reader::ndef::{
+    encode_ndef_message, handover_select, parse_ndef_message, AlternativeCarrier, BluetoothEpOob,
+    BluetoothLeOob, CarrierPowerState, Handover, HandoverCarrier, HandoverKind, LeAddressType,
+    LeRole, NdefRecord, TNF_MIME_MEDIA, TNF_WELL_KNOWN,
+};
+
+const SPEAKER: [u8; 6] = [0x00, 0x0D, 0x18, 0x01, 0x02, 0x03];
+
+fn speaker() -> BluetoothEpOob {
+    let mut oob = BluetoothEpOob::new(SPEAKER);
+    oob.local_name = Some("Speaker".to_string());
+    oob.class_of_device = Some(0x240404);
+    oob
+}
+
+#[test]
+fn ep_oob_payload_layout() {
+    let payload = speaker().encode().unwrap();
+    assert_eq!(
+        hex::encode(&payload),
+        // Length 22, reversed address, complete local name, class of device.
+        "1600".to_string() + "030201180D00" + "0809537065616B6572" + "040D040424"
+    );
+    assert_eq!(BluetoothEpOob::decode(&payload).unwrap(), speaker());
+}
+
+#[test]
+fn static_handover_points_at_the_carrier() {
+    let records = handover_select(vec![(
+        CarrierPowerState::Active,
+        speaker().to_record().unwrap(),
+    )])
+    .unwrap();
+    let message = encode_ndef_message(&records).unwrap();
+    // Hs 1.3 with one "ac" record referencing ID "0".
+    assert!(hex::encode(&message).starts_with("91020A487313D10204616301013000"));
+
+    let records = parse_ndef_message(&message).unwrap();
+    let handover = Handover::from_record(&records[0]).unwrap();
+    assert_eq!(handover.kind, HandoverKind::Select);
+    assert_eq!(handover.carriers[0].power_state, CarrierPowerState::Active);
+    assert_eq!(records[0].summary(), "Handover Select 1.3, 1 carrier(s)");
+
+    let carrier = Handover::carrier_record(&handover.carriers[0], &records).unwrap();
+    assert_eq!(carrier.id, b"0");
+    assert_eq!(BluetoothEpOob::from_record(carrier).unwrap(), speaker());
+    assert_eq!(carrier.summary(), "Bluetooth 00:0D:18:01:02:03 Speaker");
+}
+
+#[test]
+fn le_oob_round_trips() {
+    let mut oob = BluetoothLeOob::new(SPEAKER, LeAddressType::Random, LeRole::PeripheralOnly);
+    oob.local_name = Some("Tag".to_string());
+    oob.security_manager_tk = Some([0x5A; 16]);
+
+    let payload = oob.encode().unwrap();
+    assert!(hex::encode(&payload).starts_with("081B030201180D0001021C00"));
+    assert_eq!(BluetoothLeOob::decode(&payload).unwrap(), oob);
+    assert_eq!(
+        oob.to_record().unwrap().summary(),
+        "Bluetooth LE 00:0D:18:01:02:03 Tag"
+    );
+}
+
+#[test]
+fn handover_request_carries_collision_resolution_and_carriers() {
+    let carrier = HandoverCarrier {
+        carrier_type_format: TNF_MIME_MEDIA,
+        carrier_type: b"application/vnd.bluetooth.le.oob".to_vec(),
+        carrier_data: Vec::new(),
+    };
+    let mut carrier_record = carrier.to_record().unwrap();
+    carrier_record.id = b"le".to_vec();
+    let request = Handover::request(
+        0x1234,
+        vec![AlternativeCarrier {
+            power_state: CarrierPowerState::Activating,
+            carrier_data_reference: b"le".to_vec(),
+            auxiliary_data_references: vec![b"aux".to_vec()],
+        }],
+    );
+
+    let message = encode_ndef_message(&[request.to_record().unwrap(), carrier_record]).unwrap();
+    let records = parse_ndef_message(&message).unwrap();
+    let decoded = Handover::from_record(&records[0]).unwrap();
+    assert_eq!(decoded, request);
+
+    let found = Handover::carrier_record(&decoded.carriers[0], &records).unwrap();
+    assert_eq!(HandoverCarrier::from_record(found).unwrap(), carrier);
+}
+
+#[test]
+fn malformed_handover_data_is_rejected() {
+    // Hr 1.3 without a collision resolution record.
+    let request = NdefRecord::new(TNF_WELL_KNOWN, b"Hr", vec![0x13]);
+    assert!(Handover::from_record(&request).is_err());
+    let future = NdefRecord::new(TNF_WELL_KNOWN, b"Hs", vec![0x20]);
+    assert!(Handover::from_record(&future).is_err());
+    let truncated_ac = NdefRecord::new(TNF_WELL_KNOWN, b"ac", vec![0x01, 0x05, b'0']);
+    assert!(AlternativeCarrier::from_record(&truncated_ac).is_err());
+
+    let mut payload = speaker().encode().unwrap();
+    payload[0] = 0x40;
+    assert!(BluetoothEpOob::decode(&payload).is_err());
+    assert!(BluetoothLeOob::decode(&hex::decode("021C00").unwrap()).is_err());
+    assert!(BluetoothLeOob::decode(&hex::decode("051B0102").unwrap()).is_err());
+}
+
+#[test]
+fn structures_longer_than_254_bytes_are_not_encoded() {
+    let mut oob = speaker();
+    oob.local_name = Some("n".repeat(254));
+    let payload = oob.encode().unwrap();
+    assert_eq!(BluetoothEpOob::decode(&payload).unwrap(), oob);
+
+    oob.local_name = Some("n".repeat(255));
+    let err = oob.encode().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Bluetooth data structure 0x09 is 255 bytes long, the limit is 254"
+    );
+    assert!(oob.to_record().is_err());
+
+    let mut le = BluetoothLeOob::new(SPEAKER, LeAddressType::Public, LeRole::CentralOnly);
+    le.other.push((0xFF, vec![0; 254]));
+    assert!(le.encode().is_ok());
+    le.other[0].1.push(0);
+    assert!(le.encode().is_err());
+}
+
+#[test]
+fn handover_fields_longer_than_255_bytes_are_not_encoded() {
+    let mut carrier = AlternativeCarrier {
+        power_state: CarrierPowerState::Active,
+        carrier_data_reference: vec![b'r'; 255],
+        auxiliary_data_references: vec![vec![b'a'; 255]],
+    };
+    let record = carrier.to_record().unwrap();
+    assert_eq!(AlternativeCarrier::from_record(&record).unwrap(), carrier);
+
+    carrier.carrier_data_reference.push(b'r');
+    let err = Handover::select(vec![carrier.clone()])
+        .to_record()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Carrier data reference is 256 bytes long, the limit is 255"
+    );
+    carrier.carrier_data_reference.pop();
+    carrier.auxiliary_data_references[0].push(b'a');
+    assert!(carrier.to_record().is_err());
+    carrier.auxiliary_data_references = vec![Vec::new(); 256];
+    assert!(carrier.to_record().is_err());
+
+    let mut hc = HandoverCarrier {
+        carrier_type_format: TNF_MIME_MEDIA,
+        carrier_type: vec![b't'; 255],
+        carrier_data: Vec::new(),
+    };
+    assert!(hc.to_record().is_ok());
+    hc.carrier_type.push(b't');
+    assert!(hc.to_record().is_err());
+
+    // A carrier record whose ID cannot be referenced.
+    let mut record = speaker().to_record().unwrap();
+    record.id = vec![b'i'; 256];
+    assert!(handover_select(vec![(CarrierPowerState::Active, record)]).is_err());
+}
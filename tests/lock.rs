@@ -0,0 +1,226 @@
+use pcsc::Error;
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::lock::{
+    dynamic_lock_bits, locked_pages, make_read_only, plan_read_only, ReadOnlyPlan,
+};
+use rust_nfc_card_reader::transport::Transport;
+use std::cell::RefCell;
+
+/// Memory of a blank Type 2 tag: UID, static lock bytes clear, a CC with
+/// write access and an empty NDEF message.
+fn blank(chip: Chip) -> Vec<u8> {
+    let data_size = match chip {
+        Chip::Ntag213 | Chip::MifareUltralightC => 0x12,
+        Chip::Ntag215 => 0x3E,
+        Chip::Ntag216 => 0x6D,
+        _ => 0x06,
+    };
+    let mut memory = vec![0; chip.memory_size()];
+    memory[..16].copy_from_slice(&[
+        0x04, 0x11, 0x22, 0xBF, 0x33, 0x44, 0x55, 0x66, 0x44, 0x48, 0x00, 0x00, 0xE1, 0x10,
+        data_size, 0x00,
+    ]);
+    memory[16..20].copy_from_slice(&[0x03, 0x00, 0xFE, 0x00]);
+    memory
+}
+
+/// A Type 2 tag behind the reader that answers READ and UPDATE BINARY.
+struct BenchTag(RefCell<Vec<u8>>);
+
+impl Transport for BenchTag {
+    fn transmit<'buf>(
+        &self,
+        command: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error> {
+        let mut memory = self.0.borrow_mut();
+        let reply = match command {
+            [0xFF, 0xB0, 0x00, page, _] => {
+                let start = *page as usize * 4;
+                let mut reply = memory[start..(start + 16).min(memory.len())].to_vec();
+                reply.extend_from_slice(&[0x90, 0x00]);
+                reply
+            }
+            [0xFF, 0xD6, 0x00, page, 4, data @ ..] => {
+                let start = *page as usize * 4;
+                memory[start..start + 4].copy_from_slice(data);
+                vec![0x90, 0x00]
+            }
+            _ => vec![0x63, 0x00],
+        };
+        receive_buffer[..reply.len()].copy_from_slice(&reply);
+        Ok(&receive_buffer[..reply.len()])
+    }
+}
+
+impl BenchTag {
+    fn memory(&self) -> Vec<u8> {
+        self.0.borrow().clone()
+    }
+}
+
+/// Each planned write as `(page, after)`.
+fn writes(plan: &ReadOnlyPlan) -> Vec<(usize, [u8; 4])> {
+    plan.writes
+        .iter()
+        .map(|write| (write.page, write.after))
+        .collect()
+}
+
+#[test]
+fn plans_the_cc_dynamic_and_static_bits_of_each_chip() {
+    let cases = [
+        (Chip::Ntag213, 0x12, 40, [0xFF, 0x0F, 0x00, 0x00]),
+        (Chip::Ntag215, 0x3E, 130, [0xFF, 0x00, 0x00, 0x00]),
+        (Chip::Ntag216, 0x6D, 226, [0xFF, 0x3F, 0x00, 0x00]),
+        // Bits 0 and 4 are block-lock bits on Ultralight C.
+        (Chip::MifareUltralightC, 0x12, 40, [0xEE, 0x00, 0x00, 0x00]),
+    ];
+    for (chip, data_size, dynamic_page, dynamic) in cases {
+        let plan = plan_read_only(chip, &blank(chip)).unwrap();
+        // CC first, so an interrupted run never leaves locked pages behind
+        // a CC that still claims write access.
+        assert_eq!(
+            writes(&plan),
+            [
+                (3, [0xE1, 0x10, data_size, 0x0F]),
+                (dynamic_page, dynamic),
+                (2, [0x44, 0x48, 0xFF, 0xFF]),
+            ],
+            "{}",
+            chip
+        );
+        assert_eq!(plan.writes[0].bits.len(), 4, "{}", chip);
+        assert_eq!(plan.writes[2].bits.len(), 16, "{}", chip);
+    }
+
+    let chip = Chip::MifareUltralight;
+    let plan = plan_read_only(chip, &blank(chip)).unwrap();
+    assert_eq!(
+        writes(&plan),
+        [(3, [0xE1, 0x10, 0x06, 0x0F]), (2, [0x44, 0x48, 0xFF, 0xFF])]
+    );
+    assert!(plan_read_only(Chip::MifareClassic1K, &[0; 1024]).is_err());
+}
+
+#[test]
+fn memory_must_reach_the_dynamic_lock_page() {
+    let chip = Chip::Ntag213;
+    let memory = blank(chip);
+    let user_end = chip.user_pages().end * 4;
+    assert!(plan_read_only(chip, &memory[..user_end]).is_err());
+    assert!(plan_read_only(chip, &memory[..user_end + 4]).is_ok());
+}
+
+#[test]
+fn bits_already_set_are_left_out() {
+    let chip = Chip::Ntag213;
+    let mut memory = blank(chip);
+    memory[15] = 0x0F;
+    memory[10] = 0xF0;
+    let plan = plan_read_only(chip, &memory).unwrap();
+    assert_eq!(
+        writes(&plan),
+        [
+            (40, [0xFF, 0x0F, 0x00, 0x00]),
+            (2, [0x44, 0x48, 0xFF, 0xFF])
+        ]
+    );
+    assert_eq!(plan.writes[1].bits.len(), 12);
+}
+
+#[test]
+fn lock_control_tlv_overrides_the_default_layout() {
+    let chip = Chip::Ntag213;
+    let mut memory = blank(chip);
+
+    // The TLV NTAG213 ships with matches the default layout: 12 bits at
+    // byte 160, each locking 8 bytes.
+    memory[16..24].copy_from_slice(&[0x01, 0x03, 0xA0, 0x0C, 0x34, 0x03, 0x00, 0xFE]);
+    let default = plan_read_only(chip, &blank(chip)).unwrap();
+    let plan = plan_read_only(chip, &memory).unwrap();
+    assert_eq!(plan.writes[1], default.writes[1]);
+
+    // 6 bits, each locking 16 bytes.
+    memory[19..21].copy_from_slice(&[0x06, 0x44]);
+    let plan = plan_read_only(chip, &memory).unwrap();
+    assert_eq!(plan.writes[1].page, 40);
+    assert_eq!(plan.writes[1].after, [0x3F, 0x00, 0x00, 0x00]);
+    assert_eq!(plan.writes[1].bits[0].2, "locks pages 16-19");
+    assert_eq!(plan.writes[1].bits[5].2, "locks pages 36-39");
+
+    // The TLV can also move the lock bytes within the lock page.
+    memory[18] = 0xA1;
+    let plan = plan_read_only(chip, &memory).unwrap();
+    assert_eq!(plan.writes[1].page, 40);
+    assert_eq!(plan.writes[1].after, [0x00, 0x3F, 0x00, 0x00]);
+
+    assert_eq!(dynamic_lock_bits(chip, 160).len(), 12);
+}
+
+#[test]
+fn lock_control_tlv_past_the_end_of_memory_is_ignored() {
+    let chip = Chip::Ntag213;
+    let mut memory = blank(chip);
+    // Lock bytes at page 15 with 32 KiB pages: byte 0xF000 + 47.
+    memory[16..24].copy_from_slice(&[0x01, 0x03, 0xF0, 0x08, 0x2F, 0x03, 0x00, 0xFE]);
+
+    let plan = plan_read_only(chip, &memory).unwrap();
+    let default = plan_read_only(chip, &blank(chip)).unwrap();
+    assert_eq!(writes(&plan), writes(&default));
+}
+
+#[test]
+fn lock_control_tlv_pointing_at_user_pages_is_ignored() {
+    let chip = Chip::Ntag213;
+    let mut memory = blank(chip);
+    // Lock bytes at byte 0x52, in page 20 among the user data.
+    memory[16..24].copy_from_slice(&[0x01, 0x03, 0x52, 0x08, 0x22, 0x03, 0x00, 0xFE]);
+    memory[80..84].copy_from_slice(b"data");
+
+    let plan = plan_read_only(chip, &memory).unwrap();
+    assert!(plan.writes.iter().all(|write| write.page != 20));
+    assert_eq!(plan.writes[1].page, 40);
+    assert_eq!(plan.writes[1].after, [0xFF, 0x0F, 0x00, 0x00]);
+}
+
+#[test]
+fn dry_run_and_refusal_write_nothing() {
+    let chip = Chip::Ntag215;
+    let tag = BenchTag(RefCell::new(blank(chip)));
+    let before = tag.memory();
+
+    let plan = make_read_only(&tag, chip, true, |_| panic!("not asked on a dry run")).unwrap();
+    assert_eq!(plan.writes.len(), 3);
+    assert_eq!(tag.memory(), before);
+
+    let mut asked = None;
+    let plan = make_read_only(&tag, chip, false, |plan| {
+        asked = Some(plan.clone());
+        false
+    })
+    .unwrap();
+    assert_eq!(asked, Some(plan));
+    assert_eq!(tag.memory(), before);
+}
+
+#[test]
+fn confirmed_plan_is_written() {
+    let chip = Chip::Ntag213;
+    let tag = BenchTag(RefCell::new(blank(chip)));
+    let plan = make_read_only(&tag, chip, false, |_| true).unwrap();
+
+    let memory = tag.memory();
+    for (page, after) in writes(&plan) {
+        assert_eq!(memory[page * 4..page * 4 + 4], after, "page {}", page);
+    }
+    let locked = locked_pages(chip, &memory);
+    assert!(locked[3..chip.user_pages().end]
+        .iter()
+        .all(|&locked| locked));
+    assert!(!locked[chip.config_pages().unwrap().start]);
+
+    // Nothing is left to do the second time.
+    let again = make_read_only(&tag, chip, false, |_| true).unwrap();
+    assert!(again.writes.is_empty());
+}
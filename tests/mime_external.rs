@@ -0,0 +1,69 @@
+use rust_nfc_card_reader::ndef::{encode_ndef_message, parse_ndef_message, NdefRecord};
+use serde_json::json;
+
+#[test]
+fn json_record_round_trips() {
+    let value = json!({ "id": 42, "name": "door" });
+    let message = encode_ndef_message(&[NdefRecord::json(&value)]).unwrap();
+    let records = parse_ndef_message(&message).unwrap();
+
+    assert_eq!(records[0].mime_type(), Some("application/json"));
+    assert_eq!(records[0].json_value().unwrap().unwrap(), value);
+    assert_eq!(records[0].summary(), r#"JSON {"id":42,"name":"door"}"#);
+}
+
+#[test]
+fn mime_records_are_validated() {
+    let png = NdefRecord::mime("image/png", vec![0x89, b'P', b'N', b'G']).unwrap();
+    assert_eq!(png.summary(), "image/png, 4 bytes");
+    assert!(png.json_value().is_none());
+
+    let vcard = NdefRecord::mime("text/vcard", b"BEGIN:VCARD".to_vec()).unwrap();
+    assert_eq!(vcard.summary(), "text/vcard BEGIN:VCARD");
+
+    assert!(NdefRecord::mime("text/plain;charset=utf-8", Vec::new()).is_ok());
+    for invalid in [
+        "png",
+        "image/",
+        "/png",
+        "image/p ng",
+        "text/plain; charset=utf-8",
+    ] {
+        assert!(
+            NdefRecord::mime(invalid, Vec::new()).is_err(),
+            "{}",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn android_application_record() {
+    let message = encode_ndef_message(&[
+        NdefRecord::uri("https://example.com"),
+        NdefRecord::android_app("com.example.app"),
+    ])
+    .unwrap();
+    let records = parse_ndef_message(&message).unwrap();
+
+    assert_eq!(records[1].external_type(), Some("android.com:pkg"));
+    assert_eq!(records[1].android_package(), Some("com.example.app"));
+    assert_eq!(records[1].summary(), "Android app com.example.app");
+    assert_eq!(records[0].android_package(), None);
+}
+
+#[test]
+fn external_types_are_normalized() {
+    let record = NdefRecord::external("Example.COM:Sensor", vec![1, 2, 3]).unwrap();
+    assert_eq!(record.external_type(), Some("example.com:sensor"));
+    assert_eq!(record.summary(), "External example.com:sensor, 3 bytes");
+    assert!(record.mime_type().is_none());
+
+    for invalid in ["example.com", ":type", "example.com:", "exa mple.com:type"] {
+        assert!(
+            NdefRecord::external(invalid, Vec::new()).is_err(),
+            "{}",
+            invalid
+        );
+    }
+}
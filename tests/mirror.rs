@@ -0,0 +1,64 @@
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::dump::{ndef_message, read_dump};
+use rust_nfc_card_reader::ndef::{decode_uri, encode_uri_reserving, parse_ndef_message};
+use rust_nfc_card_reader::ntag::{
+    mirrored_uri, read_counter, read_mirror, write_mirrored_uri, Mirror, MirrorMode,
+};
+use rust_nfc_card_reader::sim::SimulatedTag;
+
+const TEMPLATE: &str = "https://example.com/t?d={mirror}";
+
+fn read_uri(tag: &SimulatedTag) -> String {
+    let dump = read_dump(tag, &tag.atr()).unwrap();
+    let records = parse_ndef_message(&ndef_message(&dump).unwrap()).unwrap();
+    decode_uri(&records[0].payload).unwrap()
+}
+
+#[test]
+fn placeholder_offset_accounts_for_abbreviated_prefix() {
+    let (payload, offset) = encode_uri_reserving(TEMPLATE, "{mirror}", 6).unwrap();
+    // "https://" becomes identifier code 0x04.
+    assert_eq!(payload[0], 0x04);
+    assert_eq!(&payload[offset..offset + 6], b"000000");
+    assert_eq!(&payload[1..offset], b"example.com/t?d=");
+
+    assert!(encode_uri_reserving("https://example.com", "{mirror}", 6).is_none());
+}
+
+#[test]
+fn mirror_lands_on_the_placeholder() {
+    let (data, mirror) = mirrored_uri(Chip::Ntag213, TEMPLATE, MirrorMode::Uid).unwrap();
+    let offset = mirror.address() - 16;
+    assert_eq!(&data[offset..offset + 14], b"00000000000000");
+    assert_eq!(mirror, Mirror::at(MirrorMode::Uid, 16 + offset));
+}
+
+#[test]
+fn mirror_outside_user_memory_is_rejected() {
+    let mirror = Mirror::at(MirrorMode::Counter, 0x27 * 4 + 2);
+    assert!(mirror.validate(Chip::Ntag213).is_err());
+    assert!(Mirror::at(MirrorMode::Uid, 8)
+        .validate(Chip::Ntag213)
+        .is_err());
+}
+
+#[test]
+fn tag_mirrors_uid_and_counter_into_the_uri() {
+    let tag = SimulatedTag::new(Chip::Ntag215);
+    let mirror = write_mirrored_uri(&tag, tag.chip, TEMPLATE, MirrorMode::UidCounter).unwrap();
+    assert_eq!(read_mirror(&tag, tag.chip).unwrap(), mirror);
+
+    // Reading the configuration back already counted once.
+    let start = read_counter(&tag).unwrap();
+
+    tag.remove();
+    let uri = read_uri(&tag);
+    assert_eq!(
+        uri,
+        format!("https://example.com/t?d=04112233445566x{:06X}", start + 1)
+    );
+    assert_eq!(read_counter(&tag).unwrap(), start + 1);
+
+    tag.remove();
+    assert!(read_uri(&tag).ends_with(&format!("x{:06X}", start + 2)));
+}
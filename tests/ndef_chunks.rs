@@ -0,0 +1,112 @@
+use rust_nfc_card_reader::ndef::{
+    encode_ndef_message, parse_ndef_message, NdefRecord, FLAG_CF, FLAG_IL, FLAG_MB, FLAG_ME,
+    FLAG_SR, TNF_MIME_MEDIA, TNF_UNCHANGED, TNF_WELL_KNOWN,
+};
+
+/// A short record with the given header bits, type, ID and payload.
+fn raw_record(header: u8, record_type: &[u8], id: &[u8], payload: &[u8]) -> Vec<u8> {
+    let mut header = header | FLAG_SR;
+    if !id.is_empty() {
+        header |= FLAG_IL;
+    }
+    let mut data = vec![header, record_type.len() as u8, payload.len() as u8];
+    if !id.is_empty() {
+        data.push(id.len() as u8);
+    }
+    data.extend_from_slice(record_type);
+    data.extend_from_slice(id);
+    data.extend_from_slice(payload);
+    data
+}
+
+fn chunked_message() -> Vec<u8> {
+    let mut data = raw_record(
+        FLAG_MB | FLAG_CF | TNF_MIME_MEDIA,
+        b"text/plain",
+        b"a1",
+        b"Hel",
+    );
+    data.extend(raw_record(FLAG_CF | TNF_UNCHANGED, b"", b"", b"lo, "));
+    data.extend(raw_record(TNF_UNCHANGED, b"", b"", b"world"));
+    data.extend(raw_record(
+        FLAG_ME | TNF_WELL_KNOWN,
+        b"U",
+        b"",
+        b"\x04example.com",
+    ));
+    data
+}
+
+#[test]
+fn chunks_are_reassembled_into_one_record() {
+    let records = parse_ndef_message(&chunked_message()).unwrap();
+
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].tnf, TNF_MIME_MEDIA);
+    assert_eq!(records[0].record_type, b"text/plain");
+    assert_eq!(records[0].id, b"a1");
+    assert_eq!(records[0].payload, b"Hello, world");
+    assert_eq!(records[1], NdefRecord::uri("https://example.com"));
+}
+
+#[test]
+fn record_ids_round_trip() {
+    let mut record = NdefRecord::uri("https://example.com");
+    record.id = b"#1".to_vec();
+    let message =
+        encode_ndef_message(&[record.clone(), NdefRecord::text("en", "hi").unwrap()]).unwrap();
+
+    let records = parse_ndef_message(&message).unwrap();
+    assert_eq!(records[0], record);
+    assert_eq!(records[1], NdefRecord::text("en", "hi").unwrap());
+}
+
+#[test]
+fn malformed_chunk_sequences_are_rejected() {
+    let first = raw_record(FLAG_MB | FLAG_CF | TNF_MIME_MEDIA, b"text/plain", b"", b"a");
+
+    let cases: [(&str, Vec<u8>, &str); 5] = [
+        (
+            "unchanged outside a chunk",
+            raw_record(FLAG_MB | FLAG_ME | TNF_UNCHANGED, b"", b"", b"a"),
+            "outside a chunked record",
+        ),
+        (
+            "middle chunk with its own TNF",
+            [
+                first.clone(),
+                raw_record(FLAG_ME | TNF_MIME_MEDIA, b"", b"", b"b"),
+            ]
+            .concat(),
+            "must use TNF 0x06",
+        ),
+        (
+            "middle chunk with a type",
+            [
+                first.clone(),
+                raw_record(FLAG_ME | TNF_UNCHANGED, b"x", b"", b"b"),
+            ]
+            .concat(),
+            "only the first chunk",
+        ),
+        (
+            "message end inside a chunk",
+            [
+                first.clone(),
+                raw_record(FLAG_ME | FLAG_CF | TNF_UNCHANGED, b"", b"", b"b"),
+            ]
+            .concat(),
+            "before its record is complete",
+        ),
+        (
+            "data ends inside a chunk",
+            first.clone(),
+            "ends inside a chunked record",
+        ),
+    ];
+
+    for (name, data, message) in cases {
+        let err = parse_ndef_message(&data).unwrap_err().to_string();
+        assert!(err.contains(message), "{}: {}", name, err);
+    }
+}
@@ -0,0 +1,157 @@
+use rust_nfc_card_reader::ndef::{
+    encode_ndef_message, parse_ndef_message, parse_ndef_message_strict, validate_ndef_message,
+    NdefRecord, ViolationKind, FLAG_MB, FLAG_ME, FLAG_SR, TNF_EMPTY, TNF_MIME_MEDIA,
+};
+
+fn message() -> Vec<u8> {
+    encode_ndef_message(&[
+        NdefRecord::uri("https://example.com"),
+        NdefRecord::text("en", "hello").unwrap(),
+    ])
+    .unwrap()
+}
+
+fn kinds(data: &[u8]) -> Vec<(usize, usize, ViolationKind)> {
+    validate_ndef_message(data)
+        .into_iter()
+        .map(|violation| (violation.record, violation.offset, violation.kind))
+        .collect()
+}
+
+#[test]
+fn valid_messages_have_no_violations() {
+    assert!(validate_ndef_message(&message()).is_empty());
+    assert!(validate_ndef_message(&[]).is_empty());
+    assert_eq!(parse_ndef_message_strict(&message()).unwrap().len(), 2);
+}
+
+#[test]
+fn missing_message_begin_and_end_are_reported() {
+    let mut data = message();
+    // Header, type length, payload length, "T" and an 8 byte payload.
+    let second = data.len() - 12;
+    data[0] &= !FLAG_MB;
+    data[second] &= !FLAG_ME;
+
+    assert_eq!(
+        kinds(&data),
+        vec![
+            (0, 0, ViolationKind::MissingMessageBegin),
+            (1, second, ViolationKind::MissingMessageEnd),
+        ]
+    );
+    // The lenient parser still reads both records.
+    assert_eq!(parse_ndef_message(&data).unwrap().len(), 2);
+    assert_eq!(parse_ndef_message_strict(&data).unwrap_err().len(), 2);
+}
+
+#[test]
+fn tnf_and_type_length_must_agree() {
+    let data = [
+        // Empty record with a type and a payload.
+        FLAG_MB | FLAG_SR | TNF_EMPTY,
+        1,
+        2,
+        b'x',
+        0xAA,
+        0xBB,
+        // MIME record without a type.
+        FLAG_ME | FLAG_SR | TNF_MIME_MEDIA,
+        0,
+        0,
+    ];
+
+    assert_eq!(
+        kinds(&data),
+        vec![
+            (
+                0,
+                1,
+                ViolationKind::TypeLengthMismatch {
+                    tnf: TNF_EMPTY,
+                    type_length: 1
+                }
+            ),
+            (0, 2, ViolationKind::EmptyWithPayload { payload_length: 2 }),
+            (
+                1,
+                7,
+                ViolationKind::TypeLengthMismatch {
+                    tnf: TNF_MIME_MEDIA,
+                    type_length: 0
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn overruns_and_trailing_data_are_reported() {
+    let data = message();
+    let cut = &data[..data.len() - 3];
+    let violations = validate_ndef_message(cut);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].record, 1);
+    assert_eq!(
+        violations[0].kind,
+        ViolationKind::Overrun {
+            field: "payload",
+            needed: 8,
+            available: 5
+        }
+    );
+
+    // A long record claiming 4 GiB of payload.
+    let huge = [
+        FLAG_MB | FLAG_ME | TNF_MIME_MEDIA,
+        1,
+        0xFF,
+        0xFF,
+        0xFF,
+        0xFF,
+        b'a',
+    ];
+    assert!(matches!(
+        validate_ndef_message(&huge)[0].kind,
+        ViolationKind::Overrun {
+            field: "payload",
+            ..
+        }
+    ));
+
+    let mut trailing = message();
+    trailing.extend_from_slice(&[0, 0]);
+    assert_eq!(
+        kinds(&trailing),
+        vec![(
+            1,
+            message().len(),
+            ViolationKind::TrailingData { length: 2 }
+        )]
+    );
+}
+
+#[test]
+fn hostile_input_never_panics() {
+    let valid = message();
+    for end in 0..valid.len() {
+        let _ = parse_ndef_message_strict(&valid[..end]);
+    }
+
+    // xorshift, so the inputs are the same on every run.
+    let mut state = 0x2545F491_4F6CDD1Du64;
+    for _ in 0..2000 {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let length = (state % 48) as usize;
+        let data: Vec<u8> = (0..length)
+            .map(|index| (state >> (index % 8 * 8)) as u8 ^ index as u8)
+            .collect();
+
+        match parse_ndef_message_strict(&data) {
+            Ok(records) => assert_eq!(parse_ndef_message(&data).unwrap(), records),
+            Err(violations) => assert!(!violations.is_empty(), "{:02X?}", data),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::ntag::{read_config, write_config, MirrorMode, NtagConfig, Protection};
+use rust_nfc_card_reader::sim::SimulatedTag;
+
+#[test]
+fn decode_and_encode_keep_rfui_bits() {
+    let raw = [0xE6, 0x5A, 0x10, 0x08, 0xF5, 0x05, 0x00, 0x00];
+    let config = NtagConfig::decode(raw);
+
+    assert_eq!(config.mirror.mode, MirrorMode::UidCounter);
+    assert_eq!(config.mirror.byte, 2);
+    assert_eq!(config.mirror.page, 0x10);
+    assert!(config.strong_modulation);
+    assert_eq!(config.auth0, 0x08);
+    assert_eq!(config.protection, Protection::ReadWrite);
+    assert!(config.config_locked);
+    assert!(config.counter_enabled);
+    assert!(!config.counter_password_protected);
+    assert_eq!(config.authlim, 5);
+    assert_eq!(config.encode(), raw);
+}
+
+#[test]
+fn config_is_read_from_each_chips_address() {
+    for chip in [Chip::Ntag213, Chip::Ntag215, Chip::Ntag216] {
+        let tag = SimulatedTag::new(chip);
+        let config = read_config(&tag, chip).unwrap();
+        assert_eq!(config.auth0, 0xFF, "{}", chip);
+        assert_eq!(config.mirror.mode, MirrorMode::Off, "{}", chip);
+        assert!(config.strong_modulation, "{}", chip);
+        assert!(!config.config_locked, "{}", chip);
+    }
+    assert!(read_config(
+        &SimulatedTag::new(Chip::MifareUltralight),
+        Chip::MifareUltralight
+    )
+    .is_err());
+}
+
+#[test]
+fn only_changed_pages_are_written() {
+    let chip = Chip::Ntag215;
+    let tag = SimulatedTag::new(chip);
+    let original = read_config(&tag, chip).unwrap();
+
+    let mut updated = original;
+    updated.set("cnt", "on").unwrap();
+    updated.set("authlim", "3").unwrap();
+    tag.reset_count();
+    let changes = write_config(&tag, chip, &original, &updated).unwrap();
+
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].index, 4);
+    assert_eq!(tag.transmit_count(), 1);
+    assert_eq!(read_config(&tag, chip).unwrap(), updated);
+
+    tag.reset_count();
+    assert!(write_config(&tag, chip, &updated, &updated)
+        .unwrap()
+        .is_empty());
+    assert_eq!(tag.transmit_count(), 0);
+}
+
+#[test]
+fn invalid_edits_are_rejected() {
+    let chip = Chip::Ntag213;
+    let tag = SimulatedTag::new(chip);
+    let original = read_config(&tag, chip).unwrap();
+
+    let mut updated = original;
+    updated.set("mirror", "cnt").unwrap();
+    updated.set("mirror-page", "4").unwrap();
+    assert!(write_config(&tag, chip, &original, &updated).is_err());
+    updated.set("cnt", "on").unwrap();
+    assert!(updated.validate(chip).is_ok());
+
+    let mut updated = original;
+    updated.set("authlim", "8").unwrap();
+    assert!(updated.validate(chip).is_err());
+    assert!(updated.set("bogus", "1").is_err());
+    assert!(updated.set("prot", "x").is_err());
+}
+
+#[test]
+fn config_lock_is_final() {
+    let chip = Chip::Ntag213;
+    let tag = SimulatedTag::new(chip);
+    let original = read_config(&tag, chip).unwrap();
+
+    let mut locked = original;
+    locked.set("cfglck", "on").unwrap();
+    write_config(&tag, chip, &original, &locked).unwrap();
+
+    let current = read_config(&tag, chip).unwrap();
+    assert!(current.config_locked);
+    let mut updated = current;
+    updated.set("auth0", "0x10").unwrap();
+    assert!(write_config(&tag, chip, &current, &updated).is_err());
+}
@@ -0,0 +1,121 @@
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::ntag::{
+    pwd_auth, read_pages, transceive, Password, PasswordTransport, GET_VERSION,
+};
+use rust_nfc_card_reader::passthrough::{ReaderFamily, ReaderTransport};
+use rust_nfc_card_reader::sim::SimulatedTag;
+use rust_nfc_card_reader::transport::{ControlCode, Transport};
+use rust_nfc_card_reader::Error;
+
+const READERS: [(&str, ReaderFamily); 5] = [
+    ("ACS ACR122U PICC Interface 00 00", ReaderFamily::Acr122),
+    (
+        "SCM Micro SCL3711 reader & NFC device 00 00",
+        ReaderFamily::Pn533,
+    ),
+    ("NXP PR533 (3.70) 00 00", ReaderFamily::Pn533),
+    (
+        "HID Global OMNIKEY 5022 Smart Card Reader 00 00",
+        ReaderFamily::Part3,
+    ),
+    ("Identiv uTrust 3700 F CL Reader 00 00", ReaderFamily::Part3),
+];
+
+#[test]
+fn family_is_chosen_from_reader_name() {
+    for (name, family) in READERS {
+        assert_eq!(ReaderFamily::from_reader_name(name), family, "{}", name);
+    }
+}
+
+#[test]
+fn every_family_reaches_the_tag() {
+    for (name, _) in READERS {
+        let tag = SimulatedTag::new(Chip::Ntag215);
+        let transport = ReaderTransport::new(&tag, name);
+
+        let version = transceive(&transport, &[GET_VERSION]).unwrap();
+        assert_eq!(version[6], 0x11, "{}", name);
+
+        let pages = read_pages(&transport, tag.chip, 0..tag.chip.block_count()).unwrap();
+        assert!(pages.iter().all(Option::is_some), "{}", name);
+        assert_eq!(pages[4].unwrap()[..], tag.memory()[16..20], "{}", name);
+    }
+}
+
+#[test]
+fn tag_nak_is_reported_for_every_family() {
+    for (name, _) in READERS {
+        let tag = SimulatedTag::new(Chip::Ntag213);
+        let transport = ReaderTransport::new(&tag, name);
+        assert!(transceive(&transport, &[0x30, 0xF0]).is_err(), "{}", name);
+    }
+}
+
+/// A reader whose card has just been taken away.
+struct Removed;
+
+impl Transport for Removed {
+    fn transmit<'buf>(&self, _: &[u8], _: &'buf mut [u8]) -> Result<&'buf [u8], pcsc::Error> {
+        Err(pcsc::Error::RemovedCard)
+    }
+
+    fn control<'buf>(
+        &self,
+        _: ControlCode,
+        _: &[u8],
+        _: &'buf mut [u8],
+    ) -> Result<&'buf [u8], pcsc::Error> {
+        Err(pcsc::Error::RemovedCard)
+    }
+}
+
+/// A reader without SCardControl.
+struct NoControl;
+
+impl Transport for NoControl {
+    fn transmit<'buf>(&self, _: &[u8], _: &'buf mut [u8]) -> Result<&'buf [u8], pcsc::Error> {
+        Err(pcsc::Error::NoSmartcard)
+    }
+}
+
+#[test]
+fn reader_errors_are_not_wrong_passwords() {
+    for (name, _) in READERS {
+        let err = pwd_auth(&ReaderTransport::new(Removed, name), &[1, 2, 3, 4]).unwrap_err();
+        assert!(matches!(err, Error::CardRemoved), "{}: {}", name, err);
+
+        let tag = SimulatedTag::new(Chip::Ntag213);
+        let err = pwd_auth(&ReaderTransport::new(&tag, name), &[1, 2, 3, 4]).unwrap_err();
+        assert!(matches!(err, Error::AuthFailed(_)), "{}: {}", name, err);
+
+        // An empty command is an error, not a panic.
+        assert!(transceive(&ReaderTransport::new(&tag, name), &[]).is_err());
+    }
+
+    let escape = ReaderTransport::new(NoControl, READERS[1].0);
+    let err = pwd_auth(&escape, &[1, 2, 3, 4]).unwrap_err();
+    assert!(
+        matches!(err, Error::Pcsc(pcsc::Error::UnsupportedFeature)),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn password_transport_authenticates_over_escape() {
+    let chip = Chip::Ntag213;
+    let mut memory = SimulatedTag::new(chip).memory();
+    let cfg0 = chip.config_pages().unwrap().start * 4;
+    memory[cfg0 + 3] = 0x04;
+    memory[cfg0 + 4] |= 0x80;
+    memory[cfg0 + 8..cfg0 + 12].copy_from_slice(&[0x11, 0x22, 0x33, 0x44]);
+    let tag = SimulatedTag::with_memory(chip, memory);
+
+    let reader = ReaderTransport::new(&tag, "SCM Micro SCL3711 reader & NFC device 00 00");
+    let password: Password = "11223344".parse().unwrap();
+    let transport = PasswordTransport::new(reader, password);
+
+    let pages = read_pages(&transport, chip, 0..chip.block_count()).unwrap();
+    assert!(pages.iter().all(Option::is_some));
+}
@@ -0,0 +1,210 @@
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::ntag::{
+    authenticate, protect, read_page, read_protection, remove_protection, write_page, Password,
+    PasswordTransport, Protection,
+};
+use rust_nfc_card_reader::transport::Transport;
+use rust_nfc_card_reader::Error;
+use std::cell::{Cell, RefCell};
+
+const CHIP: Chip = Chip::Ntag215;
+
+/// Just enough of an NTAG215 behind an ACR122U to check password handling:
+/// READ/UPDATE BINARY, PWD_AUTH through InCommunicateThru, and AUTH0/PROT.
+struct BenchTag {
+    memory: RefCell<Vec<u8>>,
+    authenticated: Cell<bool>,
+    pwd_auths: Cell<usize>,
+    /// Cleared when the tag leaves the field for good.
+    present: Cell<bool>,
+}
+
+impl BenchTag {
+    fn new() -> BenchTag {
+        let mut memory = vec![0; CHIP.memory_size()];
+        memory[CHIP.config_pages().unwrap().start * 4 + 3] = 0xFF;
+        BenchTag {
+            memory: RefCell::new(memory),
+            authenticated: Cell::new(false),
+            pwd_auths: Cell::new(0),
+            present: Cell::new(true),
+        }
+    }
+
+    /// Takes the tag out of the field and puts it back.
+    fn remove(&self) {
+        self.authenticated.set(false);
+    }
+
+    fn cfg0(&self) -> usize {
+        CHIP.config_pages().unwrap().start * 4
+    }
+
+    fn allowed(&self, page: usize, write: bool) -> bool {
+        let memory = self.memory.borrow();
+        let auth0 = memory[self.cfg0() + 3] as usize;
+        let prot = memory[self.cfg0() + 4] & 0x80 != 0;
+        self.authenticated.get() || page < auth0 || (!write && !prot)
+    }
+
+    fn reply(&self, command: &[u8]) -> Vec<u8> {
+        match command {
+            [0xFF, 0xB0, 0x00, page, _] if self.allowed(*page as usize, false) => {
+                let start = *page as usize * 4;
+                let memory = self.memory.borrow();
+                let mut reply = memory[start..(start + 16).min(memory.len())].to_vec();
+                reply.extend_from_slice(&[0x90, 0x00]);
+                reply
+            }
+            [0xFF, 0xD6, 0x00, page, 4, data @ ..] if self.allowed(*page as usize, true) => {
+                let start = *page as usize * 4;
+                self.memory.borrow_mut()[start..start + 4].copy_from_slice(data);
+                vec![0x90, 0x00]
+            }
+            [0xFF, 0x00, 0x00, 0x00, _, 0xD4, 0x42, 0x1B, pwd @ ..] => {
+                self.pwd_auths.set(self.pwd_auths.get() + 1);
+                let memory = self.memory.borrow();
+                let cfg0 = self.cfg0();
+                if pwd == &memory[cfg0 + 8..cfg0 + 12] {
+                    self.authenticated.set(true);
+                    vec![
+                        0xD5,
+                        0x43,
+                        0x00,
+                        memory[cfg0 + 12],
+                        memory[cfg0 + 13],
+                        0x90,
+                        0x00,
+                    ]
+                } else {
+                    vec![0xD5, 0x43, 0x01, 0x90, 0x00]
+                }
+            }
+            _ => vec![0x63, 0x00],
+        }
+    }
+}
+
+impl Transport for &BenchTag {
+    fn transmit<'buf>(
+        &self,
+        command: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], pcsc::Error> {
+        if !self.present.get() {
+            return Err(pcsc::Error::RemovedCard);
+        }
+        let reply = self.reply(command);
+        receive_buffer[..reply.len()].copy_from_slice(&reply);
+        Ok(&receive_buffer[..reply.len()])
+    }
+}
+
+fn password() -> Password {
+    "11223344:AABB".parse().unwrap()
+}
+
+/// A tag protected from page 0x10 on, taken out of the field and put back
+/// so it is no longer authenticated.
+fn protected_tag(protection: Protection) -> BenchTag {
+    let tag = BenchTag::new();
+    protect(&&tag, CHIP, &password(), 0x10, protection).unwrap();
+    tag.remove();
+    tag
+}
+
+#[test]
+fn passwords_parse_with_an_optional_pack() {
+    assert_eq!(
+        password(),
+        Password {
+            pwd: [0x11, 0x22, 0x33, 0x44],
+            pack: [0xAA, 0xBB],
+        }
+    );
+    assert_eq!("11223344".parse::<Password>().unwrap().pack, [0, 0]);
+    assert!("112233".parse::<Password>().is_err());
+    assert!("11223344:AA".parse::<Password>().is_err());
+}
+
+#[test]
+fn protect_writes_pwd_pack_prot_and_auth0() {
+    let tag = protected_tag(Protection::ReadWrite);
+    let cfg0 = tag.cfg0();
+    {
+        let memory = tag.memory.borrow();
+        assert_eq!(memory[cfg0 + 3], 0x10, "AUTH0");
+        assert_eq!(memory[cfg0 + 4] & 0x80, 0x80, "PROT");
+        assert_eq!(memory[cfg0 + 8..cfg0 + 12], [0x11, 0x22, 0x33, 0x44]);
+        assert_eq!(memory[cfg0 + 12..cfg0 + 16], [0xAA, 0xBB, 0x00, 0x00]);
+    }
+
+    authenticate(&&tag, &password()).unwrap();
+    assert_eq!(
+        read_protection(&&tag, CHIP).unwrap(),
+        (0x10, Protection::ReadWrite)
+    );
+    remove_protection(&&tag, CHIP).unwrap();
+    assert_eq!(
+        read_protection(&&tag, CHIP).unwrap(),
+        (0xFF, Protection::Write)
+    );
+}
+
+#[test]
+fn write_protection_leaves_reads_open() {
+    let tag = protected_tag(Protection::Write);
+    assert!(read_page(&&tag, 0x10).is_ok());
+    assert!(write_page(&&tag, 0x0F, &[1, 2, 3, 4]).is_ok());
+    assert!(write_page(&&tag, 0x10, &[1, 2, 3, 4]).is_err());
+
+    let tag = protected_tag(Protection::ReadWrite);
+    assert!(read_page(&&tag, 0x0F).is_ok());
+    assert!(read_page(&&tag, 0x10).is_err());
+    assert!(write_page(&&tag, 0x10, &[1, 2, 3, 4]).is_err());
+}
+
+#[test]
+fn wrong_password_or_pack_fails_authentication() {
+    let tag = protected_tag(Protection::ReadWrite);
+
+    let wrong_pwd: Password = "99999999:AABB".parse().unwrap();
+    let err = authenticate(&&tag, &wrong_pwd).unwrap_err();
+    assert!(matches!(err, Error::AuthFailed(_)), "{}", err);
+
+    let wrong_pack: Password = "11223344:0000".parse().unwrap();
+    let err = authenticate(&&tag, &wrong_pack).unwrap_err();
+    assert!(
+        matches!(&err, Error::AuthFailed(reason) if reason.starts_with("PACK mismatch")),
+        "{}",
+        err
+    );
+
+    tag.remove();
+    let err = read_page(&PasswordTransport::new(&tag, wrong_pwd), 0x10).unwrap_err();
+    assert!(matches!(err, Error::AuthFailed(_)), "{}", err);
+}
+
+#[test]
+fn password_transport_authenticates_once_before_access() {
+    let tag = protected_tag(Protection::ReadWrite);
+    let transport = PasswordTransport::new(&tag, password());
+
+    write_page(&transport, 0x10, &[1, 2, 3, 4]).unwrap();
+    assert_eq!(read_page(&transport, 0x10).unwrap(), [1, 2, 3, 4]);
+    assert_eq!(tag.pwd_auths.get(), 2, "one from protect(), one automatic");
+}
+
+#[test]
+fn card_removed_during_automatic_authentication_is_not_a_wrong_password() {
+    let tag = protected_tag(Protection::ReadWrite);
+    let transport = PasswordTransport::new(&tag, password());
+
+    tag.present.set(false);
+    let err = read_page(&transport, 0x10).unwrap_err();
+    assert!(matches!(err, Error::CardRemoved), "{}", err);
+
+    // Authentication is tried again once the card is back.
+    tag.present.set(true);
+    assert!(read_page(&transport, 0x10).is_ok());
+}
@@ -0,0 +1,98 @@
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::dump::read_dump;
+use rust_nfc_card_reader::ntag::{read_pages, Password, PasswordTransport};
+use rust_nfc_card_reader::sim::SimulatedTag;
+
+/// Round trips to read every page of `chip`, returning the count and the pages.
+fn read_all(tag: &SimulatedTag) -> (usize, Vec<Option<[u8; 4]>>) {
+    tag.reset_count();
+    let pages = read_pages(tag, tag.chip, 0..tag.chip.block_count()).unwrap();
+    (tag.transmit_count(), pages)
+}
+
+fn assert_matches_memory(tag: &SimulatedTag, pages: &[Option<[u8; 4]>]) {
+    let memory = tag.memory();
+    let secret = tag
+        .chip
+        .config_pages()
+        .map_or(0..0, |pages| pages.start + 2..pages.end);
+    for (page, data) in pages.iter().enumerate() {
+        if !secret.contains(&page) {
+            assert_eq!(
+                data.unwrap()[..],
+                memory[page * 4..page * 4 + 4],
+                "page {}",
+                page
+            );
+        }
+    }
+}
+
+#[test]
+fn fast_read_dumps_ntag216_in_eight_round_trips() {
+    let tag = SimulatedTag::new(Chip::Ntag216);
+    let (round_trips, pages) = read_all(&tag);
+
+    assert_eq!(round_trips, 8);
+    assert_eq!(pages.len(), 231);
+    assert_matches_memory(&tag, &pages);
+}
+
+#[test]
+fn read_binary_fallback_costs_one_round_trip_per_page() {
+    let mut tag = SimulatedTag::new(Chip::Ntag216);
+    tag.pass_through = false;
+    let (round_trips, pages) = read_all(&tag);
+
+    // One failed FAST_READ and one failed READ before falling back.
+    assert_eq!(round_trips, 2 + 231);
+    assert_matches_memory(&tag, &pages);
+}
+
+#[test]
+fn ultralight_uses_native_read() {
+    let tag = SimulatedTag::new(Chip::MifareUltralight);
+    let (round_trips, pages) = read_all(&tag);
+
+    assert_eq!(round_trips, 4);
+    assert_matches_memory(&tag, &pages);
+}
+
+#[test]
+fn protected_pages_are_flagged_individually() {
+    let chip = Chip::Ntag213;
+    let tag = SimulatedTag::new(chip);
+    let mut memory = tag.memory();
+    let cfg0 = chip.config_pages().unwrap().start * 4;
+    memory[cfg0 + 3] = 0x10;
+    memory[cfg0 + 4] |= 0x80;
+    memory[cfg0 + 8..cfg0 + 12].copy_from_slice(&[0x11, 0x22, 0x33, 0x44]);
+    let tag = SimulatedTag::with_memory(chip, memory);
+
+    let (_, pages) = read_all(&tag);
+    assert!(pages[..0x10].iter().all(Option::is_some));
+    assert!(pages[0x10..].iter().all(Option::is_none));
+
+    let password: Password = "11223344:0000".parse().unwrap();
+    let transport = PasswordTransport::new(&tag, password);
+    let pages = read_pages(&transport, chip, 0..chip.block_count()).unwrap();
+    assert!(pages.iter().all(Option::is_some));
+}
+
+#[test]
+fn dump_round_trips() {
+    let tag = SimulatedTag::new(Chip::Ntag215);
+    tag.reset_count();
+    let dump = read_dump(&tag, &tag.atr()).unwrap();
+    let fast = tag.transmit_count();
+
+    let mut slow_tag = SimulatedTag::new(Chip::Ntag215);
+    slow_tag.pass_through = false;
+    let slow_dump = read_dump(&slow_tag, &slow_tag.atr()).unwrap();
+    let slow = slow_tag.transmit_count();
+
+    assert_eq!(dump, slow_dump);
+    // Chip detection and GET UID, then 135 pages in 32-page chunks.
+    assert_eq!(fast, 2 + 5);
+    assert_eq!(slow, 2 + 2 + 135);
+}
@@ -0,0 +1,234 @@
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::dump::{
+    from_bin, plan_restore, restore, Conflict, Dump, RestorePlan, SkipReason,
+};
+use rust_nfc_card_reader::transport::Transport;
+use rust_nfc_card_reader::Error;
+
+/// A transport that fails the test if anything is sent to the tag.
+struct NoWrites;
+
+impl Transport for NoWrites {
+    fn transmit<'buf>(&self, command: &[u8], _: &'buf mut [u8]) -> Result<&'buf [u8], pcsc::Error> {
+        panic!("{:02X?} sent to the tag", command);
+    }
+}
+
+/// Memory of a blank NTAG213: UID, CC, an empty NDEF message and AUTH0 at
+/// 0xFF so nothing is password protected.
+fn blank_ntag213() -> Vec<u8> {
+    let chip = Chip::Ntag213;
+    let mut memory = vec![0; chip.memory_size()];
+    memory[..16].copy_from_slice(&[
+        0x04, 0x11, 0x22, 0xBF, 0x33, 0x44, 0x55, 0x66, 0x44, 0x48, 0x00, 0x00, 0xE1, 0x10, 0x12,
+        0x00,
+    ]);
+    memory[16..20].copy_from_slice(&[0x03, 0x00, 0xFE, 0x00]);
+    memory[chip.config_pages().unwrap().start * 4 + 3] = 0xFF;
+    memory
+}
+
+fn dump_of(memory: &[u8]) -> Dump {
+    from_bin(memory, Some(Chip::Ntag213)).unwrap()
+}
+
+/// `target` with every byte of `pages` changed.
+fn changed(target: &Dump, pages: impl IntoIterator<Item = usize>) -> Dump {
+    let mut source = target.clone();
+    for page in pages {
+        source.blocks[page]
+            .data
+            .iter_mut()
+            .for_each(|byte| *byte ^= 0x5A);
+    }
+    source
+}
+
+fn writes(plan: &RestorePlan) -> Vec<usize> {
+    plan.writes.iter().map(|block| block.index).collect()
+}
+
+#[test]
+fn type2_skips_uid_lock_and_config_pages() {
+    let chip = Chip::Ntag213;
+    let target = dump_of(&blank_ntag213());
+    let mut source = changed(&target, 0..chip.block_count());
+    // Set CC bits only, which the OTP CC allows.
+    source.blocks[3].data = vec![0xE1, 0x10, 0x12, 0x0F];
+    source.blocks[5] = target.blocks[5].clone();
+    source.blocks[6].readable = false;
+
+    let plan = plan_restore(&source, &target).unwrap();
+    assert_eq!(
+        plan.skipped,
+        [
+            (0, SkipReason::Uid),
+            (1, SkipReason::Uid),
+            (2, SkipReason::LockBytes),
+            (5, SkipReason::Unchanged),
+            (6, SkipReason::Unreadable),
+            (40, SkipReason::LockBytes),
+            (41, SkipReason::Config),
+            (42, SkipReason::Config),
+            (43, SkipReason::Config),
+            (44, SkipReason::Config),
+        ]
+    );
+    let expected: Vec<usize> = (3..40).filter(|page| ![5, 6].contains(page)).collect();
+    assert_eq!(writes(&plan), expected);
+    assert!(plan.conflicts.is_empty());
+
+    let other_chip = Dump::empty(Chip::Ntag215);
+    assert!(plan_restore(&source, &other_chip).is_err());
+}
+
+#[test]
+fn cc_bits_cannot_be_cleared() {
+    let mut memory = blank_ntag213();
+    memory[15] = 0x0F;
+    let target = dump_of(&memory);
+    let mut source = target.clone();
+    source.blocks[3].data[3] = 0x00;
+
+    let plan = plan_restore(&source, &target).unwrap();
+    assert_eq!(plan.conflicts, [Conflict::OneTimeProgrammable { block: 3 }]);
+}
+
+#[test]
+fn locked_and_protected_pages_are_conflicts() {
+    let chip = Chip::Ntag213;
+    let mut memory = blank_ntag213();
+    // Static lock bit for page 4, AUTH0 = 0x20.
+    memory[10] = 0x10;
+    memory[chip.config_pages().unwrap().start * 4 + 3] = 0x20;
+    let target = dump_of(&memory);
+    let source = changed(&target, [4, 8, 0x1F, 0x20, 0x27]);
+
+    let plan = plan_restore(&source, &target).unwrap();
+    assert_eq!(
+        plan.conflicts,
+        [
+            Conflict::Locked { block: 4 },
+            Conflict::PasswordProtected {
+                block: 0x20,
+                auth0: 0x20
+            },
+            Conflict::PasswordProtected {
+                block: 0x27,
+                auth0: 0x20
+            },
+        ]
+    );
+
+    // Nothing is sent to the tag when the plan has conflicts.
+    let err = restore(&NoWrites, &plan, &target).unwrap_err();
+    assert!(matches!(err, Error::LockedPage(4)), "{}", err);
+}
+
+#[test]
+fn dynamic_lock_bits_follow_the_lock_control_tlv() {
+    let mut memory = blank_ntag213();
+    // Dynamic lock bit 0 set. By default it locks pages 16-17.
+    memory[160] = 0x01;
+    let default = dump_of(&memory);
+    let plan = plan_restore(&changed(&default, [16, 17, 18]), &default).unwrap();
+    assert_eq!(
+        plan.conflicts,
+        [
+            Conflict::Locked { block: 16 },
+            Conflict::Locked { block: 17 }
+        ]
+    );
+
+    // A Lock Control TLV with 16 bytes (4 pages) per bit.
+    memory[16..24].copy_from_slice(&[0x01, 0x03, 0xA0, 0x06, 0x44, 0x03, 0x00, 0xFE]);
+    let target = dump_of(&memory);
+    let plan = plan_restore(&changed(&target, [16, 17, 18, 20]), &target).unwrap();
+    assert_eq!(
+        plan.conflicts,
+        [
+            Conflict::Locked { block: 16 },
+            Conflict::Locked { block: 17 },
+            Conflict::Locked { block: 18 },
+        ]
+    );
+}
+
+#[test]
+fn lock_control_tlvs_outside_the_lock_page_are_ignored() {
+    let mut memory = blank_ntag213();
+    memory[20..24].copy_from_slice(&[0xFF; 4]);
+    let source_pages = [16, 17, 18, 19];
+
+    // Lock bytes at page 15 with 32 KiB pages, far past the end of memory,
+    // and lock bytes at page 5, over user data.
+    for tlv in [[0xF0, 0x08, 0x2F], [0x50, 0x08, 0x22]] {
+        memory[16..24].copy_from_slice(&[0x01, 0x03, tlv[0], tlv[1], tlv[2], 0x03, 0x00, 0xFE]);
+        let target = dump_of(&memory);
+        let plan = plan_restore(&changed(&target, source_pages), &target).unwrap();
+        assert!(plan.conflicts.is_empty(), "{:02X?}", tlv);
+        assert_eq!(writes(&plan), source_pages);
+    }
+}
+
+/// Sector trailer bytes 6-8 for the access conditions of the four groups,
+/// each `0bC1C2C3`.
+fn access_bytes(conditions: [u8; 4]) -> [u8; 3] {
+    let bit = |shift: u8| {
+        (0..4).fold(0u8, |bits, group| {
+            bits | ((conditions[group] >> shift) & 1) << group
+        })
+    };
+    let (c1, c2, c3) = (bit(2), bit(1), bit(0));
+    [
+        (!c2 & 0x0F) << 4 | (!c1 & 0x0F),
+        c1 << 4 | (!c3 & 0x0F),
+        c3 << 4 | c2,
+    ]
+}
+
+fn classic_target() -> Dump {
+    let chip = Chip::MifareClassic1K;
+    let mut dump = Dump::empty(chip);
+    for block in &mut dump.blocks {
+        block.readable = true;
+        if chip.is_sector_trailer(block.index) {
+            block.data = [&[0xFF; 6][..], &[0xFF, 0x07, 0x80, 0x69], &[0xFF; 6]].concat();
+        }
+    }
+    dump.sector_keys = vec![Some([0xFF; 6]); chip.sector_count()];
+    dump
+}
+
+#[test]
+fn classic_sectors_that_cannot_be_written_are_conflicts() {
+    let chip = Chip::MifareClassic1K;
+    let mut target = classic_target();
+    assert_eq!(target.blocks[3].data[6..9], access_bytes([0, 0, 0, 1]));
+    // Sector 1 opens with no known key; block 9 of sector 2 is read-only.
+    target.sector_keys[1] = None;
+    target.blocks[11].data[6..9].copy_from_slice(&access_bytes([0, 0b010, 0, 1]));
+    let source = changed(&target, [0, 1, 3, 4, 5, 8, 9]);
+
+    let plan = plan_restore(&source, &target).unwrap();
+    assert_eq!(
+        plan.skipped[..2],
+        [(0, SkipReason::Uid), (2, SkipReason::Unchanged)]
+    );
+    assert!(plan.skipped.contains(&(3, SkipReason::SectorTrailer)));
+    assert_eq!(writes(&plan), [1, 4, 5, 8, 9]);
+    assert_eq!(
+        plan.conflicts,
+        [
+            Conflict::SectorInaccessible { sector: 1 },
+            Conflict::WriteForbidden { block: 9 },
+        ]
+    );
+    assert_eq!(chip.sector_of(9), 2);
+
+    // Writing block 1 alone is fine.
+    let source = changed(&classic_target(), [1]);
+    let plan = plan_restore(&source, &classic_target()).unwrap();
+    assert_eq!(writes(&plan), [1]);
+    assert!(plan.conflicts.is_empty());
+}
@@ -0,0 +1,315 @@
+use pcsc::Error;
+use rust_nfc_card_reader::session::{Connector, SessionManager};
+use rust_nfc_card_reader::transport::Transport;
+use rust_nfc_card_reader::Error as NfcError;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A bench reader. `insertion` numbers the card on it among all the cards
+/// put on the reader, `reset` is set when the card was reset behind the
+/// connection's back.
+#[derive(Default)]
+struct Slot {
+    insertion: Option<u8>,
+    inserted: u8,
+    reset: bool,
+    in_flight: usize,
+}
+
+#[derive(Default)]
+struct Bench {
+    readers: Mutex<BTreeMap<String, Slot>>,
+    connects: AtomicUsize,
+    reconnects: AtomicUsize,
+    overlaps: AtomicUsize,
+}
+
+impl Bench {
+    fn new(readers: &[&str]) -> Arc<Bench> {
+        let bench = Bench::default();
+        for reader in readers {
+            bench.slots().insert(reader.to_string(), Slot::default());
+        }
+        Arc::new(bench)
+    }
+
+    fn slots(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Slot>> {
+        self.readers.lock().unwrap()
+    }
+
+    fn insert(&self, reader: &str) {
+        let mut slots = self.slots();
+        let slot = slots.get_mut(reader).unwrap();
+        slot.inserted += 1;
+        slot.insertion = Some(slot.inserted);
+    }
+
+    fn remove(&self, reader: &str) {
+        self.slots().get_mut(reader).unwrap().insertion = None;
+    }
+
+    fn reset(&self, reader: &str) {
+        self.slots().get_mut(reader).unwrap().reset = true;
+    }
+}
+
+/// Connection to a bench card. Answers every APDU with the insertion it
+/// was made for and 90 00, after a short delay.
+struct BenchCard {
+    bench: Arc<Bench>,
+    reader: String,
+    insertion: u8,
+}
+
+impl BenchCard {
+    fn check(&self) -> Result<(), Error> {
+        let slots = self.bench.slots();
+        let slot = &slots[&self.reader];
+        if slot.insertion != Some(self.insertion) {
+            return Err(Error::RemovedCard);
+        }
+        if slot.reset {
+            return Err(Error::ResetCard);
+        }
+        Ok(())
+    }
+}
+
+impl Transport for BenchCard {
+    fn transmit<'buf>(
+        &self,
+        _send_buffer: &[u8],
+        receive_buffer: &'buf mut [u8],
+    ) -> Result<&'buf [u8], Error> {
+        self.check()?;
+        let in_flight = {
+            let mut slots = self.bench.slots();
+            let slot = slots.get_mut(&self.reader).unwrap();
+            slot.in_flight += 1;
+            slot.in_flight
+        };
+        if in_flight > 1 {
+            self.bench.overlaps.fetch_add(1, Ordering::SeqCst);
+        }
+        thread::sleep(Duration::from_millis(20));
+        self.bench.slots().get_mut(&self.reader).unwrap().in_flight -= 1;
+        receive_buffer[..3].copy_from_slice(&[self.insertion, 0x90, 0x00]);
+        Ok(&receive_buffer[..3])
+    }
+}
+
+/// Connects to bench cards.
+struct BenchConnector(Arc<Bench>);
+
+impl Connector for BenchConnector {
+    type Card = BenchCard;
+
+    fn list_readers(&self) -> Result<Vec<String>, Error> {
+        Ok(self.0.slots().keys().cloned().collect())
+    }
+
+    fn connect(&self, reader: &str) -> Result<(BenchCard, Vec<u8>), Error> {
+        self.0.connects.fetch_add(1, Ordering::SeqCst);
+        let slots = self.0.slots();
+        let insertion = slots
+            .get(reader)
+            .ok_or(Error::UnknownReader)?
+            .insertion
+            .ok_or(Error::NoSmartcard)?;
+        let card = BenchCard {
+            bench: Arc::clone(&self.0),
+            reader: reader.to_string(),
+            insertion,
+        };
+        Ok((card, vec![0x3B, insertion]))
+    }
+
+    fn reconnect(&self, card: &mut BenchCard) -> Result<Vec<u8>, Error> {
+        self.0.reconnects.fetch_add(1, Ordering::SeqCst);
+        let mut slots = self.0.slots();
+        let slot = slots.get_mut(&card.reader).unwrap();
+        if slot.insertion != Some(card.insertion) {
+            return Err(Error::RemovedCard);
+        }
+        slot.reset = false;
+        Ok(vec![0x3B, card.insertion])
+    }
+
+    fn status(&self, card: &BenchCard) -> Result<Vec<u8>, Error> {
+        card.check()?;
+        Ok(vec![0x3B, card.insertion])
+    }
+}
+
+fn transmit(card: &BenchCard) -> Result<u8, NfcError> {
+    let mut buf = [0; 16];
+    Ok(card.transmit(&[0xFF, 0xCA, 0x00, 0x00, 0x00], &mut buf)?[0])
+}
+
+#[test]
+fn readers_run_in_parallel_and_each_reader_in_turn() {
+    let names: Vec<String> = (0..8).map(|n| format!("ACR122U {}", n)).collect();
+    let bench = Bench::new(&names.iter().map(String::as_str).collect::<Vec<_>>());
+    for name in &names {
+        bench.insert(name);
+    }
+    let manager = Arc::new(SessionManager::new(BenchConnector(Arc::clone(&bench))));
+    assert_eq!(manager.refresh().unwrap(), names);
+
+    let started = Instant::now();
+    let workers: Vec<_> = names
+        .iter()
+        .flat_map(|name| [name.clone(), name.clone()])
+        .map(|name| {
+            let manager = Arc::clone(&manager);
+            thread::spawn(move || {
+                manager
+                    .with_card(&name, |card, _| {
+                        transmit(card)?;
+                        transmit(card)
+                    })
+                    .map_err(|err| err.to_string())
+            })
+        })
+        .collect();
+    for worker in workers {
+        assert_eq!(worker.join().unwrap(), Ok(1));
+    }
+
+    // 16 sessions of two 20 ms exchanges; two per reader, readers in parallel.
+    assert!(started.elapsed() < Duration::from_millis(16 * 40));
+    assert_eq!(bench.overlaps.load(Ordering::SeqCst), 0);
+    // Connections are kept between sessions.
+    assert_eq!(bench.connects.load(Ordering::SeqCst), 8);
+}
+
+#[test]
+fn reconnects_and_retries_after_a_reset() {
+    let bench = Bench::new(&["Front"]);
+    bench.insert("Front");
+    let manager = SessionManager::new(BenchConnector(Arc::clone(&bench)));
+
+    // Reset between sessions: picked up before the operation runs.
+    assert_eq!(
+        manager
+            .with_card("Front", |card, _| transmit(card))
+            .unwrap(),
+        1
+    );
+    bench.reset("Front");
+    assert_eq!(
+        manager
+            .with_card("Front", |card, _| transmit(card))
+            .unwrap(),
+        1
+    );
+    assert_eq!(bench.reconnects.load(Ordering::SeqCst), 1);
+
+    // Reset during the operation: reconnected and run again.
+    let mut runs = 0;
+    let result = manager.with_card("Front", |card, _| {
+        runs += 1;
+        if runs == 1 {
+            bench.reset("Front");
+        }
+        transmit(card)
+    });
+    assert_eq!(result.unwrap(), 1);
+    assert_eq!(runs, 2);
+    assert_eq!(bench.reconnects.load(Ordering::SeqCst), 2);
+    assert_eq!(bench.connects.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn connects_to_the_next_card_after_a_removal() {
+    let bench = Bench::new(&["Front"]);
+    bench.insert("Front");
+    let manager = SessionManager::new(BenchConnector(Arc::clone(&bench)));
+    assert_eq!(
+        manager
+            .with_card("Front", |card, _| transmit(card))
+            .unwrap(),
+        1
+    );
+
+    // Removed during the operation: reported, not retried.
+    let mut runs = 0;
+    let err = manager
+        .with_card("Front", |card, _| {
+            runs += 1;
+            bench.remove("Front");
+            transmit(card)
+        })
+        .unwrap_err();
+    assert!(matches!(err, NfcError::CardRemoved), "{}", err);
+    assert_eq!(runs, 1);
+
+    let err = manager
+        .with_card("Front", |card, _| transmit(card))
+        .unwrap_err();
+    assert!(matches!(err, NfcError::NoCard), "{}", err);
+
+    // Swapped between sessions: the new card gets a new connection.
+    bench.insert("Front");
+    let atr = manager
+        .with_card("Front", |_, atr| Ok(atr.to_vec()))
+        .unwrap();
+    assert_eq!(atr, [0x3B, 2]);
+    bench.insert("Front");
+    assert_eq!(
+        manager
+            .with_card("Front", |card, _| transmit(card))
+            .unwrap(),
+        3
+    );
+}
+
+#[test]
+fn tracks_readers_coming_and_going() {
+    let bench = Bench::new(&["Front"]);
+    let manager = SessionManager::new(BenchConnector(Arc::clone(&bench)));
+    assert!(manager.readers().is_empty());
+
+    bench.slots().insert("Back".to_string(), Slot::default());
+    bench.insert("Back");
+    // Unknown readers trigger a refresh.
+    assert_eq!(
+        manager.with_card("Back", |card, _| transmit(card)).unwrap(),
+        1
+    );
+    assert_eq!(manager.readers(), ["Back", "Front"]);
+
+    bench.slots().remove("Back");
+    manager.refresh().unwrap();
+    assert_eq!(manager.readers(), ["Front"]);
+    let err = manager
+        .with_card("Nowhere", |card, _| transmit(card))
+        .unwrap_err();
+    assert!(matches!(err, NfcError::NoReader), "{}", err);
+}
+
+#[test]
+fn a_panicking_operation_does_not_block_the_reader() {
+    let bench = Bench::new(&["Front"]);
+    bench.insert("Front");
+    let manager = Arc::new(SessionManager::new(BenchConnector(Arc::clone(&bench))));
+    let panicking = Arc::clone(&manager);
+    let result = thread::spawn(move || {
+        let _ = panicking.with_card("Front", |_, _| -> Result<(), _> {
+            panic!("operation failed")
+        });
+    })
+    .join();
+    assert!(result.is_err());
+
+    assert_eq!(
+        manager
+            .with_card("Front", |card, _| transmit(card))
+            .unwrap(),
+        1
+    );
+    assert_eq!(bench.connects.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,139 @@
+use rust_nfc_card_reader::ndef::{
+    decode_text, encode_ndef_message, parse_ndef_message, Action, NdefRecord, SmartPoster,
+    TNF_MIME_MEDIA,
+};
+
+fn poster() -> SmartPoster {
+    let mut poster = SmartPoster::new("https://example.com/spring");
+    poster
+        .titles
+        .push(("en".to_string(), "Spring sale".to_string()));
+    poster
+        .titles
+        .push(("de".to_string(), "Frühlingsangebot".to_string()));
+    poster.action = Some(Action::Do);
+    poster.size = Some(1024);
+    poster.mime_type = Some("text/html".to_string());
+    poster.icons.push(NdefRecord::new(
+        TNF_MIME_MEDIA,
+        b"image/png",
+        vec![0x89, b'P', b'N', b'G'],
+    ));
+    poster
+}
+
+#[test]
+fn smart_poster_round_trips_through_a_message() {
+    let message = encode_ndef_message(&[NdefRecord::smart_poster(&poster()).unwrap()]).unwrap();
+    let records = parse_ndef_message(&message).unwrap();
+
+    assert_eq!(records.len(), 1);
+    assert_eq!(SmartPoster::from_record(&records[0]).unwrap(), poster());
+    assert_eq!(
+        records[0].summary(),
+        "Smart Poster https://example.com/spring \"Spring sale\""
+    );
+}
+
+#[test]
+fn nested_message_layout() {
+    let record = poster().to_record().unwrap();
+    assert_eq!(record.record_type, b"Sp");
+
+    let nested = parse_ndef_message(&record.payload).unwrap();
+    let types: Vec<&[u8]> = nested.iter().map(|r| &r.record_type[..]).collect();
+    assert_eq!(
+        types,
+        [&b"U"[..], b"T", b"T", b"act", b"s", b"t", b"image/png"]
+    );
+    // First nested record: MB | SR | well-known, "https://" abbreviated to 0x04.
+    assert_eq!(record.payload[..5], [0x91, 0x01, 0x13, b'U', 0x04]);
+    assert_eq!(nested[4].payload, [0x00, 0x00, 0x04, 0x00]);
+}
+
+#[test]
+fn title_falls_back_to_first_language() {
+    let poster = poster();
+    assert_eq!(poster.title("DE"), Some("Frühlingsangebot"));
+    assert_eq!(poster.title("fr"), Some("Spring sale"));
+}
+
+#[test]
+fn invalid_smart_posters_are_rejected() {
+    let no_uri = NdefRecord::new(
+        0x01,
+        b"Sp",
+        encode_ndef_message(&[NdefRecord::text("en", "Title only").unwrap()]).unwrap(),
+    );
+    assert!(SmartPoster::from_record(&no_uri).is_err());
+
+    let two_uris = NdefRecord::new(
+        0x01,
+        b"Sp",
+        encode_ndef_message(&[NdefRecord::uri("https://a"), NdefRecord::uri("https://b")]).unwrap(),
+    );
+    assert!(SmartPoster::from_record(&two_uris).is_err());
+
+    let bad_action = NdefRecord::new(
+        0x01,
+        b"Sp",
+        encode_ndef_message(&[
+            NdefRecord::uri("https://a"),
+            NdefRecord::new(0x01, b"act", vec![0x07]),
+        ])
+        .unwrap(),
+    );
+    assert!(SmartPoster::from_record(&bad_action).is_err());
+    assert!(SmartPoster::from_record(&NdefRecord::uri("https://a")).is_err());
+}
+
+#[test]
+fn types_and_ids_longer_than_255_bytes_are_not_encoded() {
+    let mut record = NdefRecord::new(TNF_MIME_MEDIA, &[b'a'; 255], Vec::new());
+    record.id = vec![b'1'; 255];
+    let data = encode_ndef_message(&[record.clone()]).unwrap();
+    assert_eq!(parse_ndef_message(&data).unwrap(), [record.clone()]);
+
+    let mut long_type = record.clone();
+    long_type.record_type.push(b'a');
+    let err = encode_ndef_message(&[NdefRecord::uri("https://a"), long_type.clone()]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Type of record 1 is 256 bytes long, the limit is 255"
+    );
+
+    // A Smart Poster with such an icon cannot be encoded either.
+    let mut poster = poster();
+    poster.icons.push(long_type);
+    assert!(poster.to_record().is_err());
+
+    record.id.push(b'1');
+    let err = encode_ndef_message(&[record]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "ID of record 0 is 256 bytes long, the limit is 255"
+    );
+}
+
+#[test]
+fn language_codes_longer_than_63_bytes_are_rejected() {
+    let language = "x".repeat(63);
+    let record = NdefRecord::text(&language, "hi").unwrap();
+    assert_eq!(record.payload[0], 63);
+    assert_eq!(
+        decode_text(&record.payload),
+        Some((language.clone(), "hi".to_string()))
+    );
+
+    let language = "x".repeat(64);
+    let err = NdefRecord::text(&language, "hi").unwrap_err();
+    assert!(
+        err.to_string().ends_with("is longer than 63 bytes"),
+        "{}",
+        err
+    );
+
+    let mut poster = SmartPoster::new("https://example.com");
+    poster.titles.push((language, "hi".to_string()));
+    assert!(poster.to_record().is_err());
+}
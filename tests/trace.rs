@@ -0,0 +1,66 @@
+use rust_nfc_card_reader::apdu::load_key;
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::ntag::{authenticate, protect, read_page, Password, Protection};
+use rust_nfc_card_reader::sim::SimulatedTag;
+use rust_nfc_card_reader::trace::{redact_command, Tracer};
+use rust_nfc_card_reader::transport::Transport;
+
+#[test]
+fn records_each_exchange_with_its_reader() {
+    let tracer = Tracer::new();
+    let tag = SimulatedTag::new(Chip::Ntag213);
+    let traced = tracer.wrap(&tag, "Bench");
+    read_page(&traced, 4).unwrap();
+    let mut response_buf = [0; 16];
+    assert!(traced.transmit(&[0xFF, 0xB0], &mut response_buf).is_ok());
+
+    let exchanges = tracer.exchanges();
+    assert_eq!(exchanges.len(), tag.transmit_count());
+    assert_eq!(exchanges[0].reader, "Bench");
+    assert!(exchanges[0].command.starts_with("FF B0 00 04"));
+    assert!(exchanges[0].response.as_ref().unwrap().ends_with("90 00"));
+    let line = exchanges[0].to_string();
+    assert!(line.contains("[Bench] > FF B0 00 04"), "{}", line);
+    assert!(line.contains("[Bench] < "), "{}", line);
+}
+
+#[test]
+fn masks_keys_and_passwords() {
+    assert_eq!(
+        redact_command(&load_key(0x00, &[0xA0, 0xA1, 0xA2, 0xA3, 0xA4, 0xA5])),
+        "FF 82 00 00 06 ** ** ** ** ** **"
+    );
+    // Writes to other pages stay readable.
+    assert_eq!(
+        redact_command(&[0xFF, 0xD6, 0x00, 0x04, 0x04, 0x01, 0x02, 0x03, 0x04]),
+        "FF D6 00 04 04 01 02 03 04"
+    );
+
+    let chip = Chip::Ntag213;
+    let tracer = Tracer::new();
+    let tag = SimulatedTag::new(chip);
+    let traced = tracer.wrap(&tag, "Bench");
+    let password: Password = "11223344:AABB".parse().unwrap();
+    protect(&traced, chip, &password, 4, Protection::Write).unwrap();
+    authenticate(&traced, &password).unwrap();
+
+    let mut trace = Vec::new();
+    tracer.write_to(&mut trace).unwrap();
+    let trace = String::from_utf8(trace).unwrap();
+    assert!(!trace.contains("11 22 33 44"), "{}", trace);
+    assert!(!trace.contains("AA BB"), "{}", trace);
+    assert!(trace.contains("D4 42 1B ** ** ** **"), "{}", trace);
+}
+
+#[test]
+fn saves_to_a_file() {
+    let path = std::env::temp_dir().join(format!("nfc-trace-{}.txt", std::process::id()));
+    let tracer = Tracer::new();
+    let tag = SimulatedTag::new(Chip::Ntag213);
+    read_page(&tracer.wrap(&tag, "Bench"), 0).unwrap();
+    tracer.save(&path).unwrap();
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(text.lines().count(), 2 * tracer.exchanges().len());
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,109 @@
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::dump::{ndef_message, read_dump};
+use rust_nfc_card_reader::ndef::{
+    capacity_warning, encode_ndef_message, parse_ndef_message, Contact, NdefRecord, Phone,
+    VcardVersion,
+};
+use rust_nfc_card_reader::ntag::write_ndef_message;
+use rust_nfc_card_reader::sim::SimulatedTag;
+
+fn contact() -> Contact {
+    Contact {
+        given_name: "Ada".to_string(),
+        family_name: "Lovelace".to_string(),
+        phones: vec![
+            Phone::new("+441234567890", &["cell"]),
+            Phone::new("+441234000000", &["work", "voice"]),
+        ],
+        emails: vec!["ada@example.com".to_string()],
+        organization: Some("Analytical Engines; Ltd.".to_string()),
+        url: Some("https://example.com/ada".to_string()),
+    }
+}
+
+#[test]
+fn contact_round_trips_in_both_versions() {
+    for version in [VcardVersion::V3, VcardVersion::V4] {
+        let message = encode_ndef_message(&[NdefRecord::vcard(&contact(), version)]).unwrap();
+        let records = parse_ndef_message(&message).unwrap();
+
+        assert_eq!(records[0].mime_type(), Some("text/vcard"));
+        assert_eq!(Contact::from_record(&records[0]).unwrap(), contact());
+        assert_eq!(records[0].summary(), "Contact Ada Lovelace");
+    }
+}
+
+#[test]
+fn encoder_escapes_and_folds() {
+    let mut contact = contact();
+    contact.url = Some(format!("https://example.com/{}", "a".repeat(100)));
+    let vcard = contact.to_vcard(VcardVersion::V4);
+
+    assert!(vcard.starts_with("BEGIN:VCARD\r\nVERSION:4.0\r\n"));
+    assert!(vcard.contains("ORG:Analytical Engines\\; Ltd.\r\n"));
+    assert!(vcard.contains("TEL;VALUE=uri;TYPE=cell:tel:+441234567890\r\n"));
+    assert!(vcard.split("\r\n").all(|line| line.len() <= 75));
+    assert_eq!(Contact::parse_vcard(&vcard).unwrap(), contact);
+}
+
+#[test]
+fn parses_vcards_written_by_phones() {
+    let text = "BEGIN:VCARD\n\
+                VERSION:3.0\n\
+                FN:Grace Hopper\n\
+                N:Hopper;Grace;;;\n\
+                item1.TEL;CELL;PREF:+1 555 0100\n\
+                TEL;TYPE=\"work\":tel:+15550199\n\
+                EMAIL;TYPE=INTERNET:grace@exam\n ple.com\n\
+                ORG:Navy;Computing\n\
+                NOTE:ignored\\, entirely\n\
+                END:VCARD\n";
+    let contact = Contact::parse_vcard(text).unwrap();
+
+    assert_eq!(contact.formatted_name(), "Grace Hopper");
+    assert_eq!(
+        contact.phones,
+        vec![
+            Phone::new("+1 555 0100", &["cell", "pref"]),
+            Phone::new("+15550199", &["work"]),
+        ]
+    );
+    assert_eq!(contact.emails, vec!["grace@example.com"]);
+    assert_eq!(contact.organization.as_deref(), Some("Navy"));
+}
+
+#[test]
+fn malformed_vcards_are_rejected() {
+    for text in [
+        "VERSION:3.0\nFN:x\nEND:VCARD\n",
+        "BEGIN:VCARD\nVERSION:3.0\nFN:x\n",
+        "BEGIN:VCARD\nVERSION:2.1\nFN:x\nEND:VCARD\n",
+        "BEGIN:VCARD\nVERSION:4.0\nEMAIL:x@example.com\nEND:VCARD\n",
+        "BEGIN:VCARD\nVERSION:4.0\nFN\nEND:VCARD\n",
+    ] {
+        assert!(Contact::parse_vcard(text).is_err(), "{:?}", text);
+    }
+}
+
+#[test]
+fn capacity_is_checked_against_the_chip() {
+    let message = encode_ndef_message(&[contact().to_record(VcardVersion::V3)]).unwrap();
+    assert!(message.len() > Chip::MifareUltralight.ndef_capacity());
+    assert!(capacity_warning(Chip::MifareUltralight, message.len()).is_some());
+    assert!(capacity_warning(Chip::Ntag215, message.len()).is_none());
+
+    let tag = SimulatedTag::new(Chip::MifareUltralight);
+    assert!(write_ndef_message(&tag, tag.chip, &message).is_err());
+}
+
+#[test]
+fn contact_written_to_a_tag_reads_back() {
+    let tag = SimulatedTag::new(Chip::Ntag215);
+    let message =
+        encode_ndef_message(&[contact().to_record_for(tag.chip, VcardVersion::V3)]).unwrap();
+    write_ndef_message(&tag, tag.chip, &message).unwrap();
+
+    let dump = read_dump(&tag, &tag.atr()).unwrap();
+    let records = parse_ndef_message(&ndef_message(&dump).unwrap()).unwrap();
+    assert_eq!(Contact::from_record(&records[0]).unwrap(), contact());
+}
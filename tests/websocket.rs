@@ -0,0 +1,209 @@
+#![cfg(feature = "http-api")]
+
+use rust_nfc_card_reader::api::{ApiServer, Readers, SimulatedReaders};
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::ndef::{encode_ndef_message, NdefRecord};
+use rust_nfc_card_reader::ntag::write_ndef_message;
+use rust_nfc_card_reader::sim::SimulatedTag;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Sample handshake from RFC 6455, section 1.3.
+const KEY: &str = "dGhlIHNhbXBsZSBub25jZQ==";
+const ACCEPT: &str = "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=";
+
+fn start(readers: &Arc<SimulatedReaders>, allow_origin: Option<&str>) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let mut server = ApiServer::new(Arc::clone(readers) as Arc<dyn Readers>);
+    server.poll_interval = Duration::from_millis(20);
+    server.allow_origin = allow_origin.map(str::to_string);
+    std::thread::spawn(move || server.serve(listener).unwrap());
+    address
+}
+
+/// Minimal client: masked frames out, unmasked frames in.
+struct Client(BufReader<TcpStream>);
+
+impl Client {
+    /// Performs the handshake and returns the client with the response head.
+    fn connect(address: SocketAddr, path: &str, headers: &str) -> (Option<Client>, Vec<String>) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+             Connection: keep-alive, Upgrade\r\nSec-WebSocket-Key: {}\r\n\
+             Sec-WebSocket-Version: 13\r\n{}\r\n",
+            path, KEY, headers
+        )
+        .unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut head = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            head.push(line.trim_end().to_string());
+        }
+        let upgraded = head[0].starts_with("HTTP/1.1 101");
+        (upgraded.then_some(Client(reader)), head)
+    }
+
+    fn send(&mut self, opcode: u8, payload: &[u8]) {
+        let mask = [0x12, 0x34, 0x56, 0x78];
+        let mut frame = vec![0x80 | opcode, 0x80 | payload.len() as u8];
+        frame.extend_from_slice(&mask);
+        frame.extend(
+            payload
+                .iter()
+                .enumerate()
+                .map(|(i, byte)| byte ^ mask[i % 4]),
+        );
+        self.0.get_mut().write_all(&frame).unwrap();
+    }
+
+    fn receive(&mut self) -> (u8, Vec<u8>) {
+        let mut head = [0; 2];
+        self.0.read_exact(&mut head).unwrap();
+        assert_eq!(head[1] & 0x80, 0, "server frames are not masked");
+        let length = match head[1] {
+            126 => {
+                let mut length = [0; 2];
+                self.0.read_exact(&mut length).unwrap();
+                u16::from_be_bytes(length) as usize
+            }
+            length => length as usize,
+        };
+        let mut payload = vec![0; length];
+        self.0.read_exact(&mut payload).unwrap();
+        (head[0] & 0x0F, payload)
+    }
+
+    fn receive_json(&mut self) -> Value {
+        let (opcode, payload) = self.receive();
+        assert_eq!(opcode, 0x1);
+        serde_json::from_slice(&payload).unwrap()
+    }
+}
+
+fn tag_with_uri(uri: &str) -> SimulatedTag {
+    let tag = SimulatedTag::new(Chip::Ntag213);
+    let message = encode_ndef_message(&[NdefRecord::uri(uri)]).unwrap();
+    write_ndef_message(&tag, tag.chip, &message).unwrap();
+    tag
+}
+
+#[test]
+fn handshake_and_card_events_with_ndef() {
+    let readers = Arc::new(SimulatedReaders::new(&["Front"]));
+    let address = start(&readers, None);
+    let (client, head) = Client::connect(address, "/ws", "");
+    assert!(head.contains(&format!("Sec-WebSocket-Accept: {}", ACCEPT)));
+    let mut client = client.unwrap();
+
+    readers
+        .insert("Front", tag_with_uri("https://example.com/a"))
+        .unwrap();
+    let event = client.receive_json();
+    assert_eq!(event["event"], "inserted");
+    assert_eq!(event["reader"], "Front");
+    assert_eq!(event["card_type"], "MIFARE Ultralight/NTAG");
+    assert_eq!(event["card"]["uid"], "04112233445566");
+    assert_eq!(event["card"]["chip"], "ntag213");
+    assert_eq!(
+        event["card"]["ndef"]["records"][0]["summary"],
+        "URI https://example.com/a"
+    );
+
+    readers.remove("Front").unwrap();
+    assert_eq!(client.receive_json()["event"], "removed");
+
+    readers.attach("Back").unwrap();
+    assert_eq!(
+        client.receive_json(),
+        json!({"event": "attached", "reader": "Back"})
+    );
+    readers.detach("Back").unwrap();
+    assert_eq!(
+        client.receive_json(),
+        json!({"event": "detached", "reader": "Back"})
+    );
+}
+
+#[test]
+fn clients_subscribe_to_some_readers() {
+    let readers = Arc::new(SimulatedReaders::new(&["Front", "Back"]));
+    let address = start(&readers, None);
+    let (client, _) = Client::connect(address, "/ws?readers=Back", "");
+    let mut client = client.unwrap();
+
+    readers
+        .insert("Front", SimulatedTag::new(Chip::Ntag213))
+        .unwrap();
+    readers
+        .insert("Back", SimulatedTag::new(Chip::Ntag215))
+        .unwrap();
+    let event = client.receive_json();
+    assert_eq!(
+        (&event["event"], &event["reader"]),
+        (&json!("inserted"), &json!("Back"))
+    );
+
+    client.send(0x1, br#"{"subscribe": ["Front"]}"#);
+    assert_eq!(client.receive_json(), json!({ "subscribed": ["Front"] }));
+    readers.remove("Back").unwrap();
+    readers.remove("Front").unwrap();
+    assert_eq!(client.receive_json()["reader"], "Front");
+
+    client.send(0x1, br#"{"subscribe": 5}"#);
+    assert!(client.receive_json()["error"].is_string());
+    client.send(0x1, br#"{"subscribe": null}"#);
+    assert_eq!(client.receive_json(), json!({ "subscribed": null }));
+    readers
+        .insert("Back", SimulatedTag::new(Chip::Ntag215))
+        .unwrap();
+    assert_eq!(client.receive_json()["reader"], "Back");
+}
+
+#[test]
+fn answers_ping_and_close() {
+    let readers = Arc::new(SimulatedReaders::new(&["Front"]));
+    let address = start(&readers, None);
+    let (client, _) = Client::connect(address, "/ws", "");
+    let mut client = client.unwrap();
+
+    client.send(0x9, b"hi");
+    assert_eq!(client.receive(), (0xA, b"hi".to_vec()));
+    client.send(0x8, &1000u16.to_be_bytes());
+    assert_eq!(client.receive(), (0x8, 1000u16.to_be_bytes().to_vec()));
+    let mut rest = Vec::new();
+    client.0.read_to_end(&mut rest).unwrap();
+    assert!(rest.is_empty());
+}
+
+#[test]
+fn foreign_origins_and_bad_handshakes_are_refused() {
+    let readers = Arc::new(SimulatedReaders::new(&["Front"]));
+    let address = start(&readers, Some("http://localhost:3000"));
+
+    let (client, head) = Client::connect(address, "/ws", "Origin: http://evil.example\r\n");
+    assert!(client.is_none());
+    assert_eq!(head[0], "HTTP/1.1 403 Forbidden");
+
+    let (client, _) = Client::connect(address, "/ws", "Origin: http://localhost:3000\r\n");
+    assert!(client.is_some());
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(stream, "GET /ws HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 400 Bad Request"));
+}
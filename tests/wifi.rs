@@ -0,0 +1,110 @@
+use rust_nfc_card_reader::chip::Chip;
+use rust_nfc_card_reader::dump::{ndef_message, read_dump};
+use rust_nfc_card_reader::hex;
+use rust_nfc_card_reader::ndef::{
+    encode_ndef_message, parse_ndef_message, AuthType, EncryptionType, NdefRecord, WifiCredential,
+};
+use rust_nfc_card_reader::ntag::write_ndef_message;
+use rust_nfc_card_reader::sim::SimulatedTag;
+use rust_nfc_card_reader::Error;
+
+const HOME: &str = "104A000110\
+                    100E0030\
+                    102600010110450004486F6D65\
+                    10030002002010 0F00020008\
+                    10270009736563726574313233\
+                    10200006FFFFFFFFFFFF\
+                    10490006 00372A 000120";
+
+fn home() -> Vec<u8> {
+    hex::decode(&HOME.replace(' ', "")).unwrap()
+}
+
+#[test]
+fn credential_encodes_to_wsc_attributes() {
+    assert_eq!(
+        WifiCredential::new("Home", "secret123").unwrap().encode(),
+        home()
+    );
+}
+
+#[test]
+fn credential_decodes_from_wsc_attributes() {
+    let credential = WifiCredential::decode(&home()).unwrap();
+    assert_eq!(credential.ssid, "Home");
+    assert_eq!(credential.network_key, "secret123");
+    assert_eq!(credential.auth_type, AuthType::Wpa2Personal);
+    assert_eq!(credential.encryption_type, EncryptionType::Aes);
+    assert_eq!(credential.mac_address, [0xFF; 6]);
+    assert_eq!(credential.version2, Some(0x20));
+}
+
+#[test]
+fn open_network_without_optional_attributes() {
+    // Credential with only SSID, auth and encryption type, no Version2.
+    let data = hex::decode("100E00141045000443616665100300020001100F00020001").unwrap();
+    let credential = WifiCredential::decode(&data).unwrap();
+
+    assert_eq!(credential, {
+        let mut expected = WifiCredential::new("Cafe", "").unwrap();
+        expected.version2 = None;
+        expected
+    });
+}
+
+#[test]
+fn malformed_credentials_are_rejected() {
+    let mut truncated = home();
+    truncated.truncate(20);
+    let mut bad_auth = home();
+    // Low byte of the Authentication Type value.
+    bad_auth[27] = 0x40;
+
+    for (data, message) in [
+        (truncated, "overruns"),
+        (bad_auth, "Unknown Authentication Type"),
+        (hex::decode("104A000110").unwrap(), "no Credential"),
+    ] {
+        let err = WifiCredential::decode(&data).unwrap_err().to_string();
+        assert!(err.contains(message), "{}", err);
+    }
+}
+
+#[test]
+fn ssid_and_key_lengths_are_checked() {
+    let ssid = "s".repeat(32);
+    assert!(WifiCredential::new(&ssid, "").is_ok());
+    assert!(WifiCredential::new(&format!("{}s", ssid), "").is_err());
+    assert!(WifiCredential::new("", "").is_err());
+
+    for key in ["k".repeat(8), "k".repeat(63), "0aF9".repeat(16)] {
+        let credential = WifiCredential::new("Home", &key).unwrap();
+        assert_eq!(credential.auth_type, AuthType::Wpa2Personal, "{}", key);
+    }
+    for key in [
+        "k".repeat(7),
+        "k".repeat(64),
+        "0aF9".repeat(16).replace('F', "G"),
+        "pässwort".to_string(),
+    ] {
+        let err = WifiCredential::new("Home", &key).unwrap_err();
+        assert!(matches!(err, Error::Invalid(_)), "{}: {}", key, err);
+    }
+}
+
+#[test]
+fn credential_written_to_a_tag_reads_back() {
+    let credential = WifiCredential::new("Office", "correct horse").unwrap();
+    let tag = SimulatedTag::new(Chip::Ntag213);
+    let message = encode_ndef_message(&[NdefRecord::wifi(&credential)]).unwrap();
+    write_ndef_message(&tag, tag.chip, &message).unwrap();
+
+    let dump = read_dump(&tag, &tag.atr()).unwrap();
+    let records = parse_ndef_message(&ndef_message(&dump).unwrap()).unwrap();
+    assert_eq!(records[0].mime_type(), Some("application/vnd.wfa.wsc"));
+    assert_eq!(records[0].summary(), "Wi-Fi Office (WPA2-Personal)");
+    assert_eq!(
+        WifiCredential::from_record(&records[0]).unwrap(),
+        credential
+    );
+}